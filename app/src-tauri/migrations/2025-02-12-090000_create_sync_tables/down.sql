@@ -0,0 +1,15 @@
+DROP TRIGGER IF EXISTS sync_habits_insert;
+DROP TRIGGER IF EXISTS sync_habits_update;
+DROP TRIGGER IF EXISTS sync_habits_delete;
+DROP TRIGGER IF EXISTS sync_habit_completions_insert;
+DROP TRIGGER IF EXISTS sync_habit_completions_update;
+DROP TRIGGER IF EXISTS sync_habit_completions_delete;
+DROP TRIGGER IF EXISTS sync_pomodoro_cycles_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_cycles_update;
+DROP TRIGGER IF EXISTS sync_pomodoro_cycles_delete;
+DROP TRIGGER IF EXISTS sync_pomodoro_sessions_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_sessions_update;
+DROP TRIGGER IF EXISTS sync_pomodoro_sessions_delete;
+DROP TABLE IF EXISTS sync_batches;
+DROP TABLE IF EXISTS sync_changes;
+DROP TABLE IF EXISTS sync_state;
@@ -0,0 +1,265 @@
+-- Local device identity and sync configuration, never synced itself
+CREATE TABLE sync_state (
+    device_id TEXT PRIMARY KEY NOT NULL,
+    applying_remote BOOLEAN NOT NULL DEFAULT FALSE,
+    backend TEXT,
+    last_synced_at TEXT
+);
+
+INSERT INTO sync_state (device_id) VALUES (lower(hex(randomblob(16))));
+
+-- Append-only log of row mutations, local and applied remote ones
+CREATE TABLE sync_changes (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    change_id TEXT NOT NULL UNIQUE,
+    device_id TEXT NOT NULL,
+    entity TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    operation TEXT NOT NULL, -- INSERT, UPDATE, DELETE
+    data TEXT, -- JSON of the row after the change, NULL for deletes
+    changed_at TEXT NOT NULL,
+    pushed BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_sync_changes_entity ON sync_changes(entity, entity_id);
+CREATE INDEX idx_sync_changes_pushed ON sync_changes(pushed);
+
+-- Batches already pulled from other devices
+CREATE TABLE sync_batches (
+    device_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    pulled_at TEXT NOT NULL,
+    PRIMARY KEY (device_id, name)
+);
+
+-- habits
+CREATE TRIGGER sync_habits_insert AFTER INSERT ON habits
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habits',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'title', NEW.title, 'description', NEW.description, 'icon', NEW.icon, 'is_archived', NEW.is_archived, 'created_at', NEW.created_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habits_update AFTER UPDATE ON habits
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habits',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'title', NEW.title, 'description', NEW.description, 'icon', NEW.icon, 'is_archived', NEW.is_archived, 'created_at', NEW.created_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habits_delete AFTER DELETE ON habits
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habits',
+        OLD.id,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+
+-- habit_completions
+CREATE TRIGGER sync_habit_completions_insert AFTER INSERT ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'habit_id', NEW.habit_id, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habit_completions_update AFTER UPDATE ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'habit_id', NEW.habit_id, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habit_completions_delete AFTER DELETE ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        OLD.id,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+
+-- pomodoro_cycles
+CREATE TRIGGER sync_pomodoro_cycles_insert AFTER INSERT ON pomodoro_cycles
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_cycles',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'status', NEW.status, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_cycles_update AFTER UPDATE ON pomodoro_cycles
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_cycles',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'status', NEW.status, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_cycles_delete AFTER DELETE ON pomodoro_cycles
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_cycles',
+        OLD.id,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+
+-- pomodoro_sessions
+CREATE TRIGGER sync_pomodoro_sessions_insert AFTER INSERT ON pomodoro_sessions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_sessions',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'cycle_id', NEW.cycle_id, 'session_type', NEW.session_type, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'duration_minutes', NEW.duration_minutes, 'was_completed', NEW.was_completed),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_sessions_update AFTER UPDATE ON pomodoro_sessions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_sessions',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'cycle_id', NEW.cycle_id, 'session_type', NEW.session_type, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'duration_minutes', NEW.duration_minutes, 'was_completed', NEW.was_completed),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_sessions_delete AFTER DELETE ON pomodoro_sessions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_sessions',
+        OLD.id,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+-- Existing rows have to reach other devices too
+INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+SELECT
+    lower(hex(randomblob(16))),
+    (SELECT device_id FROM sync_state),
+    'habits',
+    id,
+    'INSERT',
+    json_object('id', habits.id, 'title', habits.title, 'description', habits.description, 'icon', habits.icon, 'is_archived', habits.is_archived, 'created_at', habits.created_at, 'updated_at', habits.updated_at),
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+FROM habits;
+
+INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+SELECT
+    lower(hex(randomblob(16))),
+    (SELECT device_id FROM sync_state),
+    'habit_completions',
+    id,
+    'INSERT',
+    json_object('id', habit_completions.id, 'habit_id', habit_completions.habit_id, 'created_at', habit_completions.created_at),
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+FROM habit_completions;
+
+INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+SELECT
+    lower(hex(randomblob(16))),
+    (SELECT device_id FROM sync_state),
+    'pomodoro_cycles',
+    id,
+    'INSERT',
+    json_object('id', pomodoro_cycles.id, 'status', pomodoro_cycles.status, 'focus_duration', pomodoro_cycles.focus_duration, 'short_break_duration', pomodoro_cycles.short_break_duration, 'long_break_duration', pomodoro_cycles.long_break_duration, 'sessions_until_long_break', pomodoro_cycles.sessions_until_long_break, 'auto_start_breaks', pomodoro_cycles.auto_start_breaks, 'auto_start_pomodoros', pomodoro_cycles.auto_start_pomodoros, 'started_at', pomodoro_cycles.started_at, 'completed_at', pomodoro_cycles.completed_at, 'updated_at', pomodoro_cycles.updated_at),
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+FROM pomodoro_cycles;
+
+INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+SELECT
+    lower(hex(randomblob(16))),
+    (SELECT device_id FROM sync_state),
+    'pomodoro_sessions',
+    id,
+    'INSERT',
+    json_object('id', pomodoro_sessions.id, 'cycle_id', pomodoro_sessions.cycle_id, 'session_type', pomodoro_sessions.session_type, 'started_at', pomodoro_sessions.started_at, 'completed_at', pomodoro_sessions.completed_at, 'duration_minutes', pomodoro_sessions.duration_minutes, 'was_completed', pomodoro_sessions.was_completed),
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+FROM pomodoro_sessions;
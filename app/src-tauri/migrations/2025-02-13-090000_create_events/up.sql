@@ -0,0 +1,23 @@
+CREATE TABLE events (
+    id TEXT PRIMARY KEY NOT NULL,
+    entity TEXT NOT NULL, -- name of the table the mutated row lives in
+    entity_id TEXT NOT NULL,
+    operation TEXT NOT NULL, -- CREATE, UPDATE, DELETE
+    before TEXT, -- JSON of the row before the mutation, NULL for creates
+    after TEXT, -- JSON of the row after the mutation, NULL for deletes
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_events_entity_id ON events(entity_id);
+CREATE INDEX idx_events_created_at ON events(created_at);
+
+-- The history is append-only
+CREATE TRIGGER events_no_update BEFORE UPDATE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
+
+CREATE TRIGGER events_no_delete BEFORE DELETE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
@@ -0,0 +1,6 @@
+-- One row per setting, so keys can be added or retired without a migration
+CREATE TABLE settings (
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL, -- JSON
+    updated_at DATETIME NOT NULL
+);
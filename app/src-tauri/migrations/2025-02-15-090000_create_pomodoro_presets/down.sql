@@ -0,0 +1,39 @@
+DROP TRIGGER IF EXISTS sync_pomodoro_presets_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_presets_update;
+DROP TRIGGER IF EXISTS sync_pomodoro_presets_delete;
+DROP TRIGGER IF EXISTS sync_pomodoro_cycles_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_cycles_update;
+
+CREATE TRIGGER sync_pomodoro_cycles_insert AFTER INSERT ON pomodoro_cycles
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_cycles',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'status', NEW.status, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_cycles_update AFTER UPDATE ON pomodoro_cycles
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_cycles',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'status', NEW.status, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+DROP INDEX IF EXISTS idx_pomodoro_cycles_preset;
+ALTER TABLE pomodoro_cycles DROP COLUMN preset_id;
+DROP TABLE IF EXISTS pomodoro_presets;
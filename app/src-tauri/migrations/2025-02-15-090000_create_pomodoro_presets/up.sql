@@ -0,0 +1,116 @@
+CREATE TABLE pomodoro_presets (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    focus_duration INTEGER NOT NULL, -- in ms, like pomodoro_cycles
+    short_break_duration INTEGER NOT NULL,
+    long_break_duration INTEGER NOT NULL,
+    sessions_until_long_break INTEGER NOT NULL,
+    auto_start_breaks BOOLEAN NOT NULL DEFAULT FALSE,
+    auto_start_pomodoros BOOLEAN NOT NULL DEFAULT FALSE,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+-- Fixed ids, so devices that sync end up with the same built-in presets
+INSERT INTO pomodoro_presets (id, name, focus_duration, short_break_duration, long_break_duration, sessions_until_long_break, auto_start_breaks, auto_start_pomodoros, is_default, created_at, updated_at)
+VALUES
+    ('classic', 'Classic 25/5', 1500000, 300000, 900000, 4, TRUE, TRUE, TRUE, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    ('deep-work', 'Deep work 50/10', 3000000, 600000, 1800000, 2, TRUE, TRUE, FALSE, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    ('study', 'Study 45/15', 2700000, 900000, 1800000, 3, TRUE, TRUE, FALSE, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
+
+-- Cycles started before presets existed keep NULL
+ALTER TABLE pomodoro_cycles ADD COLUMN preset_id TEXT REFERENCES pomodoro_presets(id);
+
+CREATE INDEX idx_pomodoro_cycles_preset ON pomodoro_cycles(preset_id);
+
+-- The sync triggers serialize every column, so they have to include preset_id
+DROP TRIGGER IF EXISTS sync_pomodoro_cycles_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_cycles_update;
+
+CREATE TRIGGER sync_pomodoro_cycles_insert AFTER INSERT ON pomodoro_cycles
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_cycles',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'status', NEW.status, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'updated_at', NEW.updated_at, 'preset_id', NEW.preset_id),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_cycles_update AFTER UPDATE ON pomodoro_cycles
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_cycles',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'status', NEW.status, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'updated_at', NEW.updated_at, 'preset_id', NEW.preset_id),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+-- pomodoro_presets
+CREATE TRIGGER sync_pomodoro_presets_insert AFTER INSERT ON pomodoro_presets
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_presets',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'name', NEW.name, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'is_default', NEW.is_default, 'created_at', NEW.created_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_presets_update AFTER UPDATE ON pomodoro_presets
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_presets',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'name', NEW.name, 'focus_duration', NEW.focus_duration, 'short_break_duration', NEW.short_break_duration, 'long_break_duration', NEW.long_break_duration, 'sessions_until_long_break', NEW.sessions_until_long_break, 'auto_start_breaks', NEW.auto_start_breaks, 'auto_start_pomodoros', NEW.auto_start_pomodoros, 'is_default', NEW.is_default, 'created_at', NEW.created_at, 'updated_at', NEW.updated_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_presets_delete AFTER DELETE ON pomodoro_presets
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_presets',
+        OLD.id,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+SELECT
+    lower(hex(randomblob(16))),
+    (SELECT device_id FROM sync_state),
+    'pomodoro_presets',
+    id,
+    'INSERT',
+    json_object('id', pomodoro_presets.id, 'name', pomodoro_presets.name, 'focus_duration', pomodoro_presets.focus_duration, 'short_break_duration', pomodoro_presets.short_break_duration, 'long_break_duration', pomodoro_presets.long_break_duration, 'sessions_until_long_break', pomodoro_presets.sessions_until_long_break, 'auto_start_breaks', pomodoro_presets.auto_start_breaks, 'auto_start_pomodoros', pomodoro_presets.auto_start_pomodoros, 'is_default', pomodoro_presets.is_default, 'created_at', pomodoro_presets.created_at, 'updated_at', pomodoro_presets.updated_at),
+    strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+FROM pomodoro_presets;
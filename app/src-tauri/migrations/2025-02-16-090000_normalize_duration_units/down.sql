@@ -0,0 +1,49 @@
+UPDATE sync_state SET applying_remote = TRUE;
+
+DROP TRIGGER IF EXISTS sync_pomodoro_sessions_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_sessions_update;
+
+UPDATE pomodoro_sessions SET duration_ms = duration_ms / 60000;
+ALTER TABLE pomodoro_sessions RENAME COLUMN duration_ms TO duration_minutes;
+
+UPDATE sync_changes
+SET data = json_set(
+    json_remove(data, '$.duration_ms'),
+    '$.duration_minutes',
+    json_extract(data, '$.duration_ms') / 60000
+)
+WHERE entity = 'pomodoro_sessions'
+    AND data IS NOT NULL
+    AND json_type(data, '$.duration_ms') IS NOT NULL;
+
+CREATE TRIGGER sync_pomodoro_sessions_insert AFTER INSERT ON pomodoro_sessions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_sessions',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'cycle_id', NEW.cycle_id, 'session_type', NEW.session_type, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'duration_minutes', NEW.duration_minutes, 'was_completed', NEW.was_completed),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_sessions_update AFTER UPDATE ON pomodoro_sessions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_sessions',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'cycle_id', NEW.cycle_id, 'session_type', NEW.session_type, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'duration_minutes', NEW.duration_minutes, 'was_completed', NEW.was_completed),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+UPDATE sync_state SET applying_remote = FALSE;
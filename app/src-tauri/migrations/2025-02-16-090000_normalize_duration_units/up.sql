@@ -0,0 +1,62 @@
+-- All pomodoro durations are stored in milliseconds. Sessions used to store
+-- minutes, and some cycles were started with durations in minutes as well.
+
+-- Every device runs this migration itself, so the rewrites are not synced
+UPDATE sync_state SET applying_remote = TRUE;
+
+DROP TRIGGER IF EXISTS sync_pomodoro_sessions_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_sessions_update;
+
+ALTER TABLE pomodoro_sessions RENAME COLUMN duration_minutes TO duration_ms;
+UPDATE pomodoro_sessions SET duration_ms = duration_ms * 60000;
+
+-- No real phase is shorter than a second, so small values are minutes
+UPDATE pomodoro_cycles SET focus_duration = focus_duration * 60000
+WHERE focus_duration BETWEEN 1 AND 999;
+UPDATE pomodoro_cycles SET short_break_duration = short_break_duration * 60000
+WHERE short_break_duration BETWEEN 1 AND 999;
+UPDATE pomodoro_cycles SET long_break_duration = long_break_duration * 60000
+WHERE long_break_duration BETWEEN 1 AND 999;
+
+-- Logged changes that haven't been pushed yet use the new shape too
+UPDATE sync_changes
+SET data = json_set(
+    json_remove(data, '$.duration_minutes'),
+    '$.duration_ms',
+    json_extract(data, '$.duration_minutes') * 60000
+)
+WHERE entity = 'pomodoro_sessions'
+    AND data IS NOT NULL
+    AND json_type(data, '$.duration_minutes') IS NOT NULL;
+
+CREATE TRIGGER sync_pomodoro_sessions_insert AFTER INSERT ON pomodoro_sessions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_sessions',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'cycle_id', NEW.cycle_id, 'session_type', NEW.session_type, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'duration_ms', NEW.duration_ms, 'was_completed', NEW.was_completed),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_sessions_update AFTER UPDATE ON pomodoro_sessions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_sessions',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'cycle_id', NEW.cycle_id, 'session_type', NEW.session_type, 'started_at', NEW.started_at, 'completed_at', NEW.completed_at, 'duration_ms', NEW.duration_ms, 'was_completed', NEW.was_completed),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+UPDATE sync_state SET applying_remote = FALSE;
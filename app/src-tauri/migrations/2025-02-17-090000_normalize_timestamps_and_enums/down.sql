@@ -0,0 +1,2 @@
+-- The normalized values are valid in the previous formats as well, and the
+-- original ones can't be recovered, so there is nothing to revert.
@@ -0,0 +1,54 @@
+-- Timestamps are stored the way Diesel writes a DateTime<Utc>: in UTC with
+-- millisecond precision, e.g. '2025-02-16 09:30:00.250+00:00', and without the
+-- fraction on whole seconds. Until now rows held RFC 3339 strings, SQLite's
+-- CURRENT_TIMESTAMP or bare dates, which don't compare correctly as text.
+-- Values SQLite can't parse at all fall back to the time of the migration.
+
+-- Every device runs this migration itself, so the rewrites are not synced
+UPDATE sync_state SET applying_remote = TRUE;
+
+UPDATE habits SET
+    created_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', created_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now')),
+    updated_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', updated_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now'));
+
+UPDATE pomodoro_cycles SET
+    started_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', started_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now')),
+    completed_at = replace(strftime('%Y-%m-%d %H:%M:%f', completed_at), '.000', '') || '+00:00',
+    updated_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', updated_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now'));
+
+UPDATE pomodoro_presets SET
+    created_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', created_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now')),
+    updated_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', updated_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now'));
+
+UPDATE pomodoro_sessions SET
+    started_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', started_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now')),
+    completed_at = replace(strftime('%Y-%m-%d %H:%M:%f', completed_at), '.000', '') || '+00:00';
+
+-- Only the format of the history changes, not what it records
+DROP TRIGGER events_no_update;
+
+UPDATE events SET
+    created_at = coalesce(replace(strftime('%Y-%m-%d %H:%M:%f', created_at), '.000', '') || '+00:00', strftime('%Y-%m-%d %H:%M:%S+00:00', 'now'));
+
+CREATE TRIGGER events_no_update BEFORE UPDATE ON events
+BEGIN
+    SELECT RAISE(ABORT, 'events are append-only');
+END;
+
+-- Completions only record the calendar day. Rows written by SQLite defaults
+-- carry a time as well, only the leading date counts.
+DELETE FROM habit_completions WHERE date(substr(created_at, 1, 10)) IS NULL;
+UPDATE habit_completions SET created_at = date(substr(created_at, 1, 10));
+
+-- Enums are stored by their upper case names
+UPDATE pomodoro_cycles SET status = upper(replace(replace(trim(status), ' ', '_'), '-', '_'));
+-- An unknown status was never picked up as the current cycle
+UPDATE pomodoro_cycles SET status = 'ABANDONED'
+WHERE status NOT IN ('IN_PROGRESS', 'COMPLETED', 'ABANDONED');
+
+UPDATE pomodoro_sessions
+SET session_type = upper(replace(replace(trim(session_type), ' ', '_'), '-', '_'));
+UPDATE pomodoro_sessions SET session_type = 'FOCUS'
+WHERE session_type NOT IN ('FOCUS', 'SHORT_BREAK', 'LONG_BREAK');
+
+UPDATE sync_state SET applying_remote = FALSE;
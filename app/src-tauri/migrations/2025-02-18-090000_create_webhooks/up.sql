@@ -0,0 +1,27 @@
+-- Webhooks stay on this device: there are no sync triggers on these tables,
+-- so secrets are never pushed and every device only delivers its own events.
+CREATE TABLE webhooks (
+    id TEXT PRIMARY KEY NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL, -- HMAC-SHA256 key for the signature header
+    event_types TEXT NOT NULL, -- JSON array of event types
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+-- Payloads waiting to be delivered, written in the same transaction as the
+-- change that caused them so none are lost when the app quits
+CREATE TABLE webhook_deliveries (
+    id TEXT PRIMARY KEY NOT NULL,
+    webhook_id TEXT NOT NULL REFERENCES webhooks(id),
+    event_type TEXT NOT NULL,
+    payload TEXT NOT NULL, -- JSON, sent as is
+    status TEXT NOT NULL, -- PENDING, DELIVERED or FAILED
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME NOT NULL,
+    last_error TEXT,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX webhook_deliveries_due ON webhook_deliveries (status, next_attempt_at);
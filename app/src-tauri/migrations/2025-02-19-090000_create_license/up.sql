@@ -0,0 +1,13 @@
+-- The license activation of this device, at most one row. Like webhooks it
+-- has no sync triggers: every device activates its own instance
+CREATE TABLE license (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    license_key TEXT NOT NULL,
+    instance_id TEXT NOT NULL,
+    instance_name TEXT NOT NULL,
+    status TEXT NOT NULL, -- INACTIVE, ACTIVE, EXPIRED or DISABLED
+    expires_at DATETIME,
+    activated_at DATETIME NOT NULL,
+    validated_at DATETIME NOT NULL, -- last time the provider confirmed the status
+    updated_at DATETIME NOT NULL
+);
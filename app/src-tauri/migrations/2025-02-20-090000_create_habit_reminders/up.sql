@@ -0,0 +1,15 @@
+-- Reminders are about this device's notifications, so like webhooks they
+-- have no sync triggers. They are kept when their habit is deleted, which
+-- undo can bring back, and ignored while the habit is missing or archived.
+CREATE TABLE habit_reminders (
+    id TEXT PRIMARY KEY NOT NULL,
+    habit_id TEXT NOT NULL REFERENCES habits(id),
+    time TEXT NOT NULL, -- local time of day, HH:MM
+    weekdays TEXT NOT NULL, -- JSON array of weekdays
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_fired_at DATETIME,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+CREATE INDEX habit_reminders_habit_id ON habit_reminders (habit_id);
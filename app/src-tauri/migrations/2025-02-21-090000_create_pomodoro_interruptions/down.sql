@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS sync_pomodoro_interruptions_insert;
+DROP TRIGGER IF EXISTS sync_pomodoro_interruptions_update;
+DROP TRIGGER IF EXISTS sync_pomodoro_interruptions_delete;
+DROP INDEX IF EXISTS pomodoro_interruptions_session_id;
+DROP TABLE IF EXISTS pomodoro_interruptions;
@@ -0,0 +1,58 @@
+-- Idle gaps detected during focus sessions. They are synced along with the
+-- sessions, since discarded idle time is taken out of their focus time.
+CREATE TABLE pomodoro_interruptions (
+    id TEXT PRIMARY KEY NOT NULL,
+    session_id TEXT NOT NULL REFERENCES pomodoro_sessions(id),
+    started_at DATETIME NOT NULL, -- last input before the gap
+    ended_at DATETIME, -- first input after it, NULL while still away
+    counted_ms INTEGER NOT NULL, -- part of the gap the timer ran before pausing
+    resolution TEXT, -- KEEP or DISCARD, NULL until the user decides
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX pomodoro_interruptions_session_id ON pomodoro_interruptions (session_id);
+
+CREATE TRIGGER sync_pomodoro_interruptions_insert AFTER INSERT ON pomodoro_interruptions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_interruptions',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'session_id', NEW.session_id, 'started_at', NEW.started_at, 'ended_at', NEW.ended_at, 'counted_ms', NEW.counted_ms, 'resolution', NEW.resolution, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_interruptions_update AFTER UPDATE ON pomodoro_interruptions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_interruptions',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'session_id', NEW.session_id, 'started_at', NEW.started_at, 'ended_at', NEW.ended_at, 'counted_ms', NEW.counted_ms, 'resolution', NEW.resolution, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_pomodoro_interruptions_delete AFTER DELETE ON pomodoro_interruptions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'pomodoro_interruptions',
+        OLD.id,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
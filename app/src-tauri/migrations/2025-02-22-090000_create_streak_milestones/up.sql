@@ -0,0 +1,10 @@
+-- Streak milestones that already fired STREAK_MILESTONE, so unmarking and
+-- marking a day again doesn't fire it twice. A streak is identified by the
+-- day it started. Like webhooks, they have no sync triggers.
+CREATE TABLE streak_milestones (
+    habit_id TEXT NOT NULL,
+    started_on DATE NOT NULL,
+    streak INTEGER NOT NULL,
+    created_at DATETIME NOT NULL,
+    PRIMARY KEY (habit_id, started_on, streak)
+);
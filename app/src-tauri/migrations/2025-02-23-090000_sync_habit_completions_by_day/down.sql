@@ -0,0 +1,51 @@
+-- Logged changes keep their `<habit_id>/<day>` ids
+DROP TRIGGER sync_habit_completions_insert;
+DROP TRIGGER sync_habit_completions_update;
+DROP TRIGGER sync_habit_completions_delete;
+
+DROP INDEX idx_habit_completions_day;
+
+CREATE TRIGGER sync_habit_completions_insert AFTER INSERT ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        NEW.id,
+        'INSERT',
+        json_object('id', NEW.id, 'habit_id', NEW.habit_id, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habit_completions_update AFTER UPDATE ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        NEW.id,
+        'UPDATE',
+        json_object('id', NEW.id, 'habit_id', NEW.habit_id, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habit_completions_delete AFTER DELETE ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        OLD.id,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
@@ -0,0 +1,81 @@
+-- A habit is completed at most once a day. Devices that ticked the same day
+-- before syncing each kept a row, only the oldest one is left. The cleanup
+-- is not logged, every device runs it itself.
+UPDATE sync_state SET applying_remote = TRUE;
+
+DELETE FROM habit_completions
+WHERE rowid NOT IN (
+    SELECT min(rowid) FROM habit_completions GROUP BY habit_id, created_at
+);
+
+UPDATE sync_state SET applying_remote = FALSE;
+
+CREATE UNIQUE INDEX idx_habit_completions_day ON habit_completions(habit_id, created_at);
+
+-- Completions are synced per habit and day instead of per row, so changes
+-- are logged under `<habit_id>/<day>`. Deletes carry no row, they take the
+-- key of the last write to the same row.
+UPDATE sync_changes AS deleted
+SET entity_id = coalesce((
+    SELECT json_extract(written.data, '$.habit_id') || '/' || json_extract(written.data, '$.created_at')
+    FROM sync_changes AS written
+    WHERE written.entity = 'habit_completions'
+        AND written.entity_id = deleted.entity_id
+        AND written.data IS NOT NULL
+    ORDER BY written.seq DESC
+    LIMIT 1
+), deleted.entity_id)
+WHERE deleted.entity = 'habit_completions' AND deleted.data IS NULL;
+
+UPDATE sync_changes
+SET entity_id = json_extract(data, '$.habit_id') || '/' || json_extract(data, '$.created_at')
+WHERE entity = 'habit_completions' AND data IS NOT NULL;
+
+DROP TRIGGER sync_habit_completions_insert;
+DROP TRIGGER sync_habit_completions_update;
+DROP TRIGGER sync_habit_completions_delete;
+
+CREATE TRIGGER sync_habit_completions_insert AFTER INSERT ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        NEW.habit_id || '/' || NEW.created_at,
+        'INSERT',
+        json_object('id', NEW.id, 'habit_id', NEW.habit_id, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habit_completions_update AFTER UPDATE ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        NEW.habit_id || '/' || NEW.created_at,
+        'UPDATE',
+        json_object('id', NEW.id, 'habit_id', NEW.habit_id, 'created_at', NEW.created_at),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
+
+CREATE TRIGGER sync_habit_completions_delete AFTER DELETE ON habit_completions
+WHEN (SELECT applying_remote FROM sync_state) = 0
+BEGIN
+    INSERT INTO sync_changes (change_id, device_id, entity, entity_id, operation, data, changed_at)
+    VALUES (
+        lower(hex(randomblob(16))),
+        (SELECT device_id FROM sync_state),
+        'habit_completions',
+        OLD.habit_id || '/' || OLD.created_at,
+        'DELETE',
+        NULL,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    );
+END;
@@ -0,0 +1,700 @@
+//! Opt-in HTTP API on a loopback address, so scripts and automation tools can
+//! call the same procedures as the frontend.
+//!
+//! Every procedure of `Api` is exposed as `POST /api/<procedure>`. The body is
+//! a JSON object keyed by the procedure's parameter names, like the args map
+//! in bindings.ts, and the response is its return value as JSON. Requests need
+//! `Authorization: Bearer <token>`. `GET /openapi.json` describes all of it,
+//! with schemas generated from the same types as bindings.ts.
+
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use specta::datatype::{
+    DataType, EnumRepr, EnumType, EnumVariants, Field, LiteralType, NamedFields, PrimitiveType,
+    StructFields,
+};
+use specta::TypeCollection;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use crate::error::AppError;
+use crate::license::LicenseErrorReason;
+use crate::repository::{
+    CompleteSessionArgs, CreateHabitArgs, CreateHabitCompletionArgs, CreatePresetArgs,
+    CreateSessionArgs, GetHabitCompletionsArgs, StartCycleArgs, UpdateCycleArgs, UpdateHabitArgs,
+    UpdatePresetArgs,
+};
+use crate::settings::{self, AutomationSettings};
+use crate::{
+    backup, idle, license, reminders, shortcuts, sync, webhooks, Api, ApiImpl, EncryptionStatus,
+    ExportArgs, ImportArgs,
+};
+
+const TOKEN_LENGTH: usize = 32;
+/// A restarted server may have to wait for the previous one to release the port.
+const BIND_ATTEMPTS: u32 = 10;
+const BIND_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// A new random token for `AutomationSettings::token`.
+pub fn generate_token() -> String {
+    let mut token = [0u8; TOKEN_LENGTH];
+    OsRng.fill_bytes(&mut token);
+    hex::encode(token)
+}
+
+struct Running {
+    settings: AutomationSettings,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// Handle to the server, which runs on the async runtime while enabled.
+#[derive(Default)]
+pub struct AutomationServer {
+    running: Mutex<Option<Running>>,
+}
+
+impl AutomationServer {
+    /// Starts, restarts or stops the server so that it matches `settings`.
+    pub fn apply(&self, settings: &AutomationSettings, api: ApiImpl) {
+        let mut running = self.running.lock().unwrap();
+
+        if running.as_ref().map(|running| &running.settings) == Some(settings) {
+            return;
+        }
+        if let Some(previous) = running.take() {
+            let _ = previous.shutdown.send(());
+        }
+
+        let Some(token) = settings.token.clone().filter(|_| settings.enabled) else {
+            return;
+        };
+
+        // Settings are validated on save, this guards against older rows
+        let address = match settings.bind_address.parse::<IpAddr>() {
+            Ok(address) if address.is_loopback() => SocketAddr::new(address, settings.port),
+            _ => {
+                println!(
+                    "Refusing to serve the automation API on {}",
+                    settings.bind_address
+                );
+                return;
+            }
+        };
+
+        let (shutdown, shutdown_signal) = oneshot::channel();
+        tauri::async_runtime::spawn(serve(address, token, api, shutdown_signal));
+
+        *running = Some(Running {
+            settings: settings.clone(),
+            shutdown,
+        });
+    }
+}
+
+#[derive(Clone)]
+struct ServerState {
+    api: ApiImpl,
+    token: Arc<str>,
+}
+
+async fn serve(
+    address: SocketAddr,
+    token: String,
+    api: ApiImpl,
+    shutdown_signal: oneshot::Receiver<()>,
+) {
+    let Some(listener) = bind(address).await else {
+        return;
+    };
+
+    let router = Router::new()
+        .route("/openapi.json", get(openapi))
+        .route("/api/{procedure}", post(call))
+        .with_state(ServerState {
+            api,
+            token: token.into(),
+        });
+
+    println!("Serving the automation API on http://{}", address);
+
+    let result = axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            let _ = shutdown_signal.await;
+        })
+        .await;
+
+    if let Err(e) = result {
+        println!("Automation API stopped: {}", e);
+    }
+}
+
+async fn bind(address: SocketAddr) -> Option<TcpListener> {
+    let mut attempt = 1;
+    loop {
+        match TcpListener::bind(address).await {
+            Ok(listener) => return Some(listener),
+            Err(e) if attempt >= BIND_ATTEMPTS => {
+                println!("Failed to serve the automation API on {}: {}", address, e);
+                return None;
+            }
+            Err(_) => {
+                attempt += 1;
+                tokio::time::sleep(BIND_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+async fn call(
+    State(state): State<ServerState>,
+    Path(procedure): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !is_authorized(&headers, &state.token) {
+        return error(
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid bearer token".into(),
+        );
+    }
+
+    let params = if body.is_empty() {
+        Map::new()
+    } else {
+        match serde_json::from_slice::<Map<String, Value>>(&body) {
+            Ok(params) => params,
+            Err(e) => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    format!("Body must be a JSON object: {}", e).into(),
+                )
+            }
+        }
+    };
+
+    dispatch(state.api, &procedure, params).await
+}
+
+fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    let Some(given) = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    // Compared in constant time, so the token can't be guessed byte by byte
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn error(status: StatusCode, error: AppError) -> Response {
+    (status, Json(error)).into_response()
+}
+
+/// Takes the parameter `name` out of the request body.
+fn param<T: serde::de::DeserializeOwned>(
+    params: &mut Map<String, Value>,
+    name: &str,
+) -> Result<T, AppError> {
+    let value = params.remove(name).unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| format!("Invalid argument {}: {}", name, e).into())
+}
+
+// --------------------------------------------------------------------------
+// Responses
+// --------------------------------------------------------------------------
+
+/// Turns the return value of a procedure into a response.
+trait IntoReply {
+    /// What the response body holds on success.
+    type Value: specta::Type;
+
+    fn into_reply(self) -> Response;
+}
+
+impl<T: Serialize + specta::Type> IntoReply for Result<T, AppError> {
+    type Value = T;
+
+    fn into_reply(self) -> Response {
+        match self {
+            Ok(value) => Json(value).into_response(),
+            Err(e @ AppError::Validation { .. }) => error(StatusCode::UNPROCESSABLE_ENTITY, e),
+            Err(
+                e @ AppError::License {
+                    reason: LicenseErrorReason::Unreachable | LicenseErrorReason::InvalidResponse,
+                    ..
+                },
+            ) => error(StatusCode::BAD_GATEWAY, e),
+            Err(
+                e @ AppError::License {
+                    reason: LicenseErrorReason::NotConfigured,
+                    ..
+                },
+            ) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+            Err(e @ AppError::License { .. }) => error(StatusCode::CONFLICT, e),
+            Err(e @ AppError::LicenseRequired { .. }) => error(StatusCode::FORBIDDEN, e),
+            Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        }
+    }
+}
+
+macro_rules! infallible_replies {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl IntoReply for $ty {
+                type Value = Self;
+
+                fn into_reply(self) -> Response {
+                    Ok::<_, AppError>(self).into_reply()
+                }
+            }
+        )*
+    };
+}
+
+infallible_replies!(
+    EncryptionStatus,
+    backup::BackupRetention,
+    Vec<shortcuts::ShortcutError>,
+);
+
+// --------------------------------------------------------------------------
+// Procedures
+// --------------------------------------------------------------------------
+
+/// Describes a type for the OpenAPI document.
+type Describe = fn(&mut TypeCollection) -> DataType;
+
+struct Procedure {
+    name: &'static str,
+    params: &'static [(&'static str, Describe)],
+    reply: Describe,
+}
+
+fn describe<T: specta::Type>(types: &mut TypeCollection) -> DataType {
+    T::reference(types, &[]).inner
+}
+
+/// Describes what `procedure` replies with. The procedure is never called,
+/// it only names the type.
+fn describe_reply<A, F>(
+    _procedure: impl FnOnce(ApiImpl, A) -> F,
+    types: &mut TypeCollection,
+) -> DataType
+where
+    F: Future,
+    F::Output: IntoReply,
+{
+    describe::<<F::Output as IntoReply>::Value>(types)
+}
+
+/// Declares the exposed procedures once, for both dispatching and the
+/// OpenAPI document. Must be kept in sync with `Api`.
+macro_rules! procedures {
+    ($($procedure:ident($($param:ident: $ty:ty),*);)*) => {
+        const PROCEDURES: &[Procedure] = &[
+            $(Procedure {
+                name: stringify!($procedure),
+                params: &[$((stringify!($param), describe::<$ty> as Describe)),*],
+                reply: |types| {
+                    describe_reply(
+                        |api, ($($param,)*): ($($ty,)*)| api.$procedure($($param),*),
+                        types,
+                    )
+                },
+            },)*
+        ];
+
+        #[allow(unused_mut)]
+        async fn dispatch(api: ApiImpl, procedure: &str, mut params: Map<String, Value>) -> Response {
+            match procedure {
+                $(stringify!($procedure) => {
+                    $(
+                        let $param: $ty = match param(&mut params, stringify!($param)) {
+                            Ok(value) => value,
+                            Err(e) => return error(StatusCode::BAD_REQUEST, e),
+                        };
+                    )*
+                    api.$procedure($($param),*).await.into_reply()
+                })*
+                _ => error(
+                    StatusCode::NOT_FOUND,
+                    format!("Unknown procedure: {}", procedure).into(),
+                ),
+            }
+        }
+    };
+}
+
+procedures! {
+    get_license();
+    get_license_status();
+    activate_license_key(args: license::ActivateLicenseKeyArgs);
+    validate_license_key();
+    deactivate_license_key();
+    get_entitlements();
+    create_habit(args: CreateHabitArgs);
+    get_active_habits();
+    get_archived_habits();
+    update_habit(args: UpdateHabitArgs);
+    delete_habit(habit_id: String);
+    toggle_habit_completion(args: CreateHabitCompletionArgs);
+    get_habit_completions(args: GetHabitCompletionsArgs);
+    get_habit_completion_streak(habit_id: String);
+    start_pomodoro_cycle(args: StartCycleArgs);
+    get_current_cycle();
+    update_cycle_status(args: UpdateCycleArgs);
+    start_session(args: CreateSessionArgs);
+    complete_session(args: CompleteSessionArgs);
+    get_daily_stats(date: String);
+    get_presets();
+    create_preset(args: CreatePresetArgs);
+    update_preset(args: UpdatePresetArgs);
+    delete_preset(preset_id: String);
+    set_default_preset(preset_id: String);
+    export_habit_completions(args: ExportArgs);
+    export_pomodoro_sessions(args: ExportArgs);
+    preview_import(args: ImportArgs);
+    import_habits(args: ImportArgs);
+    list_backups();
+    restore_backup(file_name: String);
+    get_backup_retention();
+    update_backup_retention(retention: backup::BackupRetention);
+    get_encryption_status();
+    unlock_database(passphrase: String);
+    enable_encryption(passphrase: String);
+    get_settings();
+    update_settings(new_settings: settings::Settings);
+    regenerate_automation_token();
+    get_shortcut_errors();
+    get_history(entity_id: String);
+    undo();
+    redo();
+    get_sync_config();
+    update_sync_config(config: Option<sync::SyncBackendConfig>);
+    sync_now();
+    get_webhooks();
+    create_webhook(args: webhooks::CreateWebhookArgs);
+    update_webhook(args: webhooks::UpdateWebhookArgs);
+    delete_webhook(webhook_id: String);
+    get_webhook_deliveries(webhook_id: String);
+    test_webhook(webhook_id: String);
+    get_habit_reminders(habit_id: String);
+    create_habit_reminder(args: reminders::CreateHabitReminderArgs);
+    update_habit_reminder(args: reminders::UpdateHabitReminderArgs);
+    delete_habit_reminder(reminder_id: String);
+    get_unresolved_interruptions();
+    resolve_interruption(args: idle::ResolveInterruptionArgs);
+}
+
+// --------------------------------------------------------------------------
+// OpenAPI
+// --------------------------------------------------------------------------
+
+async fn openapi() -> Json<Value> {
+    Json(openapi_document())
+}
+
+fn openapi_document() -> Value {
+    let mut types = TypeCollection::default();
+    let mut paths = Map::new();
+
+    for procedure in PROCEDURES {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for (name, describe) in procedure.params {
+            let data_type = describe(&mut types);
+            // Missing parameters are passed as `null`
+            if !matches!(data_type, DataType::Nullable(_)) {
+                required.push(*name);
+            }
+            properties.insert(name.to_string(), schema(&data_type));
+        }
+        let reply = schema(&(procedure.reply)(&mut types));
+
+        paths.insert(
+            format!("/api/{}", procedure.name),
+            json!({
+                "post": {
+                    "operationId": procedure.name,
+                    "requestBody": {
+                        "required": !required.is_empty(),
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "properties": properties,
+                                    "required": required,
+                                },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The return value of the procedure",
+                            "content": { "application/json": { "schema": reply } },
+                        },
+                        "400": { "$ref": "#/components/responses/Error" },
+                        "401": { "$ref": "#/components/responses/Error" },
+                        "403": { "$ref": "#/components/responses/Error" },
+                        "404": { "$ref": "#/components/responses/Error" },
+                        "409": { "$ref": "#/components/responses/Error" },
+                        "422": { "$ref": "#/components/responses/Error" },
+                        "500": { "$ref": "#/components/responses/Error" },
+                        "502": { "$ref": "#/components/responses/Error" },
+                    },
+                },
+            }),
+        );
+    }
+
+    let error = describe::<AppError>(&mut types);
+    // Named types are referenced by the schemas above and defined once here
+    let mut schemas = Map::new();
+    for (_, named) in &types {
+        schemas.insert(named.name().to_string(), schema(&named.inner));
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "AchieveAlchemy automation API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "The procedures of the app. Types have the same \
+                            names as in src/bindings.ts.",
+        },
+        "security": [{ "bearer": [] }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" },
+            },
+            "responses": {
+                "Error": {
+                    "description": "An AppError",
+                    "content": { "application/json": { "schema": schema(&error) } },
+                },
+            },
+            "schemas": schemas,
+        },
+    })
+}
+
+// --------------------------------------------------------------------------
+// Schemas
+// --------------------------------------------------------------------------
+
+/// OpenAPI schema of a type as specta describes it, following its serde
+/// representation like the TypeScript exporter does.
+fn schema(data_type: &DataType) -> Value {
+    match data_type {
+        DataType::Any | DataType::Unknown | DataType::Generic(_) => json!({}),
+        DataType::Primitive(primitive) => primitive_schema(primitive),
+        DataType::Literal(literal) => literal_schema(literal),
+        DataType::List(list) => json!({ "type": "array", "items": schema(list.ty()) }),
+        DataType::Map(map) => json!({
+            "type": "object",
+            "additionalProperties": schema(map.value_ty()),
+        }),
+        DataType::Nullable(inner) => nullable(schema(inner)),
+        DataType::Struct(struct_type) => match struct_type.fields() {
+            StructFields::Unit => null_schema(),
+            StructFields::Unnamed(fields) => unnamed_schema(fields.fields()),
+            StructFields::Named(fields) => named_schema(fields),
+        },
+        DataType::Enum(enum_type) => enum_schema(enum_type),
+        DataType::Tuple(tuple) => match tuple.elements().as_slice() {
+            [] => null_schema(),
+            elements => tuple_schema(elements.iter().map(schema).collect()),
+        },
+        DataType::Reference(reference) => {
+            json!({ "$ref": format!("#/components/schemas/{}", reference.name()) })
+        }
+    }
+}
+
+fn primitive_schema(primitive: &PrimitiveType) -> Value {
+    use PrimitiveType::*;
+
+    match primitive {
+        i8 | i16 | i32 | u8 | u16 => json!({ "type": "integer", "format": "int32" }),
+        i64 | i128 | isize | u32 | u64 | u128 | usize => {
+            json!({ "type": "integer", "format": "int64" })
+        }
+        f32 => json!({ "type": "number", "format": "float" }),
+        f64 => json!({ "type": "number", "format": "double" }),
+        bool => json!({ "type": "boolean" }),
+        char | String => json!({ "type": "string" }),
+    }
+}
+
+fn literal_schema(literal: &LiteralType) -> Value {
+    let (kind, value) = match literal {
+        LiteralType::i8(value) => ("integer", json!(value)),
+        LiteralType::i16(value) => ("integer", json!(value)),
+        LiteralType::i32(value) => ("integer", json!(value)),
+        LiteralType::u8(value) => ("integer", json!(value)),
+        LiteralType::u16(value) => ("integer", json!(value)),
+        LiteralType::u32(value) => ("integer", json!(value)),
+        LiteralType::f32(value) => ("number", json!(value)),
+        LiteralType::f64(value) => ("number", json!(value)),
+        LiteralType::bool(value) => ("boolean", json!(value)),
+        LiteralType::String(value) => ("string", json!(value)),
+        LiteralType::char(value) => ("string", json!(value)),
+        _ => return null_schema(),
+    };
+
+    json!({ "type": kind, "enum": [value] })
+}
+
+/// OpenAPI 3.0 has no `null` type, only nullable schemas.
+fn null_schema() -> Value {
+    json!({ "nullable": true, "enum": [null] })
+}
+
+fn nullable(schema: Value) -> Value {
+    match schema {
+        // Siblings of `$ref` are ignored
+        Value::Object(mut schema) if !schema.contains_key("$ref") => {
+            schema.insert("nullable".to_string(), true.into());
+            Value::Object(schema)
+        }
+        schema => json!({ "allOf": [schema], "nullable": true }),
+    }
+}
+
+/// OpenAPI 3.0 can't describe the items of a tuple one by one.
+fn tuple_schema(items: Vec<Value>) -> Value {
+    json!({
+        "type": "array",
+        "items": { "oneOf": items },
+        "minItems": items.len(),
+        "maxItems": items.len(),
+    })
+}
+
+/// Newtypes are represented by their only field, others as tuples.
+fn unnamed_schema(fields: &[Field]) -> Value {
+    let mut items: Vec<Value> = fields
+        .iter()
+        .filter_map(|field| field.ty().map(schema))
+        .collect();
+
+    match items.len() {
+        1 => items.remove(0),
+        _ => tuple_schema(items),
+    }
+}
+
+fn named_schema(fields: &NamedFields) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut flattened = Vec::new();
+
+    for (name, field) in fields.fields() {
+        // Skipped fields have no type
+        let Some(data_type) = field.ty() else {
+            continue;
+        };
+        if field.flatten() {
+            flattened.push(schema(data_type));
+            continue;
+        }
+        if !field.optional() && !matches!(data_type, DataType::Nullable(_)) {
+            required.push(name.to_string());
+        }
+        properties.insert(name.to_string(), schema(data_type));
+    }
+
+    let object = json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    });
+    if flattened.is_empty() {
+        return object;
+    }
+
+    flattened.insert(0, object);
+    json!({ "allOf": flattened })
+}
+
+fn enum_schema(enum_type: &EnumType) -> Value {
+    let variants: Vec<_> = enum_type
+        .variants()
+        .iter()
+        .filter(|(_, variant)| !variant.skip())
+        .map(|(name, variant)| (name.as_ref(), variant.inner()))
+        .collect();
+
+    // Like most enums of the app, e.g. `"FOCUS" | "SHORT_BREAK"`
+    let is_unit = variants
+        .iter()
+        .all(|(_, inner)| matches!(inner, EnumVariants::Unit));
+    if is_unit && *enum_type.repr() == EnumRepr::External {
+        let names: Vec<_> = variants.iter().map(|(name, _)| *name).collect();
+        return json!({ "type": "string", "enum": names });
+    }
+
+    let tagged = |tag: &str, name: &str, content: Option<(&str, Value)>| {
+        let mut properties = Map::new();
+        properties.insert(tag.to_string(), json!({ "type": "string", "enum": [name] }));
+        let mut required = vec![tag.to_string()];
+        if let Some((key, content)) = content {
+            properties.insert(key.to_string(), content);
+            required.push(key.to_string());
+        }
+        json!({ "type": "object", "properties": properties, "required": required })
+    };
+
+    let one_of: Vec<Value> = variants
+        .into_iter()
+        .map(|(name, inner)| {
+            let content = match inner {
+                EnumVariants::Unit => None,
+                EnumVariants::Named(fields) => Some(named_schema(fields)),
+                EnumVariants::Unnamed(fields) => Some(unnamed_schema(fields.fields())),
+            };
+
+            match (enum_type.repr(), content) {
+                (EnumRepr::Untagged, content) => content.unwrap_or_else(null_schema),
+                (EnumRepr::External, None) => json!({ "type": "string", "enum": [name] }),
+                (EnumRepr::External, Some(content)) => json!({
+                    "type": "object",
+                    "properties": { name: content },
+                    "required": [name],
+                }),
+                (EnumRepr::Internal { tag }, None) => tagged(tag, name, None),
+                // The tag is added to the fields of the variant
+                (EnumRepr::Internal { tag }, Some(content)) => json!({
+                    "allOf": [tagged(tag, name, None), content],
+                }),
+                (EnumRepr::Adjacent { tag, content: key }, content) => {
+                    tagged(tag, name, content.map(|content| (key.as_ref(), content)))
+                }
+            }
+        })
+        .collect();
+
+    json!({ "oneOf": one_of })
+}
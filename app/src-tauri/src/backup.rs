@@ -0,0 +1,305 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use rusqlite::{backup::Backup, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+use crate::encryption::DatabaseKey;
+use crate::error::AppError;
+use crate::validation::{Validate, Validator};
+
+const BACKUP_DIRECTORY: &str = "backups";
+const RETENTION_FILE: &str = "retention.json";
+// Milliseconds keep two snapshots taken in the same second apart, names
+// without them are still read.
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.3f";
+const PARSE_TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.f";
+const PAGES_PER_STEP: i32 = 100;
+const MAX_RETAINED_BACKUPS: i32 = 365;
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BackupKind {
+    Daily,
+    PreMigration,
+    PreRestore,
+}
+
+impl BackupKind {
+    fn to_str(self) -> &'static str {
+        match self {
+            BackupKind::Daily => "daily",
+            BackupKind::PreMigration => "pre-migration",
+            BackupKind::PreRestore => "pre-restore",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "daily" => Some(BackupKind::Daily),
+            "pre-migration" => Some(BackupKind::PreMigration),
+            "pre-restore" => Some(BackupKind::PreRestore),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub kind: BackupKind,
+    pub created_at: String,
+    /// A float, so the bindings can use a plain `number` beyond 4 GiB.
+    pub size_bytes: f64,
+}
+
+/// How many snapshots of each kind are kept before the oldest are deleted.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRetention {
+    pub daily: u32,
+    pub pre_migration: u32,
+    pub pre_restore: u32,
+}
+
+impl Default for BackupRetention {
+    fn default() -> Self {
+        BackupRetention {
+            daily: 14,
+            pre_migration: 5,
+            pre_restore: 5,
+        }
+    }
+}
+
+impl Validate for BackupRetention {
+    fn validate(&self) -> Result<(), AppError> {
+        // Keeping none would delete a snapshot right after taking it
+        let count = |limit: u32| i32::try_from(limit).unwrap_or(i32::MAX);
+        Validator::new()
+            .range("daily", count(self.daily), 1, MAX_RETAINED_BACKUPS)
+            .range(
+                "preMigration",
+                count(self.pre_migration),
+                1,
+                MAX_RETAINED_BACKUPS,
+            )
+            .range(
+                "preRestore",
+                count(self.pre_restore),
+                1,
+                MAX_RETAINED_BACKUPS,
+            )
+            .finish()
+    }
+}
+
+impl BackupRetention {
+    fn limit(&self, kind: BackupKind) -> u32 {
+        match kind {
+            BackupKind::Daily => self.daily,
+            BackupKind::PreMigration => self.pre_migration,
+            BackupKind::PreRestore => self.pre_restore,
+        }
+    }
+}
+
+pub fn backup_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(BACKUP_DIRECTORY)
+}
+
+/// Copies the database at `db_path` into the backup directory with the SQLite
+/// online backup API, so it is safe to call while the app holds connections.
+/// Snapshots of an encrypted database are encrypted with the same key.
+pub fn create_backup(
+    db_path: &Path,
+    data_dir: &Path,
+    kind: BackupKind,
+    key: Option<&DatabaseKey>,
+) -> Result<BackupInfo, String> {
+    let directory = backup_dir(data_dir);
+    fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+
+    let file_name = format!(
+        "db-{}-{}.sqlite",
+        Local::now().format(TIMESTAMP_FORMAT),
+        kind.to_str()
+    );
+    let backup_path = directory.join(&file_name);
+
+    let source = open(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY, key)?;
+    let mut destination = open(&backup_path, OpenFlags::default(), key)?;
+    copy(&source, &mut destination)?;
+
+    println!(
+        "Created {} backup at: {}",
+        kind.to_str(),
+        backup_path.display()
+    );
+
+    apply_retention(data_dir, &load_retention(data_dir), kind).map_err(|e| e.to_string())?;
+
+    parse_backup(&backup_path).ok_or_else(|| "Failed to read created backup".to_string())
+}
+
+/// Creates a daily snapshot unless one was already taken today.
+pub fn create_daily_backup_if_due(
+    db_path: &Path,
+    data_dir: &Path,
+    key: Option<&DatabaseKey>,
+) -> Result<Option<BackupInfo>, String> {
+    let today = Local::now().date_naive();
+
+    let has_backup_today = list_backups(data_dir)?.iter().any(|backup| {
+        backup.kind == BackupKind::Daily
+            && chrono::DateTime::parse_from_rfc3339(&backup.created_at)
+                .map(|created_at| created_at.with_timezone(&Local).date_naive() == today)
+                .unwrap_or(false)
+    });
+
+    if has_backup_today {
+        return Ok(None);
+    }
+
+    create_backup(db_path, data_dir, BackupKind::Daily, key).map(Some)
+}
+
+/// Lists all snapshots in the backup directory, newest first.
+pub fn list_backups(data_dir: &Path) -> Result<Vec<BackupInfo>, String> {
+    let directory = backup_dir(data_dir);
+    if !directory.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<BackupInfo> = fs::read_dir(&directory)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| parse_backup(&entry.path()))
+        .collect();
+
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+
+    Ok(backups)
+}
+
+/// Replaces the database at `db_path` with the snapshot `file_name`.
+///
+/// The caller must make sure no connection to `db_path` is open. The current
+/// database is snapshotted first so a restore can itself be undone.
+pub fn restore_backup(
+    db_path: &Path,
+    data_dir: &Path,
+    file_name: &str,
+    key: Option<&DatabaseKey>,
+) -> Result<(), String> {
+    let backup_path = backup_dir(data_dir).join(file_name);
+
+    // Only plain file names from `list_backups` are accepted.
+    let is_file_name = Path::new(file_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        == Some(file_name);
+    if !is_file_name || parse_backup(&backup_path).is_none() {
+        return Err(format!("Unknown backup: {}", file_name));
+    }
+
+    create_backup(db_path, data_dir, BackupKind::PreRestore, key)?;
+
+    let source = open(&backup_path, OpenFlags::SQLITE_OPEN_READ_ONLY, key)?;
+    let mut destination = open(db_path, OpenFlags::default(), key)?;
+    copy(&source, &mut destination)?;
+
+    println!("Restored database from backup: {}", backup_path.display());
+
+    Ok(())
+}
+
+/// The stored retention, or the default if none or an invalid one is stored.
+pub fn load_retention(data_dir: &Path) -> BackupRetention {
+    fs::read_to_string(backup_dir(data_dir).join(RETENTION_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<BackupRetention>(&contents).ok())
+        .filter(|retention| retention.validate().is_ok())
+        .unwrap_or_default()
+}
+
+pub fn save_retention(data_dir: &Path, retention: &BackupRetention) -> Result<(), AppError> {
+    retention.validate()?;
+
+    let directory = backup_dir(data_dir);
+    fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+
+    let contents = serde_json::to_string_pretty(retention).map_err(|e| e.to_string())?;
+    fs::write(directory.join(RETENTION_FILE), contents).map_err(|e| e.to_string())?;
+
+    for kind in [
+        BackupKind::Daily,
+        BackupKind::PreMigration,
+        BackupKind::PreRestore,
+    ] {
+        apply_retention(data_dir, retention, kind)?;
+    }
+
+    Ok(())
+}
+
+fn open(path: &Path, flags: OpenFlags, key: Option<&DatabaseKey>) -> Result<Connection, String> {
+    let connection = Connection::open_with_flags(path, flags).map_err(|e| e.to_string())?;
+    if let Some(key) = key {
+        key.apply_rusqlite(&connection).map_err(|e| e.to_string())?;
+    }
+    Ok(connection)
+}
+
+fn copy(source: &Connection, destination: &mut Connection) -> Result<(), String> {
+    Backup::new(source, destination)
+        .and_then(|backup| backup.run_to_completion(PAGES_PER_STEP, Duration::ZERO, None))
+        .map_err(|e| e.to_string())
+}
+
+/// Deletes the oldest snapshots of `kind` beyond the limit in `retention`.
+fn apply_retention(
+    data_dir: &Path,
+    retention: &BackupRetention,
+    kind: BackupKind,
+) -> Result<(), AppError> {
+    retention.validate()?;
+    let limit = retention.limit(kind) as usize;
+
+    let expired = list_backups(data_dir)?
+        .into_iter()
+        .filter(|backup| backup.kind == kind)
+        .skip(limit);
+
+    for backup in expired {
+        fs::remove_file(backup_dir(data_dir).join(&backup.file_name)).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Backups are named `db-<local timestamp>-<kind>.sqlite`.
+fn parse_backup(path: &Path) -> Option<BackupInfo> {
+    let file_name = path.file_name()?.to_str()?;
+    let stem = file_name.strip_prefix("db-")?.strip_suffix(".sqlite")?;
+
+    // The timestamp itself contains a dash, the kind starts after the second.
+    let (date, rest) = stem.split_once('-')?;
+    let (time, kind) = rest.split_once('-')?;
+
+    let created_at =
+        NaiveDateTime::parse_from_str(&format!("{}-{}", date, time), PARSE_TIMESTAMP_FORMAT)
+            .ok()?;
+    let created_at = Local.from_local_datetime(&created_at).earliest()?;
+    let size_bytes = fs::metadata(path).ok()?.len();
+
+    Some(BackupInfo {
+        file_name: file_name.to_string(),
+        kind: BackupKind::from_str(kind)?,
+        created_at: created_at.to_rfc3339(),
+        size_bytes: size_bytes as f64,
+    })
+}
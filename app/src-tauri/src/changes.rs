@@ -0,0 +1,160 @@
+//! Data changes pushed to the windows, so views don't have to refetch after
+//! every mutation.
+//!
+//! Every mutation made through the repositories is recorded in the history
+//! (see [`crate::events`]), whether it came from the app, the CLI or the
+//! automation API. A [`ChangeFeed`] reads what was recorded since it last
+//! looked and turns it into [`DataChange`]s, which the app emits as the
+//! `data_changed` event of `Api`.
+
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use diesel::sqlite::SqliteConnection;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::events;
+use crate::models::{Event, Habit, HabitCompletion, PomodoroCycle, PomodoroSession};
+use crate::schema::events as history;
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum DataChange {
+    #[serde(rename = "habit.created")]
+    HabitCreated { habit: Habit },
+    /// Also sent when a habit is archived or restored.
+    #[serde(rename = "habit.updated")]
+    HabitUpdated { habit: Habit },
+    #[serde(rename = "habit.deleted", rename_all = "camelCase")]
+    HabitDeleted { habit_id: String },
+    /// A day was marked, or unmarked when `completed` is false.
+    #[serde(rename = "completion.toggled")]
+    CompletionToggled {
+        completion: HabitCompletion,
+        completed: bool,
+    },
+    #[serde(rename = "cycle.started")]
+    CycleStarted { cycle: PomodoroCycle },
+    #[serde(rename = "cycle.updated")]
+    CycleUpdated { cycle: PomodoroCycle },
+    #[serde(rename = "session.started")]
+    SessionStarted { session: PomodoroSession },
+    #[serde(rename = "session.completed")]
+    SessionCompleted { session: PomodoroSession },
+    #[serde(rename = "preset.changed", rename_all = "camelCase")]
+    PresetChanged { preset_id: String },
+    /// Data changed without history, like after a sync or unlocking the
+    /// database. Everything should be reloaded.
+    #[serde(rename = "data.reloaded")]
+    DataReloaded,
+}
+
+impl DataChange {
+    /// Whether lists of habits or their completions may be out of date.
+    pub fn affects_habits(&self) -> bool {
+        matches!(
+            self,
+            DataChange::HabitCreated { .. }
+                | DataChange::HabitUpdated { .. }
+                | DataChange::HabitDeleted { .. }
+                | DataChange::CompletionToggled { .. }
+                | DataChange::DataReloaded
+        )
+    }
+}
+
+/// The change a recorded mutation made, if the windows care about it.
+pub fn from_event(event: &Event) -> Option<DataChange> {
+    let operation = event.operation.as_str();
+    let change = match (event.entity.as_str(), operation) {
+        (events::HABITS, "CREATE") => DataChange::HabitCreated {
+            habit: row(&event.after)?,
+        },
+        (events::HABITS, "UPDATE") => DataChange::HabitUpdated {
+            habit: row(&event.after)?,
+        },
+        (events::HABITS, "DELETE") => DataChange::HabitDeleted {
+            habit_id: event.entity_id.clone(),
+        },
+        (events::HABIT_COMPLETIONS, "CREATE") => DataChange::CompletionToggled {
+            completion: row(&event.after)?,
+            completed: true,
+        },
+        (events::HABIT_COMPLETIONS, "DELETE") => DataChange::CompletionToggled {
+            completion: row(&event.before)?,
+            completed: false,
+        },
+        (events::POMODORO_CYCLES, "CREATE") => DataChange::CycleStarted {
+            cycle: row(&event.after)?,
+        },
+        (events::POMODORO_CYCLES, "UPDATE") => DataChange::CycleUpdated {
+            cycle: row(&event.after)?,
+        },
+        (events::POMODORO_SESSIONS, "CREATE") => DataChange::SessionStarted {
+            session: row(&event.after)?,
+        },
+        (events::POMODORO_SESSIONS, "UPDATE") => {
+            let session: PomodoroSession = row(&event.after)?;
+            session.completed_at?;
+            DataChange::SessionCompleted { session }
+        }
+        (events::POMODORO_PRESETS, _) => DataChange::PresetChanged {
+            preset_id: event.entity_id.clone(),
+        },
+        _ => return None,
+    };
+
+    Some(change)
+}
+
+/// Rows recorded by older versions may not parse anymore, those are skipped.
+fn row<T: DeserializeOwned>(json: &Option<String>) -> Option<T> {
+    serde_json::from_str(json.as_deref()?).ok()
+}
+
+/// Reads the history in the order it was recorded.
+pub struct ChangeFeed {
+    /// The `rowid` of the last read mutation. SQLite has one writer at a
+    /// time, so rows are numbered in the order they were committed, also
+    /// when the CLI writes alongside the app.
+    last_rowid: i64,
+}
+
+fn rowid() -> SqlLiteral<BigInt> {
+    sql::<BigInt>("events.rowid")
+}
+
+impl ChangeFeed {
+    /// A feed of the mutations recorded from now on.
+    pub fn new(connection: &mut SqliteConnection) -> QueryResult<Self> {
+        let last_rowid = history::table
+            .select(rowid())
+            .order(rowid().desc())
+            .first::<i64>(connection)
+            .optional()?;
+
+        Ok(ChangeFeed {
+            last_rowid: last_rowid.unwrap_or(0),
+        })
+    }
+
+    /// The changes recorded since the last call, oldest first.
+    pub fn poll(&mut self, connection: &mut SqliteConnection) -> QueryResult<Vec<DataChange>> {
+        let recorded = history::table
+            .filter(rowid().gt(self.last_rowid))
+            .order(rowid().asc())
+            .select((rowid(), Event::as_select()))
+            .load::<(i64, Event)>(connection)?;
+
+        if let Some((last_rowid, _)) = recorded.last() {
+            self.last_rowid = *last_rowid;
+        }
+
+        Ok(recorded
+            .iter()
+            .filter_map(|(_, event)| from_event(event))
+            .collect())
+    }
+}
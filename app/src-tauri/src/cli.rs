@@ -0,0 +1,478 @@
+//! The `alchemy` command line tool. It works on the same database as the app
+//! through the same repositories as the resolvers.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{Parser, Subcommand};
+use diesel::sqlite::SqliteConnection;
+use serde::Serialize;
+
+use crate::db;
+use crate::encryption;
+use crate::entitlements;
+use crate::error::AppError;
+use crate::models::{self, Habit, HabitCompletion, SessionType, Status};
+use crate::repository::{
+    CompleteSessionArgs, CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs,
+    CreateSessionArgs, GetHabitCompletionsArgs, HabitRepository, PomodoroRepository,
+    StartCycleArgs, UpdateCycleArgs, UpdateHabitArgs,
+};
+use crate::settings;
+
+/// Read when the database is encrypted, as there is no prompt to unlock it.
+const PASSPHRASE_VAR: &str = "ALCHEMY_PASSPHRASE";
+const DEFAULT_ICON: &str = "CircleCheck";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "alchemy",
+    version,
+    about = "Track habits and pomodoros from the terminal"
+)]
+struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    /// Use this data directory instead of the app's
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage habits and mark them as done
+    #[command(subcommand)]
+    Habit(HabitCommand),
+    /// Run pomodoro cycles
+    #[command(subcommand)]
+    Pomo(PomoCommand),
+    /// Show focus time and completed habits of a day
+    Stats {
+        /// Day formatted as YYYY-MM-DD, today by default
+        #[arg(long)]
+        date: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum HabitCommand {
+    /// List active habits, or archived ones with --archived
+    List {
+        #[arg(long)]
+        archived: bool,
+    },
+    /// Create a habit
+    Add {
+        title: String,
+        #[arg(long, default_value = "")]
+        description: String,
+        #[arg(long, default_value = DEFAULT_ICON)]
+        icon: String,
+    },
+    /// Mark a habit, given by id or title, as done
+    Done {
+        habit: String,
+        /// Day formatted as YYYY-MM-DD, today by default
+        #[arg(long)]
+        date: Option<String>,
+    },
+    /// Unmark a habit, given by id or title, as done
+    Undo {
+        habit: String,
+        /// Day formatted as YYYY-MM-DD, today by default
+        #[arg(long)]
+        date: Option<String>,
+    },
+    /// Archive a habit, given by id or title
+    Archive { habit: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum PomoCommand {
+    /// Start a cycle and its first focus session
+    Start {
+        /// Preset to take the durations from, the default preset otherwise
+        #[arg(long)]
+        preset: Option<String>,
+    },
+    /// Show the running cycle
+    Status,
+    /// End the running session and cycle
+    Stop {
+        /// Mark the cycle as abandoned instead of completed
+        #[arg(long)]
+        abandon: bool,
+    },
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PomoStatus {
+    cycle_id: String,
+    preset_id: Option<String>,
+    session_id: Option<String>,
+    session_type: Option<SessionType>,
+    started_at: Option<DateTime<Utc>>,
+    remaining_ms: Option<i64>,
+    completed_sessions: usize,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DayStats {
+    date: NaiveDate,
+    focus_minutes: i32,
+    habits_completed: usize,
+    habits_active: usize,
+}
+
+/// Entry point of the `alchemy` binary.
+pub fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            if json {
+                eprintln!("{}", serde_json::to_string(&error).unwrap_or_default());
+            } else {
+                eprintln!("Error: {}", error);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), AppError> {
+    let mut connection = open(cli.data_dir)?;
+    let output = Output { json: cli.json };
+
+    match cli.command {
+        Command::Habit(command) => habit(&mut connection, &output, command),
+        Command::Pomo(command) => pomo(&mut connection, &output, command),
+        Command::Stats { date } => stats(&mut connection, &output, date),
+    }
+}
+
+fn open(data_dir: Option<PathBuf>) -> Result<SqliteConnection, AppError> {
+    let data_dir = data_dir
+        .or_else(db::default_data_dir)
+        .ok_or("Failed to get application data directory")?;
+
+    let key = if encryption::is_enabled(&data_dir) {
+        let passphrase = std::env::var(PASSPHRASE_VAR).map_err(|_| {
+            format!(
+                "Database is encrypted, set {} to its passphrase",
+                PASSPHRASE_VAR
+            )
+        })?;
+        Some(encryption::unlock(
+            &db::database_path(&data_dir),
+            &data_dir,
+            &passphrase,
+        )?)
+    } else {
+        None
+    };
+
+    Ok(db::open_connection(&data_dir, key.as_ref())?)
+}
+
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    /// Prints `value` as JSON, or as the text `format` makes of it.
+    fn print<T: Serialize>(&self, value: &T, format: impl FnOnce(&T) -> String) {
+        if self.json {
+            match serde_json::to_string_pretty(value) {
+                Ok(json) => println!("{}", json),
+                Err(e) => eprintln!("Error: {}", e),
+            }
+        } else {
+            println!("{}", format(value));
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+// Habits
+// --------------------------------------------------------------------------
+
+fn habit(
+    connection: &mut SqliteConnection,
+    output: &Output,
+    command: HabitCommand,
+) -> Result<(), AppError> {
+    match command {
+        HabitCommand::List { archived } => {
+            let habits = if archived {
+                connection.archived_habits()?
+            } else {
+                connection.active_habits()?
+            };
+
+            output.print(&habits, |habits| {
+                if habits.is_empty() {
+                    return "No habits".to_string();
+                }
+                habits
+                    .iter()
+                    .map(|habit| format!("{}  {}", habit.id, habit.title))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+        }
+        HabitCommand::Add {
+            title,
+            description,
+            icon,
+        } => {
+            entitlements::require_habit_slots(connection, 1)?;
+            let habit = connection.create_habit(CreateHabitArgs {
+                title,
+                description,
+                icon,
+            })?;
+
+            output.print(&habit, |habit| format!("Created \"{}\"", habit.title));
+        }
+        HabitCommand::Done { habit, date } => {
+            let habit = find_habit(connection, &habit)?;
+            let day = parse_day(connection, date.as_deref())?;
+            if completion_on(connection, &habit, day)?.is_some() {
+                return Err(format!("\"{}\" is already done on {}", habit.title, day).into());
+            }
+
+            let mutation = connection.toggle_habit_completion(CreateHabitCompletionArgs {
+                id: None,
+                habit_id: habit.id,
+                created_at: Some(day.to_string()),
+            })?;
+
+            output.print(&mutation.value, |_| mutation.description.clone());
+        }
+        HabitCommand::Undo { habit, date } => {
+            let habit = find_habit(connection, &habit)?;
+            let day = parse_day(connection, date.as_deref())?;
+            let completion = completion_on(connection, &habit, day)?
+                .ok_or_else(|| format!("\"{}\" is not done on {}", habit.title, day))?;
+
+            let mutation = connection.toggle_habit_completion(CreateHabitCompletionArgs {
+                id: Some(completion.id),
+                habit_id: habit.id,
+                created_at: None,
+            })?;
+
+            output.print(&mutation.value, |_| mutation.description.clone());
+        }
+        HabitCommand::Archive { habit } => {
+            let habit = find_habit(connection, &habit)?;
+
+            let mutation = connection.update_habit(UpdateHabitArgs {
+                id: habit.id,
+                title: None,
+                description: None,
+                icon: None,
+                is_archived: Some(true),
+            })?;
+
+            output.print(&mutation.value, |_| mutation.description.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds a habit by id, or else by its title ignoring case.
+fn find_habit(connection: &mut SqliteConnection, habit: &str) -> Result<Habit, AppError> {
+    let mut habits = connection.active_habits()?;
+    habits.extend(connection.archived_habits()?);
+
+    if let Some(index) = habits.iter().position(|h| h.id == habit) {
+        return Ok(habits.swap_remove(index));
+    }
+
+    let mut matches: Vec<Habit> = habits
+        .into_iter()
+        .filter(|h| h.title.to_lowercase() == habit.to_lowercase())
+        .collect();
+
+    match matches.len() {
+        0 => Err(format!("Unknown habit: {}", habit).into()),
+        1 => Ok(matches.remove(0)),
+        _ => Err(format!("Several habits are called \"{}\", use its id", habit).into()),
+    }
+}
+
+fn completion_on(
+    connection: &mut SqliteConnection,
+    habit: &Habit,
+    day: NaiveDate,
+) -> Result<Option<HabitCompletion>, AppError> {
+    Ok(connection
+        .habit_completions(GetHabitCompletionsArgs {
+            habit_id: habit.id.clone(),
+            limit: None,
+        })?
+        .into_iter()
+        .find(|completion| completion.created_at == day))
+}
+
+/// `date`, or today in the configured timezone.
+fn parse_day(connection: &mut SqliteConnection, date: Option<&str>) -> Result<NaiveDate, AppError> {
+    match date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date {}, expected YYYY-MM-DD", date).into()),
+        None => Ok(settings::load(connection)?.today()),
+    }
+}
+
+// --------------------------------------------------------------------------
+// Pomodoro
+// --------------------------------------------------------------------------
+
+fn pomo(
+    connection: &mut SqliteConnection,
+    output: &Output,
+    command: PomoCommand,
+) -> Result<(), AppError> {
+    match command {
+        PomoCommand::Start { preset } => {
+            if connection.current_cycle()?.is_some() {
+                return Err("A pomodoro cycle is already running".into());
+            }
+
+            let cycle = connection.start_pomodoro_cycle(StartCycleArgs {
+                preset_id: preset,
+                focus_duration: None,
+                short_break_duration: None,
+                long_break_duration: None,
+                sessions_until_long_break: None,
+                auto_start_breaks: None,
+                auto_start_pomodoros: None,
+            })?;
+            connection.start_session(CreateSessionArgs {
+                cycle_id: cycle.id,
+                session_type: SessionType::Focus,
+                duration_ms: cycle.focus_duration,
+            })?;
+
+            output.print(&status(connection)?, format_status);
+        }
+        PomoCommand::Status => {
+            output.print(&status(connection)?, format_status);
+        }
+        PomoCommand::Stop { abandon } => {
+            let cycle = connection
+                .current_cycle()?
+                .ok_or("No pomodoro cycle is running")?;
+
+            // A session counts as completed once its full duration has passed
+            if let Some(session) = cycle.sessions.iter().find(|s| s.completed_at.is_none()) {
+                let elapsed = (models::now() - session.started_at).num_milliseconds();
+                connection.complete_session(CompleteSessionArgs {
+                    session_id: session.id.clone(),
+                    was_completed: elapsed >= i64::from(session.duration_ms),
+                })?;
+            }
+
+            let mutation = connection.update_cycle_status(UpdateCycleArgs {
+                id: cycle.id,
+                status: if abandon {
+                    Status::Abandoned
+                } else {
+                    Status::Completed
+                },
+            })?;
+
+            output.print(&mutation.value, |_| mutation.description.clone());
+        }
+    }
+
+    Ok(())
+}
+
+fn status(connection: &mut SqliteConnection) -> Result<Option<PomoStatus>, AppError> {
+    let Some(cycle) = connection.current_cycle()? else {
+        return Ok(None);
+    };
+
+    let running = cycle.sessions.iter().find(|s| s.completed_at.is_none());
+
+    Ok(Some(PomoStatus {
+        cycle_id: cycle.id.clone(),
+        preset_id: cycle.preset_id.clone(),
+        session_id: running.map(|session| session.id.clone()),
+        session_type: running.map(|session| session.session_type),
+        started_at: running.map(|session| session.started_at),
+        remaining_ms: running.map(|session| {
+            let elapsed = (models::now() - session.started_at).num_milliseconds();
+            (i64::from(session.duration_ms) - elapsed).max(0)
+        }),
+        completed_sessions: cycle.sessions.iter().filter(|s| s.was_completed).count(),
+    }))
+}
+
+fn format_status(status: &Option<PomoStatus>) -> String {
+    let Some(status) = status else {
+        return "No pomodoro cycle is running".to_string();
+    };
+
+    match (status.session_type, status.remaining_ms) {
+        (Some(session_type), Some(remaining_ms)) => format!(
+            "{} session, {}:{:02} left ({} completed)",
+            session_type.as_str().to_lowercase().replace('_', " "),
+            remaining_ms / 60_000,
+            remaining_ms % 60_000 / 1000,
+            status.completed_sessions
+        ),
+        _ => format!(
+            "Cycle running, no session in progress ({} completed)",
+            status.completed_sessions
+        ),
+    }
+}
+
+// --------------------------------------------------------------------------
+// Stats
+// --------------------------------------------------------------------------
+
+fn stats(
+    connection: &mut SqliteConnection,
+    output: &Output,
+    date: Option<String>,
+) -> Result<(), AppError> {
+    // Focus time is counted per local day, like on the dashboard
+    let day = parse_day(connection, date.as_deref())?;
+
+    let habits = connection.active_habits()?;
+    let mut habits_completed = 0;
+    for habit in &habits {
+        if completion_on(connection, habit, day)?.is_some() {
+            habits_completed += 1;
+        }
+    }
+
+    let stats = DayStats {
+        date: day,
+        focus_minutes: connection.daily_stats(&day.format("%Y-%m-%d").to_string())?,
+        habits_completed,
+        habits_active: habits.len(),
+    };
+
+    output.print(&stats, |stats| {
+        format!(
+            "{}: {} min focused, {}/{} habits done",
+            stats.date, stats.focus_minutes, stats.habits_completed, stats.habits_active
+        )
+    });
+
+    Ok(())
+}
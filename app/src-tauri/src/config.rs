@@ -0,0 +1,180 @@
+//! Endpoints and credentials, which differ between test and production.
+//!
+//! Values come from, highest precedence first: `ALCHEMY_*` environment
+//! variables at runtime, `config.json` in the data directory, the same
+//! variables at build time, and defaults for the environment. Release builds
+//! skip the runtime sources, so the license server can't be swapped for
+//! another one after shipping. Debug builds default to the test environment,
+//! release builds to production. The test API key is only compiled into
+//! debug builds, a release build has no key unless one is built in.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(debug_assertions, feature = "test-util"))]
+pub const CONFIG_FILE_NAME: &str = "config.json";
+
+// Only read at runtime by debug builds
+#[cfg(any(debug_assertions, feature = "test-util"))]
+const ENVIRONMENT_VAR: &str = "ALCHEMY_ENV";
+#[cfg(any(debug_assertions, feature = "test-util"))]
+const LICENSE_API_URL_VAR: &str = "ALCHEMY_LICENSE_API_URL";
+#[cfg(any(debug_assertions, feature = "test-util"))]
+const LICENSE_API_KEY_VAR: &str = "ALCHEMY_LICENSE_API_KEY";
+
+pub const TEST_LICENSE_API_URL: &str = "https://test-api.creem.io/v1";
+pub const PRODUCTION_LICENSE_API_URL: &str = "https://api.creem.io/v1";
+#[cfg(debug_assertions)]
+const TEST_LICENSE_API_KEY: &str = "test-api-key";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    Test,
+    Production,
+}
+
+impl Environment {
+    fn for_build() -> Self {
+        if cfg!(debug_assertions) {
+            Environment::Test
+        } else {
+            Environment::Production
+        }
+    }
+
+    fn license_api_url(&self) -> &'static str {
+        match self {
+            Environment::Test => TEST_LICENSE_API_URL,
+            Environment::Production => PRODUCTION_LICENSE_API_URL,
+        }
+    }
+
+    fn license_api_key(&self) -> Option<&'static str> {
+        #[cfg(debug_assertions)]
+        if *self == Environment::Test {
+            return Some(TEST_LICENSE_API_KEY);
+        }
+        None
+    }
+}
+
+impl std::str::FromStr for Environment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "test" => Ok(Environment::Test),
+            "production" => Ok(Environment::Production),
+            _ => Err(format!("Unknown environment: {}", s)),
+        }
+    }
+}
+
+/// Values from one source, any of which may be missing.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ConfigValues {
+    pub environment: Option<Environment>,
+    pub license_api_url: Option<String>,
+    pub license_api_key: Option<String>,
+}
+
+impl ConfigValues {
+    /// The `ALCHEMY_*` variables set when the app was compiled.
+    pub fn from_build() -> Result<Self, String> {
+        Ok(ConfigValues {
+            environment: option_env!("ALCHEMY_ENV").map(str::parse).transpose()?,
+            license_api_url: option_env!("ALCHEMY_LICENSE_API_URL").map(String::from),
+            license_api_key: option_env!("ALCHEMY_LICENSE_API_KEY").map(String::from),
+        })
+    }
+
+    /// The `ALCHEMY_*` variables of the running process.
+    #[cfg(any(debug_assertions, feature = "test-util"))]
+    pub fn from_env() -> Result<Self, String> {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        Ok(ConfigValues {
+            environment: var(ENVIRONMENT_VAR).map(|e| e.parse()).transpose()?,
+            license_api_url: var(LICENSE_API_URL_VAR),
+            license_api_key: var(LICENSE_API_KEY_VAR),
+        })
+    }
+
+    /// Reads `config.json` in `data_dir`, if there is one.
+    #[cfg(any(debug_assertions, feature = "test-util"))]
+    pub fn read(data_dir: &Path) -> Result<Self, String> {
+        let path = data_dir.join(CONFIG_FILE_NAME);
+        if !path.exists() {
+            return Ok(ConfigValues::default());
+        }
+
+        let contents = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+    }
+
+    /// Fills in what `self` is missing from `other`.
+    fn or(self, other: ConfigValues) -> ConfigValues {
+        ConfigValues {
+            environment: self.environment.or(other.environment),
+            license_api_url: self.license_api_url.or(other.license_api_url),
+            license_api_key: self.license_api_key.or(other.license_api_key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LicenseConfig {
+    pub api_url: String,
+    /// `None` if no key is configured for the environment.
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub environment: Environment,
+    pub license: LicenseConfig,
+}
+
+impl Config {
+    /// Loads the configuration from all sources.
+    pub fn load(data_dir: Option<&Path>) -> Result<Self, String> {
+        Ok(Config::resolve(
+            Config::runtime_values(data_dir)?.or(ConfigValues::from_build()?),
+        ))
+    }
+
+    #[cfg(any(debug_assertions, feature = "test-util"))]
+    fn runtime_values(data_dir: Option<&Path>) -> Result<ConfigValues, String> {
+        let file = match data_dir {
+            Some(data_dir) => ConfigValues::read(data_dir)?,
+            None => ConfigValues::default(),
+        };
+
+        Ok(ConfigValues::from_env()?.or(file))
+    }
+
+    #[cfg(not(any(debug_assertions, feature = "test-util")))]
+    fn runtime_values(_data_dir: Option<&Path>) -> Result<ConfigValues, String> {
+        Ok(ConfigValues::default())
+    }
+
+    /// Completes `values` with the defaults of their environment.
+    pub fn resolve(values: ConfigValues) -> Self {
+        let environment = values.environment.unwrap_or_else(Environment::for_build);
+
+        Config {
+            environment,
+            license: LicenseConfig {
+                api_url: values
+                    .license_api_url
+                    .unwrap_or_else(|| environment.license_api_url().to_string()),
+                api_key: values
+                    .license_api_key
+                    .or_else(|| environment.license_api_key().map(String::from)),
+            },
+        }
+    }
+}
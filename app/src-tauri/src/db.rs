@@ -1,43 +1,141 @@
 use diesel::{
-    r2d2::{ConnectionManager, Pool},
+    r2d2::{self, ConnectionManager, CustomizeConnection, Pool},
     sqlite::SqliteConnection,
     Connection,
 };
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 use tauri::{App, Manager};
 
-const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+use crate::backup::{self, BackupKind};
+use crate::encryption::{self, DatabaseKey};
+
+pub(crate) const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+
+const DATABASE_FILE: &str = "db.sqlite";
+/// Must match `identifier` in tauri.conf.json.
+const APP_IDENTIFIER: &str = "com.achievealchemy.app";
 
-pub fn setup_db(app: &App) -> Pool<ConnectionManager<SqliteConnection>> {
-    let mut path = app
-        .path()
+pub fn data_dir(app: &App) -> PathBuf {
+    app.path()
         .app_data_dir()
-        .expect("Failed to get application data directory");
+        .expect("Failed to get application data directory")
+}
 
-    println!("Setting up database at: {}", path.display());
+/// The directory Tauri resolves as the app data dir, for processes that run
+/// without an `App` such as the CLI.
+pub fn default_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+pub fn database_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(DATABASE_FILE)
+}
+
+/// Opens the database in `data_dir`, or returns `None` if it is encrypted and
+/// has to be unlocked with the user's passphrase first.
+pub fn setup_db(data_dir: &Path) -> Option<Pool<ConnectionManager<SqliteConnection>>> {
+    println!("Setting up database at: {}", data_dir.display());
 
     // Create data directory if needed
-    if !path.exists() {
-        fs::create_dir_all(&path).expect("Failed to create data directory");
+    if !data_dir.exists() {
+        fs::create_dir_all(data_dir).expect("Failed to create data directory");
     }
 
-    path.push("db.sqlite");
+    let path = database_path(data_dir);
+
+    if encryption::is_enabled(data_dir) {
+        println!("Database is encrypted, waiting for passphrase");
+        return None;
+    }
 
     // Initialize database file and run migrations
     if !path.exists() {
         create_db_file(&path);
     }
-    run_migrations(&path);
+    run_migrations(&path, data_dir, None).expect("Failed to run database migrations");
+
+    if let Err(e) = backup::create_daily_backup_if_due(&path, data_dir, None) {
+        println!("Failed to create daily backup: {}", e);
+    }
+
+    Some(open_pool(&path, None))
+}
+
+/// Opens a single connection to the database in `data_dir`, creating and
+/// migrating it like `setup_db` does.
+pub fn open_connection(
+    data_dir: &Path,
+    key: Option<&DatabaseKey>,
+) -> Result<SqliteConnection, String> {
+    fs::create_dir_all(data_dir).map_err(|e| e.to_string())?;
+
+    let path = database_path(data_dir);
+    run_migrations(&path, data_dir, key)?;
+
+    let database_url = path.to_str().ok_or("Invalid database path")?;
+    let mut connection = SqliteConnection::establish(database_url).map_err(|e| e.to_string())?;
+    if let Some(key) = key {
+        key.apply(&mut connection).map_err(|e| e.to_string())?;
+    }
+
+    Ok(connection)
+}
 
-    // Set up connection pool
+#[derive(Debug)]
+struct KeyCustomizer(DatabaseKey);
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for KeyCustomizer {
+    fn on_acquire(&self, connection: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        self.0.apply(connection).map_err(r2d2::Error::QueryError)
+    }
+}
+
+pub fn open_pool(
+    path: &Path,
+    key: Option<DatabaseKey>,
+) -> Pool<ConnectionManager<SqliteConnection>> {
     let database_url = path.to_str().expect("Invalid database path");
     let manager = ConnectionManager::<SqliteConnection>::new(database_url);
-    Pool::builder()
+    let builder = Pool::builder();
+
+    // Every pooled connection of an encrypted database has to be keyed
+    let builder = match key {
+        Some(key) => builder.connection_customizer(Box::new(KeyCustomizer(key))),
+        None => builder,
+    };
+
+    builder
         .build(manager)
         .expect("Failed to create database connection pool")
 }
 
+/// Blocks until every connection has been returned to `pool`, or fails after
+/// `timeout`. Callers must prevent new checkouts while waiting, and should run
+/// it off the async runtime.
+pub fn wait_until_idle(
+    pool: &Pool<ConnectionManager<SqliteConnection>>,
+    timeout: Duration,
+) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let state = pool.state();
+        if state.idle_connections == state.connections {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err("Database is busy, please try again".to_string());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
 fn create_db_file(path: &PathBuf) {
     if let Some(parent) = path.parent() {
         if !parent.exists() {
@@ -47,11 +145,33 @@ fn create_db_file(path: &PathBuf) {
     fs::File::create(path).expect("Failed to create database file");
 }
 
-fn run_migrations(path: &PathBuf) {
-    let database_url = path.to_str().expect("Invalid database path");
-    let mut connection =
-        SqliteConnection::establish(database_url).expect("Failed to connect to database");
+/// Runs pending migrations, snapshotting the database first unless it is
+/// still empty.
+pub fn run_migrations(
+    path: &Path,
+    data_dir: &Path,
+    key: Option<&DatabaseKey>,
+) -> Result<(), String> {
+    // Checked before connecting, as the migration harness writes its own table.
+    let is_empty = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+
+    let database_url = path.to_str().ok_or("Invalid database path")?;
+    let mut connection = SqliteConnection::establish(database_url).map_err(|e| e.to_string())?;
+    if let Some(key) = key {
+        key.apply(&mut connection).map_err(|e| e.to_string())?;
+    }
+
+    let has_pending_migrations = connection
+        .has_pending_migration(MIGRATIONS)
+        .map_err(|e| e.to_string())?;
+
+    if has_pending_migrations && !is_empty {
+        backup::create_backup(path, data_dir, BackupKind::PreMigration, key)?;
+    }
+
     connection
         .run_pending_migrations(MIGRATIONS)
-        .expect("Failed to run database migrations");
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
 }
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use diesel::connection::SimpleConnection;
+use diesel::sqlite::SqliteConnection;
+use diesel::{Connection, QueryResult};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::backup;
+
+const CONFIG_FILE: &str = "encryption.json";
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+const MIN_PASSPHRASE_LENGTH: usize = 8;
+
+/// Key derivation parameters, stored next to the database because they are
+/// needed before it can be opened. Contains nothing secret.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionConfig {
+    pub salt: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl EncryptionConfig {
+    fn generate() -> Self {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+
+        // Argon2id with the minimum parameters recommended by OWASP
+        EncryptionConfig {
+            salt: hex::encode(salt),
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// The raw SQLCipher key derived from the user's passphrase.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DatabaseKey([u8; KEY_LENGTH]);
+
+impl fmt::Debug for DatabaseKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DatabaseKey(..)")
+    }
+}
+
+impl DatabaseKey {
+    /// A raw hex key makes SQLCipher skip its own key derivation.
+    fn sql_literal(&self) -> String {
+        format!("\"x'{}'\"", hex::encode(self.0))
+    }
+
+    /// Keys a freshly opened connection. Must run before any other statement.
+    pub fn apply(&self, connection: &mut SqliteConnection) -> QueryResult<()> {
+        let mut pragma = format!("PRAGMA key = {};", self.sql_literal());
+        let result = connection.batch_execute(&pragma);
+        pragma.zeroize();
+        result
+    }
+
+    pub fn apply_rusqlite(&self, connection: &rusqlite::Connection) -> rusqlite::Result<()> {
+        let mut pragma = format!("PRAGMA key = {};", self.sql_literal());
+        let result = connection.execute_batch(&pragma);
+        pragma.zeroize();
+        result
+    }
+}
+
+fn config_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(CONFIG_FILE)
+}
+
+pub fn load_config(data_dir: &Path) -> Option<EncryptionConfig> {
+    fs::read_to_string(config_path(data_dir))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+}
+
+pub fn is_enabled(data_dir: &Path) -> bool {
+    config_path(data_dir).exists()
+}
+
+fn derive_key(passphrase: &str, config: &EncryptionConfig) -> Result<DatabaseKey, String> {
+    let salt = hex::decode(&config.salt).map_err(|e| e.to_string())?;
+    let params = Params::new(
+        config.memory_kib,
+        config.iterations,
+        config.parallelism,
+        Some(KEY_LENGTH),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut key = [0u8; KEY_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| e.to_string())?;
+
+    Ok(DatabaseKey(key))
+}
+
+/// Derives the key for an encrypted database and checks that it opens it.
+pub fn unlock(db_path: &Path, data_dir: &Path, passphrase: &str) -> Result<DatabaseKey, String> {
+    let config = load_config(data_dir).ok_or("Database is not encrypted")?;
+    let key = derive_key(passphrase, &config)?;
+
+    let database_url = db_path.to_str().ok_or("Invalid database path")?;
+    let mut connection = SqliteConnection::establish(database_url).map_err(|e| e.to_string())?;
+    key.apply(&mut connection).map_err(|e| e.to_string())?;
+
+    // SQLCipher only reports a wrong key once a page is actually read
+    connection
+        .batch_execute("SELECT count(*) FROM sqlite_master;")
+        .map_err(|_| "Incorrect passphrase".to_string())?;
+
+    Ok(key)
+}
+
+/// Encrypts the existing plaintext database and all of its backups with a
+/// key derived from `passphrase`.
+///
+/// The caller must make sure no connection to `db_path` is open.
+pub fn encrypt_database(
+    db_path: &Path,
+    data_dir: &Path,
+    passphrase: &str,
+) -> Result<DatabaseKey, String> {
+    if is_enabled(data_dir) {
+        return Err("Database is already encrypted".to_string());
+    }
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        return Err(format!(
+            "Passphrase must be at least {} characters long",
+            MIN_PASSPHRASE_LENGTH
+        ));
+    }
+
+    let config = EncryptionConfig::generate();
+    let key = derive_key(passphrase, &config)?;
+    let encrypted_path = export_encrypted(db_path, &key)?;
+
+    // The config is written before the swap, so the database can never end up
+    // encrypted without the parameters needed to open it.
+    let contents = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    fs::write(config_path(data_dir), contents).map_err(|e| e.to_string())?;
+
+    if let Err(e) = fs::rename(&encrypted_path, db_path) {
+        let _ = fs::remove_file(config_path(data_dir));
+        let _ = fs::remove_file(&encrypted_path);
+        return Err(e.to_string());
+    }
+
+    // Plaintext snapshots would defeat the purpose on a shared machine
+    for backup in backup::list_backups(data_dir)? {
+        let backup_path = backup::backup_dir(data_dir).join(&backup.file_name);
+        let encrypted_backup = export_encrypted(&backup_path, &key)?;
+        fs::rename(&encrypted_backup, &backup_path).map_err(|e| e.to_string())?;
+    }
+
+    Ok(key)
+}
+
+/// Writes an encrypted copy of the plaintext database at `path` next to it
+/// and returns the path of the copy.
+fn export_encrypted(path: &Path, key: &DatabaseKey) -> Result<PathBuf, String> {
+    let mut encrypted_path = path.as_os_str().to_owned();
+    encrypted_path.push(".encrypted");
+    let encrypted_path = PathBuf::from(encrypted_path);
+
+    if encrypted_path.exists() {
+        fs::remove_file(&encrypted_path).map_err(|e| e.to_string())?;
+    }
+
+    let database_url = path.to_str().ok_or("Invalid database path")?;
+    let encrypted_url = encrypted_path
+        .to_str()
+        .ok_or("Invalid database path")?
+        .replace('\'', "''");
+
+    let mut connection = SqliteConnection::establish(database_url).map_err(|e| e.to_string())?;
+    let mut statements = format!(
+        "ATTACH DATABASE '{}' AS encrypted KEY {};\
+         SELECT sqlcipher_export('encrypted');\
+         DETACH DATABASE encrypted;",
+        encrypted_url,
+        key.sql_literal()
+    );
+    let result = connection.batch_execute(&statements);
+    statements.zeroize();
+    result.map_err(|e| e.to_string())?;
+
+    Ok(encrypted_path)
+}
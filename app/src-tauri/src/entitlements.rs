@@ -0,0 +1,121 @@
+//! The features a license unlocks.
+//!
+//! A valid license, or one in its offline grace period, unlocks every
+//! feature. Without one the app keeps working with up to `FREE_HABIT_LIMIT`
+//! active habits.
+
+use diesel::sqlite::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::license::{self, LicenseState};
+use crate::repository::HabitRepository;
+
+pub const FREE_HABIT_LIMIT: i32 = 3;
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Feature {
+    UnlimitedHabits,
+    Analytics,
+    Export,
+    Sync,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 4] = [
+        Feature::UnlimitedHabits,
+        Feature::Analytics,
+        Feature::Export,
+        Feature::Sync,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::UnlimitedHabits => "Unlimited habits",
+            Feature::Analytics => "Analytics",
+            Feature::Export => "Export",
+            Feature::Sync => "Sync",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Entitlements {
+    pub license_state: LicenseState,
+    pub features: Vec<Feature>,
+    /// Most active habits allowed, `None` with `UnlimitedHabits`.
+    pub habit_limit: Option<i32>,
+}
+
+impl Entitlements {
+    pub fn for_state(license_state: LicenseState) -> Self {
+        let features = match license_state {
+            LicenseState::Valid | LicenseState::Grace => Feature::ALL.to_vec(),
+            LicenseState::Expired | LicenseState::Invalid => Vec::new(),
+        };
+        let habit_limit = if features.contains(&Feature::UnlimitedHabits) {
+            None
+        } else {
+            Some(FREE_HABIT_LIMIT)
+        };
+
+        Entitlements {
+            license_state,
+            features,
+            habit_limit,
+        }
+    }
+
+    pub fn has(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    pub fn require(&self, feature: Feature) -> Result<(), AppError> {
+        if self.has(feature) {
+            Ok(())
+        } else {
+            Err(license_required(
+                feature,
+                format!("{} needs a license", feature.name()),
+            ))
+        }
+    }
+
+    /// Fails if `new_habits` more active habits would go past the limit.
+    pub fn require_habit_slots(&self, active_habits: i32, new_habits: i32) -> Result<(), AppError> {
+        match self.habit_limit {
+            Some(limit) if active_habits + new_habits > limit => Err(license_required(
+                Feature::UnlimitedHabits,
+                format!("More than {} active habits need a license", limit),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn license_required(feature: Feature, message: String) -> AppError {
+    AppError::LicenseRequired { feature, message }
+}
+
+/// The entitlements of the stored license, without contacting the provider.
+pub fn load(connection: &mut SqliteConnection) -> Result<Entitlements, AppError> {
+    Ok(Entitlements::for_state(
+        license::load_status(connection)?.state,
+    ))
+}
+
+pub fn require(connection: &mut SqliteConnection, feature: Feature) -> Result<(), AppError> {
+    load(connection)?.require(feature)
+}
+
+/// Checks that `new_habits` more active habits fit in the license.
+pub fn require_habit_slots(
+    connection: &mut SqliteConnection,
+    new_habits: i32,
+) -> Result<(), AppError> {
+    let active_habits = connection.active_habits()?.len() as i32;
+
+    load(connection)?.require_habit_slots(active_habits, new_habits)
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entitlements::Feature;
+use crate::license::LicenseErrorReason;
+use crate::validation::FieldError;
+
+/// Error returned by resolvers that the frontend needs to tell apart, e.g. to
+/// highlight invalid form fields. Everything else is `Internal`.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AppError {
+    Validation {
+        errors: Vec<FieldError>,
+    },
+    /// The license provider could not be reached or refused the request.
+    License {
+        reason: LicenseErrorReason,
+        message: String,
+    },
+    /// The feature isn't unlocked by the current license.
+    LicenseRequired {
+        feature: Feature,
+        message: String,
+    },
+    Internal {
+        message: String,
+    },
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Validation { errors } => {
+                let messages: Vec<String> = errors
+                    .iter()
+                    .map(|error| format!("{}: {}", error.field, error.message))
+                    .collect();
+                write!(f, "Invalid input: {}", messages.join("; "))
+            }
+            AppError::License { message, .. }
+            | AppError::LicenseRequired { message, .. }
+            | AppError::Internal { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal { message }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for AppError {
+    fn from(error: diesel::result::Error) -> Self {
+        AppError::Internal {
+            message: error.to_string(),
+        }
+    }
+}
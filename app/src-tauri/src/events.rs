@@ -0,0 +1,91 @@
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sqlite::SqliteConnection;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::{self, Event};
+use crate::schema::events;
+
+pub const HABITS: &str = "habits";
+pub const HABIT_COMPLETIONS: &str = "habit_completions";
+pub const POMODORO_CYCLES: &str = "pomodoro_cycles";
+pub const POMODORO_PRESETS: &str = "pomodoro_presets";
+pub const POMODORO_SESSIONS: &str = "pomodoro_sessions";
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    fn to_str(self) -> &'static str {
+        match self {
+            Operation::Create => "CREATE",
+            Operation::Update => "UPDATE",
+            Operation::Delete => "DELETE",
+        }
+    }
+}
+
+/// Appends a mutation of the row `entity_id` in the `entity` table to the
+/// history. Meant to run in the same transaction as the mutation itself.
+pub fn record<T: Serialize>(
+    connection: &mut SqliteConnection,
+    entity: &str,
+    entity_id: &str,
+    operation: Operation,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> QueryResult<Event> {
+    let to_json = |row: Option<&T>| {
+        row.map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| Error::SerializationError(Box::new(e)))
+    };
+
+    record_json(
+        connection,
+        entity,
+        entity_id,
+        operation,
+        to_json(before)?,
+        to_json(after)?,
+    )
+}
+
+/// Like [`record`], for rows that are already serialized.
+pub fn record_json(
+    connection: &mut SqliteConnection,
+    entity: &str,
+    entity_id: &str,
+    operation: Operation,
+    before: Option<String>,
+    after: Option<String>,
+) -> QueryResult<Event> {
+    let event = Event {
+        id: Uuid::new_v4().to_string(),
+        entity: entity.to_string(),
+        entity_id: entity_id.to_string(),
+        operation: operation.to_str().to_string(),
+        before,
+        after,
+        created_at: models::now(),
+    };
+
+    diesel::insert_into(events::table)
+        .values(&event)
+        .execute(connection)?;
+
+    Ok(event)
+}
+
+/// All recorded mutations of the row `entity_id`, newest first.
+pub fn history(connection: &mut SqliteConnection, entity_id: &str) -> QueryResult<Vec<Event>> {
+    events::table
+        .filter(events::entity_id.eq(entity_id))
+        .order(events::created_at.desc())
+        .load::<Event>(connection)
+}
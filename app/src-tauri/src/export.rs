@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use std::fmt::Display;
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde::Serialize;
+
+use crate::models::{HabitCompletion, PomodoroCycle, PomodoroSession, SessionType, Status};
+use crate::schema::{habit_completions, habits, pomodoro_cycles, pomodoro_sessions};
+use crate::settings;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const LOCAL_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Inclusive range of local calendar days, both ends formatted as `%Y-%m-%d`.
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl DateRange {
+    pub fn parse(start_date: &str, end_date: &str) -> Result<Self, String> {
+        let start =
+            NaiveDate::parse_from_str(start_date, DATE_FORMAT).map_err(|e| e.to_string())?;
+        let end = NaiveDate::parse_from_str(end_date, DATE_FORMAT).map_err(|e| e.to_string())?;
+
+        if end < start {
+            return Err("End date must not be before start date".to_string());
+        }
+
+        Ok(DateRange { start, end })
+    }
+
+    /// The range as UTC instants, from midnight of `start` in `timezone` up
+    /// to but excluding midnight after `end`.
+    pub fn bounds<Z: TimeZone>(&self, timezone: &Z) -> (DateTime<Utc>, DateTime<Utc>) {
+        (
+            local_midnight(self.start, timezone),
+            local_midnight(self.end + chrono::Duration::days(1), timezone),
+        )
+    }
+}
+
+/// Midnight may not exist on days a DST change happens at midnight, the
+/// first instant of the day is taken as UTC then.
+fn local_midnight<Z: TimeZone>(date: NaiveDate, timezone: &Z) -> DateTime<Utc> {
+    let midnight = date.and_time(chrono::NaiveTime::MIN);
+    timezone
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+#[derive(Serialize)]
+struct HabitCompletionRow {
+    completion_id: String,
+    habit_id: String,
+    habit_title: String,
+    date: String,
+}
+
+#[derive(Serialize)]
+struct PomodoroSessionRow {
+    session_id: String,
+    cycle_id: String,
+    cycle_status: Status,
+    session_type: SessionType,
+    date: String,
+    started_at_local: String,
+    completed_at_local: String,
+    duration_ms: i32,
+    was_completed: bool,
+    focus_duration: i32,
+    short_break_duration: i32,
+    long_break_duration: i32,
+    sessions_until_long_break: i32,
+    auto_start_breaks: bool,
+    auto_start_pomodoros: bool,
+}
+
+/// Writes every habit completion in `range` to a CSV file at `path`, joined
+/// with the title of its habit. Returns the number of rows written.
+pub fn export_habit_completions(
+    connection: &mut SqliteConnection,
+    range: &DateRange,
+    path: &Path,
+) -> Result<usize, String> {
+    let results = habit_completions::table
+        .inner_join(habits::table)
+        .filter(habit_completions::created_at.ge(range.start))
+        .filter(habit_completions::created_at.le(range.end))
+        .order(habit_completions::created_at.asc())
+        .select((HabitCompletion::as_select(), habits::title))
+        .load::<(HabitCompletion, String)>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let mut writer = csv::Writer::from_path(path).map_err(|e| e.to_string())?;
+    let mut written = 0;
+
+    for (completion, habit_title) in results {
+        // A completion only carries the calendar date the user ticked, there
+        // is no time of day to report.
+        writer
+            .serialize(HabitCompletionRow {
+                completion_id: completion.id,
+                habit_id: completion.habit_id,
+                habit_title,
+                date: completion.created_at.format(DATE_FORMAT).to_string(),
+            })
+            .map_err(|e| e.to_string())?;
+        written += 1;
+    }
+
+    writer.flush().map_err(|e| e.to_string())?;
+
+    Ok(written)
+}
+
+/// Writes every pomodoro session started in `range` to a CSV file at `path`,
+/// joined with the settings of its cycle. Returns the number of rows written.
+///
+/// The range and the written times are in the configured timezone.
+pub fn export_pomodoro_sessions(
+    connection: &mut SqliteConnection,
+    range: &DateRange,
+    path: &Path,
+) -> Result<usize, String> {
+    let settings = settings::load(connection)?;
+    match settings.timezone.as_deref().map(str::parse::<Tz>) {
+        Some(Ok(timezone)) => write_pomodoro_sessions(connection, range, path, &timezone),
+        _ => write_pomodoro_sessions(connection, range, path, &Local),
+    }
+}
+
+fn write_pomodoro_sessions<Z>(
+    connection: &mut SqliteConnection,
+    range: &DateRange,
+    path: &Path,
+    timezone: &Z,
+) -> Result<usize, String>
+where
+    Z: TimeZone,
+    Z::Offset: Display,
+{
+    let (start, end) = range.bounds(timezone);
+
+    let results = pomodoro_sessions::table
+        .inner_join(pomodoro_cycles::table)
+        .filter(pomodoro_sessions::started_at.ge(start))
+        .filter(pomodoro_sessions::started_at.lt(end))
+        .order(pomodoro_sessions::started_at.asc())
+        .load::<(PomodoroSession, PomodoroCycle)>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let mut writer = csv::Writer::from_path(path).map_err(|e| e.to_string())?;
+    let mut written = 0;
+
+    for (session, cycle) in results {
+        // Sessions are stored in UTC, the range is in local days.
+        let started_at = session.started_at.with_timezone(timezone);
+        let completed_at_local = session
+            .completed_at
+            .map(|completed_at| {
+                completed_at
+                    .with_timezone(timezone)
+                    .format(LOCAL_TIME_FORMAT)
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        writer
+            .serialize(PomodoroSessionRow {
+                session_id: session.id,
+                cycle_id: session.cycle_id,
+                cycle_status: cycle.status,
+                session_type: session.session_type,
+                date: started_at.format(DATE_FORMAT).to_string(),
+                started_at_local: started_at.format(LOCAL_TIME_FORMAT).to_string(),
+                completed_at_local,
+                duration_ms: session.duration_ms,
+                was_completed: session.was_completed,
+                focus_duration: cycle.focus_duration,
+                short_break_duration: cycle.short_break_duration,
+                long_break_duration: cycle.long_break_duration,
+                sessions_until_long_break: cycle.sessions_until_long_break,
+                auto_start_breaks: cycle.auto_start_breaks,
+                auto_start_pomodoros: cycle.auto_start_pomodoros,
+            })
+            .map_err(|e| e.to_string())?;
+        written += 1;
+    }
+
+    writer.flush().map_err(|e| e.to_string())?;
+
+    Ok(written)
+}
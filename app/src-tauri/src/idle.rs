@@ -0,0 +1,225 @@
+//! Pausing focus sessions while the user is away from the computer.
+//!
+//! The monitor in the app process calls [`check`] every few seconds with an
+//! [`IdleSource`], which reads the time since the last keyboard or mouse
+//! input from the system in the app and is faked in tests. Once that time
+//! passes `Settings::idle`'s threshold during a running focus session, the
+//! gap is recorded as an [`Interruption`] and the timer is paused. When input
+//! resumes the user decides whether the part of the gap the timer counted is
+//! kept as focus time or discarded from `daily_stats`.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, SubsecRound, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::prelude::*;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteConnection, SqliteValue};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{PomodoroSession, SessionType};
+use crate::schema::{pomodoro_interruptions, pomodoro_sessions};
+use crate::settings;
+use crate::timer::TimerState;
+use crate::validation::{Validate, Validator};
+
+pub trait IdleSource: Send + Sync {
+    /// Time since the last keyboard or mouse input.
+    fn idle_time(&self) -> Result<Duration, String>;
+}
+
+/// What the user decided about the time the timer counted while they were
+/// away. Interruptions without one count as kept.
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum InterruptionResolution {
+    Keep,
+    Discard,
+}
+
+impl InterruptionResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InterruptionResolution::Keep => "KEEP",
+            InterruptionResolution::Discard => "DISCARD",
+        }
+    }
+}
+
+impl FromStr for InterruptionResolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "KEEP" => Ok(InterruptionResolution::Keep),
+            "DISCARD" => Ok(InterruptionResolution::Discard),
+            _ => Err(format!("Unknown interruption resolution: {}", s)),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for InterruptionResolution {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for InterruptionResolution {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(value.parse()?)
+    }
+}
+
+/// A gap in input during a focus session.
+#[derive(Serialize, Deserialize, specta::Type, Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = pomodoro_interruptions)]
+#[serde(rename_all = "camelCase")]
+pub struct Interruption {
+    pub id: String,
+    pub session_id: String,
+    /// The last input before the gap.
+    pub started_at: DateTime<Utc>,
+    /// The first input after the gap, `None` while the user is still away.
+    pub ended_at: Option<DateTime<Utc>>,
+    /// The part of the gap the timer ran before it was paused.
+    pub counted_ms: i32,
+    pub resolution: Option<InterruptionResolution>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveInterruptionArgs {
+    pub id: String,
+    pub resolution: InterruptionResolution,
+}
+
+impl Validate for ResolveInterruptionArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new().not_empty("id", &self.id).finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum IdleEvent {
+    /// The user went away during a focus session, which should be paused.
+    Paused(Interruption),
+    /// The user is back and can keep or discard the counted time.
+    Returned(Interruption),
+}
+
+/// Records the start or end of an interruption, given the timer's last
+/// reported state.
+pub fn check(
+    connection: &mut SqliteConnection,
+    source: &dyn IdleSource,
+    timer: Option<&TimerState>,
+    now: DateTime<Utc>,
+) -> Result<Option<IdleEvent>, AppError> {
+    let idle_time = source.idle_time()?;
+    let idle_for = chrono::Duration::from_std(idle_time).map_err(|e| e.to_string())?;
+    // Stored timestamps have millisecond precision
+    let last_input = (now - idle_for).trunc_subsecs(3);
+
+    let open = pomodoro_interruptions::table
+        .filter(pomodoro_interruptions::ended_at.is_null())
+        .first::<Interruption>(connection)
+        .optional()?;
+    if let Some(mut interruption) = open {
+        // Still away
+        if last_input <= interruption.created_at {
+            return Ok(None);
+        }
+
+        interruption.ended_at = Some(last_input);
+        diesel::update(pomodoro_interruptions::table.find(&interruption.id))
+            .set(pomodoro_interruptions::ended_at.eq(interruption.ended_at))
+            .execute(connection)?;
+
+        return Ok(Some(IdleEvent::Returned(interruption)));
+    }
+
+    let settings = settings::load(connection)?.idle;
+    let is_focusing =
+        timer.is_some_and(|timer| timer.is_ticking() && timer.mode == SessionType::Focus);
+    if !settings.enabled || !is_focusing || idle_time.as_millis() < settings.threshold_ms as u128 {
+        return Ok(None);
+    }
+
+    let Some(session) = running_focus_session(connection)? else {
+        return Ok(None);
+    };
+
+    // Input that started the session ends any earlier gap
+    let started_at = last_input.max(session.started_at);
+    let interruption = Interruption {
+        id: Uuid::new_v4().to_string(),
+        session_id: session.id,
+        started_at,
+        ended_at: None,
+        counted_ms: (now - started_at).num_milliseconds() as i32,
+        resolution: None,
+        created_at: now,
+    };
+
+    diesel::insert_into(pomodoro_interruptions::table)
+        .values(&interruption)
+        .execute(connection)?;
+
+    Ok(Some(IdleEvent::Paused(interruption)))
+}
+
+fn running_focus_session(
+    connection: &mut SqliteConnection,
+) -> QueryResult<Option<PomodoroSession>> {
+    pomodoro_sessions::table
+        .filter(pomodoro_sessions::session_type.eq(SessionType::Focus))
+        .filter(pomodoro_sessions::completed_at.is_null())
+        .order(pomodoro_sessions::started_at.desc())
+        .first::<PomodoroSession>(connection)
+        .optional()
+}
+
+pub fn resolve_interruption(
+    connection: &mut SqliteConnection,
+    args: ResolveInterruptionArgs,
+) -> Result<Interruption, AppError> {
+    args.validate()?;
+
+    let mut interruption = pomodoro_interruptions::table
+        .find(&args.id)
+        .first::<Interruption>(connection)
+        .optional()?
+        .ok_or_else(|| format!("Unknown interruption: {}", args.id))?;
+    interruption.resolution = Some(args.resolution);
+
+    diesel::update(pomodoro_interruptions::table.find(&interruption.id))
+        .set(pomodoro_interruptions::resolution.eq(interruption.resolution))
+        .execute(connection)?;
+
+    Ok(interruption)
+}
+
+/// Interruptions the user came back from without deciding about, oldest
+/// first.
+pub fn unresolved_interruptions(
+    connection: &mut SqliteConnection,
+) -> QueryResult<Vec<Interruption>> {
+    pomodoro_interruptions::table
+        .filter(pomodoro_interruptions::ended_at.is_not_null())
+        .filter(pomodoro_interruptions::resolution.is_null())
+        .order(pomodoro_interruptions::started_at.asc())
+        .load::<Interruption>(connection)
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+
+use super::{HabitImporter, SourceHabit};
+
+/// Reads the "User Data" JSON export of Habitica. Dailies are imported with
+/// the days they were completed, habits with the days they were scored up.
+pub struct HabiticaImporter;
+
+#[derive(Deserialize)]
+struct HabiticaExport {
+    #[serde(default)]
+    tasks: HabiticaTasks,
+}
+
+#[derive(Deserialize, Default)]
+struct HabiticaTasks {
+    #[serde(default)]
+    habits: Vec<HabiticaTask>,
+    #[serde(default)]
+    dailys: Vec<HabiticaTask>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HabiticaTask {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    notes: String,
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    history: Vec<HabiticaHistoryEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HabiticaHistoryEntry {
+    date: serde_json::Value,
+    completed: Option<bool>,
+    scored_up: Option<i64>,
+}
+
+impl HabiticaHistoryEntry {
+    /// Habitica has written history dates both as epoch milliseconds and as
+    /// ISO strings over the years.
+    fn day(&self) -> Option<NaiveDate> {
+        match &self.date {
+            serde_json::Value::Number(millis) => millis
+                .as_f64()
+                .and_then(|millis| DateTime::from_timestamp_millis(millis as i64))
+                .map(|date| date.date_naive()),
+            serde_json::Value::String(date) => DateTime::parse_from_rfc3339(date)
+                .ok()
+                .map(|date| date.date_naive()),
+            _ => None,
+        }
+    }
+}
+
+impl HabitImporter for HabiticaImporter {
+    fn read(&self, path: &Path) -> Result<Vec<SourceHabit>, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let export: HabiticaExport = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+
+        let dailies = export.tasks.dailys.into_iter().map(|task| {
+            let completions = task
+                .history
+                .iter()
+                .filter(|entry| entry.completed == Some(true))
+                .filter_map(HabiticaHistoryEntry::day)
+                .collect();
+            to_source_habit(task, completions)
+        });
+
+        let habits = export.tasks.habits.into_iter().map(|task| {
+            let completions = task
+                .history
+                .iter()
+                .filter(|entry| entry.scored_up.unwrap_or(0) > 0)
+                .filter_map(HabiticaHistoryEntry::day)
+                .collect();
+            to_source_habit(task, completions)
+        });
+
+        Ok(dailies.chain(habits).collect())
+    }
+}
+
+fn to_source_habit(task: HabiticaTask, completions: Vec<NaiveDate>) -> SourceHabit {
+    SourceHabit {
+        title: task.text,
+        description: task.notes,
+        is_archived: false,
+        created_at: task.created_at.map(|created_at| created_at.naive_utc()),
+        completions,
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+use diesel::sqlite::SqliteConnection;
+
+use super::{HabitImporter, SourceHabit};
+
+// Loop stores check-ins as integers. Only days the user ticked themselves are
+// imported; "automatic" checkmarks are derived from the habit's frequency.
+const YES_MANUAL: i64 = 2;
+
+// Loop's numeric habits store their amounts multiplied by 1000.
+const HABIT_TYPE_NUMERICAL: i32 = 1;
+
+/// Reads the CSV export of Loop Habit Tracker, given either the extracted
+/// export directory or its `Habits.csv`.
+pub struct LoopCsvImporter;
+
+impl HabitImporter for LoopCsvImporter {
+    fn read(&self, path: &Path) -> Result<Vec<SourceHabit>, String> {
+        let directory: PathBuf = if path.is_dir() {
+            path.to_path_buf()
+        } else {
+            path.parent()
+                .map(Path::to_path_buf)
+                .ok_or_else(|| "Invalid Loop export path".to_string())?
+        };
+
+        let mut habits_reader =
+            csv::Reader::from_path(directory.join("Habits.csv")).map_err(|e| e.to_string())?;
+        let headers = habits_reader.headers().map_err(|e| e.to_string())?.clone();
+        let name_index = column_index(&headers, "Name")?;
+        let description_index = headers.iter().position(|header| header == "Description");
+        let question_index = headers.iter().position(|header| header == "Question");
+
+        let mut habits = Vec::new();
+        for record in habits_reader.records() {
+            let record = record.map_err(|e| e.to_string())?;
+            let description = description_index
+                .and_then(|index| record.get(index))
+                .filter(|description| !description.is_empty())
+                .or_else(|| question_index.and_then(|index| record.get(index)))
+                .unwrap_or_default();
+
+            habits.push(SourceHabit {
+                title: record.get(name_index).unwrap_or_default().to_string(),
+                description: description.to_string(),
+                is_archived: false,
+                created_at: None,
+                completions: Vec::new(),
+            });
+        }
+
+        // Checkmarks.csv has a `Date` column followed by one column per habit,
+        // in the same order as Habits.csv.
+        let mut checkmarks_reader =
+            csv::Reader::from_path(directory.join("Checkmarks.csv")).map_err(|e| e.to_string())?;
+        let headers = checkmarks_reader
+            .headers()
+            .map_err(|e| e.to_string())?
+            .clone();
+        let habit_columns: HashMap<usize, usize> = headers
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(column, name)| {
+                habits
+                    .iter()
+                    .position(|habit| habit.title == name)
+                    .map(|habit| (column, habit))
+            })
+            .collect();
+
+        for record in checkmarks_reader.records() {
+            let record = record.map_err(|e| e.to_string())?;
+            let date = match record
+                .get(0)
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            {
+                Some(date) => date,
+                None => continue,
+            };
+
+            for (column, habit) in &habit_columns {
+                if record
+                    .get(*column)
+                    .and_then(|value| value.trim().parse::<i64>().ok())
+                    == Some(YES_MANUAL)
+                {
+                    habits[*habit].completions.push(date);
+                }
+            }
+        }
+
+        Ok(habits)
+    }
+}
+
+/// Reads the `.db` backup file of Loop Habit Tracker.
+pub struct LoopSqliteImporter;
+
+#[derive(QueryableByName)]
+struct LoopHabitRow {
+    #[diesel(sql_type = Integer)]
+    id: i32,
+    #[diesel(sql_type = Nullable<Text>)]
+    name: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    description: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    question: Option<String>,
+    #[diesel(sql_type = Integer)]
+    archived: i32,
+    #[diesel(sql_type = Integer)]
+    habit_type: i32,
+}
+
+#[derive(QueryableByName)]
+struct LoopRepetitionRow {
+    #[diesel(sql_type = Integer)]
+    habit: i32,
+    #[diesel(sql_type = BigInt)]
+    timestamp: i64,
+    #[diesel(sql_type = BigInt)]
+    value: i64,
+}
+
+impl HabitImporter for LoopSqliteImporter {
+    fn read(&self, path: &Path) -> Result<Vec<SourceHabit>, String> {
+        let database_url = path.to_str().ok_or("Invalid Loop backup path")?;
+        let connection =
+            &mut SqliteConnection::establish(database_url).map_err(|e| e.to_string())?;
+
+        let habit_rows = diesel::sql_query(
+            "SELECT id, name, description, question, \
+             COALESCE(archived, 0) AS archived, COALESCE(type, 0) AS habit_type \
+             FROM Habits ORDER BY position",
+        )
+        .load::<LoopHabitRow>(connection)
+        .map_err(|e| e.to_string())?;
+
+        let repetition_rows =
+            diesel::sql_query("SELECT habit, timestamp, value FROM Repetitions ORDER BY timestamp")
+                .load::<LoopRepetitionRow>(connection)
+                .map_err(|e| e.to_string())?;
+
+        let mut habits = Vec::new();
+        let mut habit_indices = HashMap::new();
+        let mut habit_types = HashMap::new();
+
+        for row in habit_rows {
+            let description = row
+                .description
+                .filter(|description| !description.is_empty())
+                .or(row.question)
+                .unwrap_or_default();
+
+            habit_indices.insert(row.id, habits.len());
+            habit_types.insert(row.id, row.habit_type);
+            habits.push(SourceHabit {
+                title: row.name.unwrap_or_default(),
+                description,
+                is_archived: row.archived != 0,
+                created_at: None,
+                completions: Vec::new(),
+            });
+        }
+
+        for row in repetition_rows {
+            let index = match habit_indices.get(&row.habit) {
+                Some(index) => *index,
+                None => continue,
+            };
+
+            let completed = if habit_types.get(&row.habit) == Some(&HABIT_TYPE_NUMERICAL) {
+                row.value > 0
+            } else {
+                row.value == YES_MANUAL
+            };
+
+            // Timestamps are milliseconds at midnight UTC of the check-in day.
+            if let Some(timestamp) = DateTime::from_timestamp_millis(row.timestamp) {
+                if completed {
+                    habits[index].completions.push(timestamp.date_naive());
+                }
+            }
+        }
+
+        Ok(habits)
+    }
+}
+
+fn column_index(headers: &csv::StringRecord, name: &str) -> Result<usize, String> {
+    headers
+        .iter()
+        .position(|header| header == name)
+        .ok_or_else(|| format!("Missing `{}` column", name))
+}
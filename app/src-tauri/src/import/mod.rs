@@ -0,0 +1,259 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::events::{self, Operation};
+use crate::models::{self, CreateHabitCompletion, Habit};
+use crate::schema::{habit_completions, habits};
+
+mod habitica;
+mod loop_habit;
+mod streaks;
+
+pub use habitica::HabiticaImporter;
+pub use loop_habit::{LoopCsvImporter, LoopSqliteImporter};
+pub use streaks::StreaksImporter;
+
+const DEFAULT_ICON: &str = "CircleCheck";
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ImportSource {
+    LoopCsv,
+    LoopSqlite,
+    Habitica,
+    Streaks,
+}
+
+/// A habit as read from another app, before it is matched against the
+/// habits already in the database.
+#[derive(Debug, Clone)]
+pub struct SourceHabit {
+    pub title: String,
+    pub description: String,
+    pub is_archived: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub completions: Vec<NaiveDate>,
+}
+
+/// Reads the habits and check-ins of one source format.
+pub trait HabitImporter {
+    fn read(&self, path: &Path) -> Result<Vec<SourceHabit>, String>;
+}
+
+pub fn importer_for(source: ImportSource) -> Box<dyn HabitImporter> {
+    match source {
+        ImportSource::LoopCsv => Box::new(LoopCsvImporter),
+        ImportSource::LoopSqlite => Box::new(LoopSqliteImporter),
+        ImportSource::Habitica => Box::new(HabiticaImporter),
+        ImportSource::Streaks => Box::new(StreaksImporter),
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ImportAction {
+    Create,
+    Merge,
+    Skip,
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedHabitReport {
+    pub title: String,
+    pub action: ImportAction,
+    pub reason: Option<String>,
+    pub completions_to_create: i32,
+    pub completions_skipped: i32,
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub source: ImportSource,
+    pub habits_to_create: i32,
+    pub habits_merged: i32,
+    pub habits_skipped: i32,
+    pub completions_to_create: i32,
+    pub completions_skipped: i32,
+    pub habits: Vec<ImportedHabitReport>,
+}
+
+/// The rows an import would write, together with the report shown to the
+/// user before anything is committed.
+pub struct ImportPlan {
+    pub habits: Vec<Habit>,
+    pub completions: Vec<CreateHabitCompletion>,
+    pub report: ImportReport,
+}
+
+/// Matches `source_habits` against the database without writing anything.
+///
+/// Habits whose title matches an existing habit (case-insensitively) are
+/// merged into it, and check-ins on days that are already completed are
+/// skipped, so running the same import twice creates nothing the second time.
+pub fn plan_import(
+    connection: &mut SqliteConnection,
+    source: ImportSource,
+    source_habits: Vec<SourceHabit>,
+) -> Result<ImportPlan, String> {
+    let existing_habits = habits::table
+        .load::<Habit>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let mut habit_ids_by_title: HashMap<String, String> = existing_habits
+        .iter()
+        .map(|habit| (normalize_title(&habit.title), habit.id.clone()))
+        .collect();
+
+    let mut completed_days: HashMap<String, HashSet<NaiveDate>> = HashMap::new();
+    for (habit_id, created_at) in habit_completions::table
+        .select((habit_completions::habit_id, habit_completions::created_at))
+        .load::<(String, NaiveDate)>(connection)
+        .map_err(|e| e.to_string())?
+    {
+        completed_days
+            .entry(habit_id)
+            .or_default()
+            .insert(created_at);
+    }
+
+    let mut plan = ImportPlan {
+        habits: Vec::new(),
+        completions: Vec::new(),
+        report: ImportReport {
+            source,
+            habits_to_create: 0,
+            habits_merged: 0,
+            habits_skipped: 0,
+            completions_to_create: 0,
+            completions_skipped: 0,
+            habits: Vec::new(),
+        },
+    };
+
+    for source_habit in source_habits {
+        let title = source_habit.title.trim().to_string();
+
+        if title.is_empty() {
+            plan.report.habits_skipped += 1;
+            plan.report.completions_skipped += source_habit.completions.len() as i32;
+            plan.report.habits.push(ImportedHabitReport {
+                title,
+                action: ImportAction::Skip,
+                reason: Some("Habit has no title".to_string()),
+                completions_to_create: 0,
+                completions_skipped: source_habit.completions.len() as i32,
+            });
+            continue;
+        }
+
+        let (habit_id, action) = match habit_ids_by_title.get(&normalize_title(&title)) {
+            Some(habit_id) => {
+                plan.report.habits_merged += 1;
+                (habit_id.clone(), ImportAction::Merge)
+            }
+            None => {
+                let now = models::now();
+                let created_at = source_habit
+                    .created_at
+                    .map(|created_at| created_at.and_utc())
+                    .unwrap_or(now);
+
+                let habit = Habit {
+                    id: Uuid::new_v4().to_string(),
+                    title: title.clone(),
+                    description: source_habit.description.clone(),
+                    icon: DEFAULT_ICON.to_string(),
+                    is_archived: source_habit.is_archived,
+                    updated_at: now,
+                    created_at,
+                };
+
+                habit_ids_by_title.insert(normalize_title(&title), habit.id.clone());
+                plan.report.habits_to_create += 1;
+                let habit_id = habit.id.clone();
+                plan.habits.push(habit);
+                (habit_id, ImportAction::Create)
+            }
+        };
+
+        let days = completed_days.entry(habit_id.clone()).or_default();
+        let unique_days: BTreeSet<NaiveDate> = source_habit.completions.iter().copied().collect();
+        let mut completions_to_create = 0;
+        let mut completions_skipped = (source_habit.completions.len() - unique_days.len()) as i32;
+
+        for day in unique_days {
+            if !days.insert(day) {
+                completions_skipped += 1;
+                continue;
+            }
+
+            plan.completions.push(CreateHabitCompletion {
+                id: Uuid::new_v4().to_string(),
+                habit_id: habit_id.clone(),
+                created_at: day,
+            });
+            completions_to_create += 1;
+        }
+
+        plan.report.completions_to_create += completions_to_create;
+        plan.report.completions_skipped += completions_skipped;
+        plan.report.habits.push(ImportedHabitReport {
+            title,
+            action,
+            reason: None,
+            completions_to_create,
+            completions_skipped,
+        });
+    }
+
+    Ok(plan)
+}
+
+/// Writes a plan produced by [`plan_import`] in a single transaction.
+pub fn commit_import(connection: &mut SqliteConnection, plan: &ImportPlan) -> Result<(), String> {
+    connection
+        .transaction::<_, diesel::result::Error, _>(|connection| {
+            diesel::insert_into(habits::table)
+                .values(&plan.habits)
+                .execute(connection)?;
+            diesel::insert_into(habit_completions::table)
+                .values(&plan.completions)
+                .execute(connection)?;
+
+            for habit in &plan.habits {
+                events::record(
+                    connection,
+                    events::HABITS,
+                    &habit.id,
+                    Operation::Create,
+                    None,
+                    Some(habit),
+                )?;
+            }
+            for completion in &plan.completions {
+                events::record(
+                    connection,
+                    events::HABIT_COMPLETIONS,
+                    &completion.id,
+                    Operation::Create,
+                    None,
+                    Some(completion),
+                )?;
+            }
+
+            Ok(())
+        })
+        .map_err(|e| e.to_string())
+}
+
+fn normalize_title(title: &str) -> String {
+    title.trim().to_lowercase()
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use super::{HabitImporter, SourceHabit};
+
+/// Reads the CSV export of Streaks, which has one row per entry and repeats
+/// the task title on each row.
+pub struct StreaksImporter;
+
+impl HabitImporter for StreaksImporter {
+    fn read(&self, path: &Path) -> Result<Vec<SourceHabit>, String> {
+        let mut reader = csv::Reader::from_path(path).map_err(|e| e.to_string())?;
+        let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+        let find = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|header| names.contains(&header.trim().to_lowercase().as_str()))
+        };
+        let title_index = find(&["title", "task_title"]).ok_or("Missing `title` column")?;
+        let date_index = find(&["entry_date", "date"]).ok_or("Missing `entry_date` column")?;
+        let type_index = find(&["entry_type"]);
+
+        let mut habits: Vec<SourceHabit> = Vec::new();
+
+        for record in reader.records() {
+            let record = record.map_err(|e| e.to_string())?;
+            let title = record.get(title_index).unwrap_or_default().trim();
+
+            let index = match habits.iter().position(|habit| habit.title == title) {
+                Some(index) => index,
+                None => {
+                    habits.push(SourceHabit {
+                        title: title.to_string(),
+                        description: String::new(),
+                        is_archived: false,
+                        created_at: None,
+                        completions: Vec::new(),
+                    });
+                    habits.len() - 1
+                }
+            };
+
+            // Missed and skipped days are exported as entries too.
+            let completed = type_index
+                .and_then(|index| record.get(index))
+                .map(|entry_type| entry_type.starts_with("completed"))
+                .unwrap_or(true);
+
+            if let Some(date) = record.get(date_index).and_then(parse_date) {
+                if completed {
+                    habits[index].completions.push(date);
+                }
+            }
+        }
+
+        Ok(habits)
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d"))
+        .ok()
+}
@@ -1,763 +1,1742 @@
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-use chrono::{Duration, NaiveDate, Utc};
-use diesel::prelude::*;
+use chrono::{Local, Utc};
+use chrono_tz::Tz;
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 use diesel::sqlite::SqliteConnection;
-use models::{CreateHabitCompletion, Habit, UpdateHabit};
-use reqwest;
-use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use models::{Habit, Status};
+use tauri::{Emitter, Listener, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Notify;
+
+use error::AppError;
+use repository::{
+    CompleteSessionArgs, CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs,
+    CreatePresetArgs, CreateSessionArgs, GetHabitCompletionsArgs,
+    GetPomodoroCycleWithRelationships, HabitRepository, PomodoroRepository, PresetRepository,
+    StartCycleArgs, UpdateCycleArgs, UpdateHabitArgs, UpdatePresetArgs,
+};
+use validation::{Validate, Validator};
+
+/// Declares modules that are private to the app. The integration tests in
+/// `tests/` enable the `test-util` feature, which makes them public.
+macro_rules! internal_modules {
+    ($($module:ident),* $(,)?) => {
+        $(
+            #[cfg(feature = "test-util")]
+            pub mod $module;
+            #[cfg(not(feature = "test-util"))]
+            mod $module;
+        )*
+    };
+}
 
+pub mod cli;
 mod db;
-mod models;
+mod encryption;
 mod schema;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+mod tray;
+
+internal_modules!(
+    automation,
+    backup,
+    changes,
+    config,
+    entitlements,
+    error,
+    events,
+    export,
+    idle,
+    import,
+    license,
+    models,
+    notifications,
+    reminders,
+    repository,
+    settings,
+    shortcuts,
+    sync,
+    timer,
+    undo,
+    validation,
+    webhooks,
+);
 
 // --------------------------------------------------------------------------
-// License key
+// Pomodoro
 // --------------------------------------------------------------------------
+
 #[taurpc::ipc_type]
 #[derive(Debug)]
 #[serde(rename_all = "camelCase")]
-struct ActivateLicenseKeyArgs {
-    license_key: String,
-    instance_name: String,
+struct GetPomodoroCyclesArgs {
+    status: Status,
+    limit: Option<i32>,
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct ActivateLicenseKeyResponse {
-    is_activated: bool,
-    instance_id: String,
+impl Validate for GetPomodoroCyclesArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        if let Some(limit) = self.limit {
+            validator.range("limit", limit, 1, i32::MAX);
+        }
+        validator.finish()
+    }
 }
 
 #[taurpc::ipc_type]
 #[derive(Debug)]
 #[serde(rename_all = "camelCase")]
-struct ValidateLicenseKeyArgs {
-    license_key: String,
-    instance_id: String,
+struct GetTotalFocusTimeArgs {
+    start_date: String,
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct ValidateLicenseKeyResponse {
-    is_valid: bool,
+impl Validate for GetTotalFocusTimeArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.date("startDate", &self.start_date);
+        validator.finish()
+    }
 }
 
+// --------------------------------------------------------------------------
+// Export
+// --------------------------------------------------------------------------
+
 #[taurpc::ipc_type]
 #[derive(Debug)]
 #[serde(rename_all = "camelCase")]
-struct DeactivateLicenseKeyArgs {
-    license_key: String,
-    instance_id: String,
+pub struct ExportArgs {
+    pub start_date: String,
+    pub end_date: String,
+    pub path: String,
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct DeactivateLicenseKeyResponse {
-    is_deactivated: bool,
+impl Validate for ExportArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        let start = validator.date("startDate", &self.start_date);
+        let end = validator.date("endDate", &self.end_date);
+        if let (Some(start), Some(end)) = (start, end) {
+            if end < start {
+                validator.error("endDate", "must not be before the start date");
+            }
+        }
+        validator.not_empty("path", &self.path).finish()
+    }
 }
 
 // --------------------------------------------------------------------------
-// Habits
+// Import
 // --------------------------------------------------------------------------
 
 #[taurpc::ipc_type]
 #[derive(Debug)]
 #[serde(rename_all = "camelCase")]
-struct CreateHabitArgs {
-    title: String,
-    description: String,
-    icon: String,
+pub struct ImportArgs {
+    pub source: import::ImportSource,
+    pub path: String,
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct UpdateHabitArgs {
-    id: String,
-    title: Option<String>,
-    description: Option<String>,
-    icon: Option<String>,
-    is_archived: Option<bool>,
+impl Validate for ImportArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new().not_empty("path", &self.path).finish()
+    }
 }
 
 // --------------------------------------------------------------------------
-// Habit completions
+// Backups
 // --------------------------------------------------------------------------
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct CreateHabitCompletionArgs {
-    id: Option<String>,
-    habit_id: String,
-    created_at: Option<String>,
-}
+const POOL_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+const DAILY_BACKUP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+// --------------------------------------------------------------------------
+// License key
+// --------------------------------------------------------------------------
+
+const LICENSE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+// --------------------------------------------------------------------------
+// Webhooks
+// --------------------------------------------------------------------------
+
+const WEBHOOK_DELIVERY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+const WEBHOOK_DELIVERIES_LIMIT: i64 = 50;
+
+// --------------------------------------------------------------------------
+// Changes
+// --------------------------------------------------------------------------
+
+/// Longest a change made outside the app, like by the CLI, takes to reach the
+/// windows. Changes made by the app are read right away.
+const CHANGE_FEED_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+/// Emitted after habits or their completions changed, for views outside the
+/// window to reload.
+const HABITS_CHANGED_EVENT: &str = "habits-changed";
+
+// --------------------------------------------------------------------------
+// Reminders
+// --------------------------------------------------------------------------
+
+/// Emitted with a `reminders::DueReminder` when a habit reminder fires.
+const HABIT_REMINDER_EVENT: &str = "habit-reminder";
+/// Longest the reminder scheduler sleeps. Timers don't advance while the
+/// computer is asleep on every platform, so the next fire time is recomputed
+/// from the clock at least this often.
+const REMINDER_RECHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// --------------------------------------------------------------------------
+// Shortcuts
+// --------------------------------------------------------------------------
+
+/// Label of the small window the `LogHabit` shortcut opens.
+const QUICK_LOG_WINDOW: &str = "quick-log";
+
+// --------------------------------------------------------------------------
+// Pomodoro timer
+// --------------------------------------------------------------------------
+
+const PHASE_END_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+// --------------------------------------------------------------------------
+// Idle
+// --------------------------------------------------------------------------
+
+const IDLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+/// Emitted with an `idle::Interruption` when the user is back, to ask whether
+/// to keep the time.
+const IDLE_RETURNED_EVENT: &str = "idle-returned";
+
+// --------------------------------------------------------------------------
+// Encryption
+// --------------------------------------------------------------------------
 
 #[taurpc::ipc_type]
 #[derive(Debug)]
 #[serde(rename_all = "camelCase")]
-struct GetHabitCompletionsArgs {
-    habit_id: String,
-    limit: Option<i32>,
+pub struct EncryptionStatus {
+    pub is_encrypted: bool,
+    pub is_unlocked: bool,
 }
 
-// --------------------------------------------------------------------------
-// Pomodoro
-// --------------------------------------------------------------------------
+#[taurpc::procedures(event_trigger = ApiEventTrigger, export_to = "../src/bindings.ts")]
+pub trait Api {
+    // Events
+    /// Data changed in the app, the CLI or a background task.
+    #[taurpc(event)]
+    async fn data_changed(change: changes::DataChange);
+
+    // License key
+    async fn get_license() -> Result<Option<license::License>, AppError>;
+    async fn get_license_status() -> Result<license::LicenseStatusReport, AppError>;
+    async fn activate_license_key(
+        args: license::ActivateLicenseKeyArgs,
+    ) -> Result<license::License, AppError>;
+    async fn validate_license_key() -> Result<license::License, AppError>;
+    /// Forgets the license on this device even if the license server can't
+    /// release it, the server's error is returned as a warning then.
+    async fn deactivate_license_key() -> Result<Option<String>, AppError>;
+    async fn get_entitlements() -> Result<entitlements::Entitlements, AppError>;
 
-#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-enum Status {
-    Completed,
-    InProgress,
-    Abandoned,
+    // Habits
+    async fn create_habit(args: CreateHabitArgs) -> Result<(), AppError>;
+    async fn get_active_habits() -> Result<Vec<Habit>, AppError>;
+    async fn get_archived_habits() -> Result<Vec<Habit>, AppError>;
+    async fn update_habit(args: UpdateHabitArgs) -> Result<(), AppError>;
+    async fn delete_habit(habit_id: String) -> Result<(), AppError>;
+
+    // Habit completions
+    async fn toggle_habit_completion(args: CreateHabitCompletionArgs) -> Result<(), AppError>;
+    async fn get_habit_completions(
+        args: GetHabitCompletionsArgs,
+    ) -> Result<Vec<models::HabitCompletion>, AppError>;
+    async fn get_habit_completion_streak(habit_id: String) -> Result<i32, AppError>;
+
+    // Pomodoro
+    async fn start_pomodoro_cycle(args: StartCycleArgs) -> Result<models::PomodoroCycle, AppError>;
+    async fn get_current_cycle() -> Result<Option<GetPomodoroCycleWithRelationships>, AppError>;
+    async fn update_cycle_status(args: UpdateCycleArgs) -> Result<models::PomodoroCycle, AppError>;
+    async fn start_session(args: CreateSessionArgs) -> Result<models::PomodoroSession, AppError>;
+    async fn complete_session(
+        args: CompleteSessionArgs,
+    ) -> Result<models::PomodoroSession, AppError>;
+    async fn get_daily_stats(date: String) -> Result<i32, AppError>;
+
+    // Pomodoro presets
+    async fn get_presets() -> Result<Vec<models::PomodoroPreset>, AppError>;
+    async fn create_preset(args: CreatePresetArgs) -> Result<models::PomodoroPreset, AppError>;
+    async fn update_preset(args: UpdatePresetArgs) -> Result<models::PomodoroPreset, AppError>;
+    async fn delete_preset(preset_id: String) -> Result<(), AppError>;
+    async fn set_default_preset(preset_id: String) -> Result<(), AppError>;
+
+    // Export
+    async fn export_habit_completions(args: ExportArgs) -> Result<i32, AppError>;
+    async fn export_pomodoro_sessions(args: ExportArgs) -> Result<i32, AppError>;
+
+    // Import
+    async fn preview_import(args: ImportArgs) -> Result<import::ImportReport, AppError>;
+    async fn import_habits(args: ImportArgs) -> Result<import::ImportReport, AppError>;
+
+    // Backups
+    async fn list_backups() -> Result<Vec<backup::BackupInfo>, AppError>;
+    async fn restore_backup(file_name: String) -> Result<(), AppError>;
+    async fn get_backup_retention() -> backup::BackupRetention;
+    async fn update_backup_retention(retention: backup::BackupRetention) -> Result<(), AppError>;
+
+    // Encryption
+    async fn get_encryption_status() -> EncryptionStatus;
+    async fn unlock_database(passphrase: String) -> Result<(), AppError>;
+    async fn enable_encryption(passphrase: String) -> Result<(), AppError>;
+
+    // Settings
+    async fn get_settings() -> Result<settings::Settings, AppError>;
+    async fn update_settings(
+        new_settings: settings::Settings,
+    ) -> Result<settings::Settings, AppError>;
+    async fn regenerate_automation_token() -> Result<settings::Settings, AppError>;
+    /// Shortcuts from the settings that couldn't be registered.
+    async fn get_shortcut_errors() -> Vec<shortcuts::ShortcutError>;
+
+    // History
+    async fn get_history(entity_id: String) -> Result<Vec<models::Event>, AppError>;
+    async fn undo() -> Result<Option<String>, AppError>;
+    async fn redo() -> Result<Option<String>, AppError>;
+
+    // Sync
+    async fn get_sync_config() -> Result<Option<sync::SyncBackendConfig>, AppError>;
+    async fn update_sync_config(config: Option<sync::SyncBackendConfig>) -> Result<(), AppError>;
+    async fn sync_now() -> Result<sync::SyncReport, AppError>;
+
+    // Webhooks
+    async fn get_webhooks() -> Result<Vec<webhooks::Webhook>, AppError>;
+    async fn create_webhook(
+        args: webhooks::CreateWebhookArgs,
+    ) -> Result<webhooks::Webhook, AppError>;
+    async fn update_webhook(
+        args: webhooks::UpdateWebhookArgs,
+    ) -> Result<webhooks::Webhook, AppError>;
+    async fn delete_webhook(webhook_id: String) -> Result<(), AppError>;
+    async fn get_webhook_deliveries(
+        webhook_id: String,
+    ) -> Result<Vec<webhooks::WebhookDelivery>, AppError>;
+    async fn test_webhook(webhook_id: String) -> Result<webhooks::WebhookDelivery, AppError>;
+
+    // Reminders
+    async fn get_habit_reminders(
+        habit_id: String,
+    ) -> Result<Vec<reminders::HabitReminder>, AppError>;
+    async fn create_habit_reminder(
+        args: reminders::CreateHabitReminderArgs,
+    ) -> Result<reminders::HabitReminder, AppError>;
+    async fn update_habit_reminder(
+        args: reminders::UpdateHabitReminderArgs,
+    ) -> Result<reminders::HabitReminder, AppError>;
+    async fn delete_habit_reminder(reminder_id: String) -> Result<(), AppError>;
+
+    // Idle
+    async fn get_unresolved_interruptions() -> Result<Vec<idle::Interruption>, AppError>;
+    async fn resolve_interruption(
+        args: idle::ResolveInterruptionArgs,
+    ) -> Result<idle::Interruption, AppError>;
 }
 
-impl Status {
-    fn from_str(s: &str) -> Self {
-        match s {
-            "COMPLETED" => Status::Completed,
-            "IN_PROGRESS" => Status::InProgress,
-            "ABANDONED" => Status::Abandoned,
-            _ => Status::InProgress, // Default to in progress instead of unrecognized
+#[derive(Clone)]
+pub struct ApiImpl {
+    pool: Arc<Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>>,
+    data_dir: Arc<Mutex<Option<PathBuf>>>,
+    key: Arc<Mutex<Option<encryption::DatabaseKey>>>,
+    undo_stack: Arc<Mutex<undo::UndoStack>>,
+    automation: Arc<automation::AutomationServer>,
+    license_client: license::LicenseClient,
+    /// Wakes the reminder scheduler after reminders were edited.
+    reminders_changed: Arc<Notify>,
+    /// Wakes the change feed after the app mutated data.
+    changes_recorded: Arc<Notify>,
+    /// Reset when the history is replaced, to start over at its end.
+    change_feed: Arc<Mutex<Option<changes::ChangeFeed>>>,
+    notifier: Arc<Mutex<Option<Arc<dyn notifications::Notifier>>>>,
+    app_handle: Arc<Mutex<Option<tauri::AppHandle>>>,
+    /// Last state reported by the pomodoro timer in the window.
+    timer_state: Arc<Mutex<Option<timer::TimerState>>>,
+    phase_clock: Arc<Mutex<timer::PhaseClock>>,
+    /// Session whose end was notified before the window completed it.
+    notified_session: Arc<Mutex<Option<models::PomodoroSession>>>,
+    shortcut_registry: Arc<Mutex<Option<Arc<dyn shortcuts::ShortcutRegistry>>>>,
+    shortcut_errors: Arc<Mutex<Vec<shortcuts::ShortcutError>>>,
+    secrets: Arc<Mutex<Option<Arc<dyn sync::SecretStore>>>>,
+}
+
+impl ApiImpl {
+    /// The API before startup, with the database not opened yet.
+    fn new(license_client: license::LicenseClient) -> Self {
+        ApiImpl {
+            pool: Arc::new(Mutex::new(None)),
+            data_dir: Arc::new(Mutex::new(None)),
+            key: Arc::new(Mutex::new(None)),
+            undo_stack: Arc::new(Mutex::new(undo::UndoStack::default())),
+            automation: Arc::new(automation::AutomationServer::default()),
+            license_client,
+            reminders_changed: Arc::new(Notify::new()),
+            changes_recorded: Arc::new(Notify::new()),
+            change_feed: Arc::new(Mutex::new(None)),
+            notifier: Arc::new(Mutex::new(None)),
+            app_handle: Arc::new(Mutex::new(None)),
+            timer_state: Arc::new(Mutex::new(None)),
+            phase_clock: Arc::new(Mutex::new(timer::PhaseClock::default())),
+            notified_session: Arc::new(Mutex::new(None)),
+            shortcut_registry: Arc::new(Mutex::new(None)),
+            shortcut_errors: Arc::new(Mutex::new(Vec::new())),
+            secrets: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Checks out a connection, which fails while the database is locked.
+    fn get_connection(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, AppError> {
+        let pool = self.pool.lock().unwrap();
+        let pool = pool.as_ref().ok_or("Database is locked")?;
+
+        pool.get()
+            .map_err(|e| format!("Failed to get a connection from the pool: {}", e).into())
+    }
+
+    fn get_data_dir(&self) -> PathBuf {
+        self.data_dir
+            .lock()
+            .unwrap()
+            .clone()
+            .expect("Data directory has not been set up")
+    }
+
+    fn get_notifier(&self) -> Arc<dyn notifications::Notifier> {
+        self.notifier
+            .lock()
+            .unwrap()
+            .clone()
+            .expect("Notifier has not been set up")
+    }
+
+    fn get_secrets(&self) -> Arc<dyn sync::SecretStore> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .clone()
+            .expect("Secret store has not been set up")
+    }
+
+    fn get_key(&self) -> Option<encryption::DatabaseKey> {
+        self.key.lock().unwrap().clone()
+    }
+
+    /// Tells the windows about the mutations recorded in the history.
+    fn data_changed(&self) {
+        self.changes_recorded.notify_one();
+    }
+
+    /// Tells the windows to reload everything, after data changed without
+    /// history or the history itself was replaced.
+    fn data_reloaded(&self) {
+        *self.change_feed.lock().unwrap() = None;
+        if let Some(app) = self.app_handle.lock().unwrap().as_ref() {
+            emit_changes(app, &[changes::DataChange::DataReloaded]);
+        }
+    }
+
+    fn push_undo(&self, description: String, event: models::Event) {
+        self.undo_stack
+            .lock()
+            .unwrap()
+            .push(description, vec![event]);
+    }
+
+    /// Follows a state reported by the timer in the window.
+    fn timer_state_changed(&self, state: Option<timer::TimerState>) {
+        self.phase_clock
+            .lock()
+            .unwrap()
+            .update(state.clone(), models::now());
+        *self.timer_state.lock().unwrap() = state;
+    }
+
+    /// Notifies of the end of the current phase once its time is up, as the
+    /// timer of a hidden window may only catch up later.
+    fn notify_phase_end(&self) {
+        let now = models::now();
+        let Some(session_type) = self.phase_clock.lock().unwrap().take_ended(now) else {
+            return;
+        };
+
+        let result = self.get_connection().and_then(|mut connection| {
+            notifications::notify_phase_ended(
+                &mut connection,
+                self.get_notifier().as_ref(),
+                session_type,
+                now,
+            )
+        });
+        match result {
+            Ok(Some(session)) => *self.notified_session.lock().unwrap() = Some(session),
+            Ok(None) => {}
+            Err(e) => println!("Failed to notify of the ended phase: {}", e),
+        }
+    }
+
+    /// Whether the end of `session` was notified already.
+    fn was_notified(&self, session_id: &str) -> bool {
+        self.notified_session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|session| session.id == session_id)
+    }
+
+    /// Whether the end of `cycle_id`, at the end of its long break, was
+    /// notified already.
+    fn was_cycle_notified(&self, cycle_id: &str) -> bool {
+        self.notified_session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|session| {
+                session.cycle_id == cycle_id
+                    && session.session_type == models::SessionType::LongBreak
+            })
+    }
+
+    /// Brings the automation server in line with the stored settings. The
+    /// database must be unlocked.
+    fn apply_automation_settings(&self) {
+        let settings = self
+            .get_connection()
+            .and_then(|mut connection| Ok(settings::load(&mut connection)?));
+
+        match settings {
+            Ok(settings) => self.automation.apply(&settings.automation, self.clone()),
+            Err(e) => println!("Failed to load automation settings: {}", e),
         }
     }
-    fn to_str(&self) -> &str {
-        match self {
-            Status::Completed => "COMPLETED",
-            Status::InProgress => "IN_PROGRESS",
-            Status::Abandoned => "ABANDONED",
+
+    /// Registers the stored shortcuts. The database must be unlocked.
+    fn apply_shortcut_settings(&self) {
+        let settings = self
+            .get_connection()
+            .and_then(|mut connection| Ok(settings::load(&mut connection)?));
+
+        match settings {
+            Ok(settings) => self.register_shortcuts(&settings.shortcuts),
+            Err(e) => println!("Failed to load shortcut settings: {}", e),
+        }
+    }
+
+    fn register_shortcuts(&self, settings: &settings::ShortcutSettings) {
+        let registry = self.shortcut_registry.lock().unwrap().clone();
+        if let Some(registry) = registry {
+            *self.shortcut_errors.lock().unwrap() =
+                shortcuts::register(registry.as_ref(), settings);
         }
     }
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct GetPomodoroCycleWithRelationships {
-    id: String,
-    status: Status,
-    focus_duration: i32,
-    short_break_duration: i32,
-    long_break_duration: i32,
-    sessions_until_long_break: i32,
-    auto_start_breaks: bool,
-    auto_start_pomodoros: bool,
-    started_at: String,
-    completed_at: Option<String>,
-    updated_at: String,
-    sessions: Vec<models::PomodoroSession>,
+fn create_daily_backup(
+    pool: &Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>,
+    key: &Mutex<Option<encryption::DatabaseKey>>,
+    data_dir: &Path,
+) {
+    // Holding the pool lock keeps a restore from replacing the file mid-backup
+    let pool = pool.lock().unwrap();
+
+    // A locked database cannot be read until the user enters the passphrase
+    if pool.is_none() {
+        return;
+    }
+
+    let key = key.lock().unwrap().clone();
+    if let Err(e) =
+        backup::create_daily_backup_if_due(&db::database_path(data_dir), data_dir, key.as_ref())
+    {
+        println!("Failed to create daily backup: {}", e);
+    }
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct GetPomodoroCyclesArgs {
-    status: Status,
-    limit: Option<i32>,
+async fn revalidate_license(
+    pool: &Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>,
+    client: &license::LicenseClient,
+) {
+    // Neither the pool lock nor a connection is held while waiting for the
+    // license server
+    let connect = || {
+        pool.lock()
+            .unwrap()
+            .as_ref()
+            .map(|pool| pool.get().map_err(|e| AppError::from(e.to_string())))
+    };
+
+    let due = match connect() {
+        Some(connection) => connection
+            .and_then(|mut connection| Ok(license::due_for_revalidation(&mut connection)?)),
+        None => return,
+    };
+    let result = match due {
+        Ok(Some(value)) => {
+            let response = license::validate(client, &value).await;
+            match connect() {
+                Some(connection) => connection.and_then(|mut connection| {
+                    license::store_validation(&mut connection, value, response)
+                }),
+                None => return,
+            }
+        }
+        Ok(None) => return,
+        Err(e) => Err(e),
+    };
+    match result {
+        // Offline, the grace period covers this until the next check
+        Ok(_)
+        | Err(AppError::License {
+            reason: license::LicenseErrorReason::Unreachable,
+            ..
+        }) => {}
+        Err(e) => println!("Failed to revalidate the license: {}", e),
+    }
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct StartCycleArgs {
-    focus_duration: i32,
-    short_break_duration: i32,
-    long_break_duration: i32,
-    sessions_until_long_break: i32,
-    auto_start_breaks: bool,
-    auto_start_pomodoros: bool,
+fn deliver_webhooks(pool: &Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>) {
+    // Only hold the pool lock long enough to check out a connection
+    let connection = match pool.lock().unwrap().as_ref() {
+        Some(pool) => pool.get(),
+        None => return,
+    };
+
+    let result = connection
+        .map_err(|e| e.to_string())
+        .and_then(|mut connection| {
+            let client = webhooks::client()?;
+            webhooks::deliver_due(&mut connection, &client)
+        });
+    if let Err(e) = result {
+        println!("Failed to deliver webhooks: {}", e);
+    }
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct UpdateCycleArgs {
-    id: String,
-    status: Status,
+/// Shows notifications with the notification plugin.
+struct TauriNotifier(tauri::AppHandle);
+
+impl notifications::Notifier for TauriNotifier {
+    fn notify(&self, notification: notifications::Notification) -> Result<(), String> {
+        let mut builder = self
+            .0
+            .notification()
+            .builder()
+            .title(notification.title)
+            .body(notification.body);
+        if notification.sound {
+            builder = builder.sound("default");
+        }
+        // Only shown as a button where the platform supports actions
+        if let Some(action) = notification.action {
+            builder = builder.action_type_id(action.id());
+        }
+
+        builder.show().map_err(|e| e.to_string())
+    }
 }
 
-#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-enum SessionType {
-    LongBreak,
-    Focus,
-    ShortBreak,
+/// Lets phase end notifications offer to start the next phase. Clicks come
+/// back to the window, which turns them into timer commands.
+#[cfg(mobile)]
+fn register_notification_actions(app: &tauri::AppHandle) -> Result<(), String> {
+    let action_types =
+        serde_json::from_value(notifications::action_types()).map_err(|e| e.to_string())?;
+
+    app.notification()
+        .register_action_types(action_types)
+        .map_err(|e| e.to_string())
 }
 
-impl SessionType {
-    fn from_str(s: &str) -> Self {
-        match s {
-            "LONG_BREAK" => SessionType::LongBreak,
-            "FOCUS" => SessionType::Focus,
-            "SHORT_BREAK" => SessionType::ShortBreak,
-            _ => SessionType::Focus, // Default to focus instead of unrecognized
-        }
+/// Registers shortcuts with the global shortcut plugin.
+struct TauriShortcuts {
+    app: tauri::AppHandle,
+    timer_state: Arc<Mutex<Option<timer::TimerState>>>,
+}
+
+impl shortcuts::ShortcutRegistry for TauriShortcuts {
+    fn unregister_all(&self) -> Result<(), String> {
+        self.app
+            .global_shortcut()
+            .unregister_all()
+            .map_err(|e| e.to_string())
+    }
+
+    fn register(
+        &self,
+        shortcut: &shortcuts::Shortcut,
+        action: shortcuts::ShortcutAction,
+    ) -> Result<(), String> {
+        let timer_state = self.timer_state.clone();
+
+        self.app
+            .global_shortcut()
+            .on_shortcut(shortcut.to_string().as_str(), move |app, _, event| {
+                if event.state() == ShortcutState::Pressed {
+                    run_shortcut(app, action, &timer_state);
+                }
+            })
+            .map_err(|e| e.to_string())
     }
-    fn to_str(&self) -> &str {
-        match self {
-            SessionType::LongBreak => "LONG_BREAK",
-            SessionType::Focus => "FOCUS",
-            SessionType::ShortBreak => "SHORT_BREAK",
+}
+
+fn run_shortcut(
+    app: &tauri::AppHandle,
+    action: shortcuts::ShortcutAction,
+    timer_state: &Mutex<Option<timer::TimerState>>,
+) {
+    let command = match action {
+        shortcuts::ShortcutAction::StartPause => {
+            timer::TimerCommand::start_or_pause(timer_state.lock().unwrap().as_ref())
+        }
+        shortcuts::ShortcutAction::Skip => timer::TimerCommand::Skip,
+        shortcuts::ShortcutAction::LogHabit => {
+            if let Err(e) = open_quick_log_window(app) {
+                println!("Failed to open the quick log window: {}", e);
+            }
+            return;
         }
+    };
+
+    if let Err(e) = app.emit(timer::TIMER_COMMAND_EVENT, command) {
+        println!("Failed to send the timer command: {}", e);
     }
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct CreateSessionArgs {
-    cycle_id: String,
-    session_type: SessionType,
-    duration_minutes: i32,
+/// Brings up the quick log window, opening it if needed.
+fn open_quick_log_window(app: &tauri::AppHandle) -> tauri::Result<()> {
+    let window = match app.get_webview_window(QUICK_LOG_WINDOW) {
+        Some(window) => window,
+        None => tauri::WebviewWindowBuilder::new(
+            app,
+            QUICK_LOG_WINDOW,
+            tauri::WebviewUrl::App("quick-log".into()),
+        )
+        .title("Log habit")
+        .inner_size(360.0, 480.0)
+        .resizable(false)
+        .always_on_top(true)
+        .build()?,
+    };
+
+    window.show()?;
+    window.set_focus()
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct GetTotalFocusTimeArgs {
-    start_date: String,
+/// Emits the changes recorded since the last call. The feed starts at the
+/// end of the history once the database is unlocked.
+fn publish_recorded_changes(
+    app: &tauri::AppHandle,
+    pool: &Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>,
+    change_feed: &Mutex<Option<changes::ChangeFeed>>,
+) {
+    let connection = match pool.lock().unwrap().as_ref() {
+        Some(pool) => pool.get(),
+        None => return,
+    };
+
+    let result = connection
+        .map_err(|e| e.to_string())
+        .and_then(|mut connection| {
+            let mut change_feed = change_feed.lock().unwrap();
+            let changes = match change_feed.as_mut() {
+                Some(feed) => feed.poll(&mut connection),
+                None => changes::ChangeFeed::new(&mut connection).map(|feed| {
+                    *change_feed = Some(feed);
+                    Vec::new()
+                }),
+            };
+            changes.map_err(|e| e.to_string())
+        });
+    match result {
+        Ok(changes) => emit_changes(app, &changes),
+        Err(e) => println!("Failed to read recorded changes: {}", e),
+    }
 }
 
-#[taurpc::ipc_type]
-#[derive(Debug)]
-#[serde(rename_all = "camelCase")]
-struct CompleteSessionArgs {
-    session_id: String,
-    was_completed: bool,
+fn emit_changes(app: &tauri::AppHandle, changes: &[changes::DataChange]) {
+    let trigger = ApiEventTrigger::new(app.clone());
+    for change in changes {
+        if let Err(e) = trigger.data_changed(change.clone()) {
+            println!("Failed to emit a data change: {}", e);
+        }
+    }
+
+    // The tray lists today's habits
+    if changes.iter().any(changes::DataChange::affects_habits) {
+        if let Err(e) = app.emit(HABITS_CHANGED_EVENT, ()) {
+            println!("Failed to emit {}: {}", HABITS_CHANGED_EVENT, e);
+        }
+    }
 }
 
-#[taurpc::procedures(export_to = "../src/bindings.ts")]
-trait Api {
-    // License key
-    async fn activate_license_key(args: ActivateLicenseKeyArgs) -> ActivateLicenseKeyResponse;
-    async fn validate_license_key(args: ValidateLicenseKeyArgs) -> ValidateLicenseKeyResponse;
-    async fn deactivate_license_key(args: DeactivateLicenseKeyArgs)
-        -> DeactivateLicenseKeyResponse;
+/// Keeps secrets in the keychain of the operating system.
+struct KeyringSecrets;
 
-    // Habits
-    async fn create_habit(args: CreateHabitArgs);
-    async fn get_active_habits() -> Vec<Habit>;
-    async fn get_archived_habits() -> Vec<Habit>;
-    async fn update_habit(args: UpdateHabitArgs);
-    async fn delete_habit(habit_id: String);
+impl KeyringSecrets {
+    const SERVICE: &'static str = "com.achievealchemy.app";
 
-    // Habit completions
-    async fn toggle_habit_completion(args: CreateHabitCompletionArgs);
-    async fn get_habit_completions(args: GetHabitCompletionsArgs) -> Vec<models::HabitCompletion>;
-    async fn get_habit_completion_streak(habit_id: String) -> i32;
+    fn entry(name: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(Self::SERVICE, name).map_err(|e| e.to_string())
+    }
+}
 
-    // Pomodoro
-    async fn start_pomodoro_cycle(args: StartCycleArgs) -> Result<models::PomodoroCycle, String>;
-    async fn get_current_cycle() -> Result<Option<GetPomodoroCycleWithRelationships>, String>;
-    async fn update_cycle_status(args: UpdateCycleArgs) -> Result<models::PomodoroCycle, String>;
-    async fn start_session(args: CreateSessionArgs) -> Result<models::PomodoroSession, String>;
-    async fn complete_session(args: CompleteSessionArgs)
-        -> Result<models::PomodoroSession, String>;
-    async fn get_daily_stats(date: String) -> Result<i32, String>;
+impl sync::SecretStore for KeyringSecrets {
+    fn get(&self, name: &str) -> Result<Option<String>, String> {
+        match Self::entry(name)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn set(&self, name: &str, secret: &str) -> Result<(), String> {
+        Self::entry(name)?
+            .set_password(secret)
+            .map_err(|e| e.to_string())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        match Self::entry(name)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
 }
 
-#[derive(Clone)]
-struct ApiImpl {
-    pool: Arc<Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>>,
+/// Reads the time since the last input from the operating system.
+struct SystemIdleSource;
+
+impl idle::IdleSource for SystemIdleSource {
+    fn idle_time(&self) -> Result<std::time::Duration, String> {
+        user_idle::UserIdle::get_time()
+            .map(|idle| idle.duration())
+            .map_err(|e| e.to_string())
+    }
 }
 
-impl ApiImpl {
-    fn get_connection(&self) -> PooledConnection<ConnectionManager<SqliteConnection>> {
-        self.pool
-            .lock()
-            .unwrap()
-            .as_ref()
-            .unwrap()
-            .get()
-            .expect("Failed to get a connection from the pool")
+/// Pauses the timer when the user went away during a focus session, and asks
+/// about the time once they are back.
+fn check_idle(
+    app: &tauri::AppHandle,
+    pool: &Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>,
+    timer_state: &Mutex<Option<timer::TimerState>>,
+) {
+    let connection = match pool.lock().unwrap().as_ref() {
+        Some(pool) => pool.get(),
+        None => return,
+    };
+
+    let timer = timer_state.lock().unwrap().clone();
+    let result = connection
+        .map_err(|e| AppError::from(e.to_string()))
+        .and_then(|mut connection| {
+            idle::check(
+                &mut connection,
+                &SystemIdleSource,
+                timer.as_ref(),
+                models::now(),
+            )
+        });
+    let emitted = match result {
+        Ok(Some(idle::IdleEvent::Paused(interruption))) => {
+            println!("Pausing the timer, idle since {}", interruption.started_at);
+            app.emit(timer::TIMER_COMMAND_EVENT, timer::TimerCommand::Pause)
+        }
+        Ok(Some(idle::IdleEvent::Returned(interruption))) => {
+            app.emit(IDLE_RETURNED_EVENT, interruption)
+        }
+        Ok(None) => Ok(()),
+        Err(e) => {
+            println!("Failed to check for idle time: {}", e);
+            Ok(())
+        }
+    };
+    if let Err(e) = emitted {
+        println!("Failed to emit an idle event: {}", e);
     }
 }
 
-const API_URL: &str = "https://test-api.creem.io/v1";
-const API_KEY: &str = "test-api-key";
+/// Fires the due reminders and returns how long to wait for the next one.
+fn fire_reminders(
+    app: &tauri::AppHandle,
+    notifier: &dyn notifications::Notifier,
+    pool: &Mutex<Option<Pool<ConnectionManager<SqliteConnection>>>>,
+) -> std::time::Duration {
+    let connection = match pool.lock().unwrap().as_ref() {
+        Some(pool) => pool.get(),
+        None => return REMINDER_RECHECK_INTERVAL,
+    };
+
+    let now = models::now();
+    let result = connection
+        .map_err(|e| AppError::from(e.to_string()))
+        .and_then(|mut connection| {
+            let settings = settings::load(&mut connection)?;
+            let check = match settings.timezone.as_deref().map(str::parse::<Tz>) {
+                Some(Ok(timezone)) => reminders::check(&mut connection, now, &timezone)?,
+                _ => reminders::check(&mut connection, now, &Local)?,
+            };
+            Ok((settings.notifications, check))
+        });
+    let (notification_settings, check) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            println!("Failed to check reminders: {}", e);
+            return REMINDER_RECHECK_INTERVAL;
+        }
+    };
+
+    if notification_settings.habit_reminders {
+        for reminder in &check.due {
+            if let Err(e) = app.emit(HABIT_REMINDER_EVENT, reminder) {
+                println!("Failed to emit a reminder: {}", e);
+            }
+            if let Some(notification) = notifications::reminder(&notification_settings, reminder) {
+                if let Err(e) = notifier.notify(notification) {
+                    println!("Failed to show a notification: {}", e);
+                }
+            }
+        }
+    }
+
+    check.next_at.map_or(REMINDER_RECHECK_INTERVAL, |next_at| {
+        (next_at - Utc::now())
+            .to_std()
+            .unwrap_or_default()
+            .min(REMINDER_RECHECK_INTERVAL)
+    })
+}
 
 #[taurpc::resolvers]
 impl Api for ApiImpl {
     // --------------------------------------------------------------------------
     // License key
     // --------------------------------------------------------------------------
+    async fn get_license(self) -> Result<Option<license::License>, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        Ok(license::load(connection)?)
+    }
+
+    async fn get_license_status(self) -> Result<license::LicenseStatusReport, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        license::load_status(connection)
+    }
+
+    // Connections are only checked out around database access, holding one
+    // while waiting for the license server would keep the pool busy.
     async fn activate_license_key(
         self,
-        args: ActivateLicenseKeyArgs,
-    ) -> ActivateLicenseKeyResponse {
-        let client = reqwest::Client::new();
-        let response = client
-            .post("https://test-api.creem.io/v1/licenses/activate")
-            .header("accept", "application/json")
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "key": args.license_key,
-                "instance_name": args.instance_name
-            }))
-            .send()
-            .await
-            .map_err(|e| e.to_string())
-            .expect("Failed to send activate request");
+        args: license::ActivateLicenseKeyArgs,
+    ) -> Result<license::License, AppError> {
+        let value = license::activate(&self.license_client, args).await?;
 
-        println!("Activate license key response: {:?}", response);
+        let connection = &mut self.get_connection()?;
+        license::store(connection, &value)?;
 
-        if response.status().is_success() {
-            ActivateLicenseKeyResponse {
-                is_activated: true,
-                instance_id: "1".to_string(),
-            }
-        } else {
-            ActivateLicenseKeyResponse {
-                is_activated: false,
-                instance_id: "1".to_string(),
-            }
+        Ok(value)
+    }
+
+    async fn validate_license_key(self) -> Result<license::License, AppError> {
+        let value = {
+            let connection = &mut self.get_connection()?;
+            license::stored(connection)?
+        };
+        let response = license::validate(&self.license_client, &value).await;
+
+        let connection = &mut self.get_connection()?;
+        license::store_validation(connection, value, response)
+    }
+
+    async fn deactivate_license_key(self) -> Result<Option<String>, AppError> {
+        let value = {
+            let connection = &mut self.get_connection()?;
+            license::stored(connection)?
+        };
+        let result = license::deactivate(&self.license_client, &value).await;
+
+        let connection = &mut self.get_connection()?;
+        license::forget(connection)?;
+
+        Ok(result.err().map(|e| e.to_string()))
+    }
+
+    async fn get_entitlements(self) -> Result<entitlements::Entitlements, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        entitlements::load(connection)
+    }
+
+    // --------------------------------------------------------------------------
+    // Habits
+    // --------------------------------------------------------------------------
+    async fn create_habit(self, args: CreateHabitArgs) -> Result<(), AppError> {
+        let connection = &mut self.get_connection()?;
+
+        entitlements::require_habit_slots(connection, 1)?;
+        connection.create_habit(args)?;
+        self.data_changed();
+
+        Ok(())
+    }
+
+    async fn get_active_habits(self) -> Result<Vec<models::Habit>, AppError> {
+        self.get_connection()?.active_habits()
+    }
+
+    async fn get_archived_habits(self) -> Result<Vec<models::Habit>, AppError> {
+        self.get_connection()?.archived_habits()
+    }
+
+    async fn update_habit(self, args: UpdateHabitArgs) -> Result<(), AppError> {
+        let connection = &mut self.get_connection()?;
+
+        let unarchives = args.is_archived == Some(false)
+            && connection
+                .archived_habits()?
+                .iter()
+                .any(|habit| habit.id == args.id);
+        if unarchives {
+            entitlements::require_habit_slots(connection, 1)?;
         }
+        let mutation = connection.update_habit(args)?;
+        self.push_undo(mutation.description, mutation.event);
+        self.data_changed();
+
+        Ok(())
     }
 
-    async fn validate_license_key(
+    async fn delete_habit(self, habit_id: String) -> Result<(), AppError> {
+        let mutation = self.get_connection()?.delete_habit(&habit_id)?;
+        self.push_undo(mutation.description, mutation.event);
+        self.data_changed();
+
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------
+    // Habit completions
+    // --------------------------------------------------------------------------
+    async fn toggle_habit_completion(
         self,
-        args: ValidateLicenseKeyArgs,
-    ) -> ValidateLicenseKeyResponse {
-        let client = reqwest::Client::new();
-        let response = client
-            .post(format!("{}/licenses/validate", API_URL))
-            .header("accept", "application/json")
-            .header("x-api-key", API_KEY)
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "key": args.license_key,
-                "instance_id": args.instance_id
-            }))
-            .send()
-            .await
-            .map_err(|e| e.to_string())
-            .expect("Failed to send validate request");
+        args: CreateHabitCompletionArgs,
+    ) -> Result<(), AppError> {
+        let mutation = self.get_connection()?.toggle_habit_completion(args)?;
+        self.push_undo(mutation.description, mutation.event);
+        self.data_changed();
 
-        if response.status().is_success() {
-            ValidateLicenseKeyResponse { is_valid: true }
-        } else {
-            ValidateLicenseKeyResponse { is_valid: false }
-        }
+        Ok(())
     }
 
-    async fn deactivate_license_key(
+    async fn get_habit_completions(
         self,
-        args: DeactivateLicenseKeyArgs,
-    ) -> DeactivateLicenseKeyResponse {
-        let client = reqwest::Client::new();
-        let response = client
-            .post(format!("{}/licenses/deactivate", API_URL))
-            .header("accept", "application/json")
-            .header("x-api-key", API_KEY)
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "key": args.license_key,
-                "instance_id": args.instance_id
-            }))
-            .send()
-            .await
-            .map_err(|e| e.to_string())
-            .expect("Failed to send deactivate request");
+        args: GetHabitCompletionsArgs,
+    ) -> Result<Vec<models::HabitCompletion>, AppError> {
+        self.get_connection()?.habit_completions(args)
+    }
 
-        if response.status().is_success() {
-            DeactivateLicenseKeyResponse {
-                is_deactivated: true,
-            }
-        } else {
-            DeactivateLicenseKeyResponse {
-                is_deactivated: false,
+    async fn get_habit_completion_streak(self, id: String) -> Result<i32, AppError> {
+        let connection = &mut self.get_connection()?;
+        let today = settings::load(connection)?.today();
+
+        connection.habit_completion_streak(&id, today)
+    }
+
+    // --------------------------------------------------------------------------
+    // Pomodoro
+    // --------------------------------------------------------------------------
+    async fn start_pomodoro_cycle(
+        self,
+        args: StartCycleArgs,
+    ) -> Result<models::PomodoroCycle, AppError> {
+        let cycle = self.get_connection()?.start_pomodoro_cycle(args)?;
+        self.data_changed();
+
+        Ok(cycle)
+    }
+
+    async fn get_current_cycle(
+        self,
+    ) -> Result<Option<GetPomodoroCycleWithRelationships>, AppError> {
+        self.get_connection()?.current_cycle()
+    }
+
+    async fn update_cycle_status(
+        self,
+        args: UpdateCycleArgs,
+    ) -> Result<models::PomodoroCycle, AppError> {
+        let connection = &mut self.get_connection()?;
+        let completed = args.status == Status::Completed;
+
+        let mutation = connection.update_cycle_status(args)?;
+        self.push_undo(mutation.description, mutation.event);
+        self.data_changed();
+
+        if completed && !self.was_cycle_notified(&mutation.value.id) {
+            if let Err(e) =
+                notifications::notify_cycle_completed(connection, self.get_notifier().as_ref())
+            {
+                println!("Failed to notify of the completed cycle: {}", e);
             }
         }
+
+        Ok(mutation.value)
+    }
+
+    async fn start_session(
+        self,
+        args: CreateSessionArgs,
+    ) -> Result<models::PomodoroSession, AppError> {
+        let session = self.get_connection()?.start_session(args)?;
+        self.data_changed();
+
+        Ok(session)
+    }
+
+    async fn complete_session(
+        self,
+        args: CompleteSessionArgs,
+    ) -> Result<models::PomodoroSession, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        let session = connection.complete_session(args)?;
+        self.data_changed();
+        if self.was_notified(&session.id) {
+            return Ok(session);
+        }
+        if let Err(e) =
+            notifications::notify_session_ended(connection, self.get_notifier().as_ref(), &session)
+        {
+            println!("Failed to notify of the ended session: {}", e);
+        }
+
+        Ok(session)
+    }
+
+    async fn get_daily_stats(self, date: String) -> Result<i32, AppError> {
+        self.get_connection()?.daily_stats(&date)
     }
 
     // --------------------------------------------------------------------------
-    // Habits
+    // Pomodoro presets
     // --------------------------------------------------------------------------
-    async fn create_habit(self, args: CreateHabitArgs) {
-        let connection = &mut self.get_connection();
-
-        let new_habit = models::Habit {
-            id: Uuid::new_v4().to_string(),
-            title: args.title,
-            description: args.description,
-            icon: args.icon,
-            is_archived: false,
-            updated_at: chrono::Utc::now().to_rfc3339(),
-            created_at: chrono::Utc::now().to_rfc3339(),
-        };
+    async fn get_presets(self) -> Result<Vec<models::PomodoroPreset>, AppError> {
+        self.get_connection()?.presets()
+    }
 
-        println!("Creating habit: {:?}", new_habit);
+    async fn create_preset(
+        self,
+        args: CreatePresetArgs,
+    ) -> Result<models::PomodoroPreset, AppError> {
+        let preset = self.get_connection()?.create_preset(args)?;
+        self.data_changed();
 
-        diesel::insert_into(schema::habits::table)
-            .values(&new_habit)
-            .execute(connection)
-            .expect("Error saving new habit");
+        Ok(preset)
     }
 
-    async fn get_active_habits(self) -> Vec<models::Habit> {
-        use crate::schema::habits::dsl::*;
+    async fn update_preset(
+        self,
+        args: UpdatePresetArgs,
+    ) -> Result<models::PomodoroPreset, AppError> {
+        let preset = self.get_connection()?.update_preset(args)?;
+        self.data_changed();
+
+        Ok(preset)
+    }
 
-        let connection = &mut self.get_connection();
+    async fn delete_preset(self, preset_id: String) -> Result<(), AppError> {
+        self.get_connection()?.delete_preset(&preset_id)?;
+        self.data_changed();
 
-        habits
-            .filter(is_archived.eq(false))
-            .order(created_at.desc())
-            .load::<models::Habit>(connection)
-            .expect("Error loading habits")
+        Ok(())
     }
 
-    async fn get_archived_habits(self) -> Vec<models::Habit> {
-        use crate::schema::habits::dsl::*;
+    async fn set_default_preset(self, preset_id: String) -> Result<(), AppError> {
+        self.get_connection()?.set_default_preset(&preset_id)?;
+        self.data_changed();
 
-        let connection = &mut self.get_connection();
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------
+    // Export
+    // --------------------------------------------------------------------------
+    async fn export_habit_completions(self, args: ExportArgs) -> Result<i32, AppError> {
+        args.validate()?;
 
-        let results = habits
-            .filter(is_archived.eq(true))
-            .order(created_at.desc())
-            .load::<models::Habit>(connection)
-            .expect("Error loading habits");
+        let connection = &mut self.get_connection()?;
+        entitlements::require(connection, entitlements::Feature::Export)?;
 
-        return results;
+        let range = export::DateRange::parse(&args.start_date, &args.end_date)?;
+        let written = export::export_habit_completions(connection, &range, Path::new(&args.path))?;
+
+        Ok(written as i32)
     }
 
-    async fn update_habit(self, args: UpdateHabitArgs) {
-        use crate::schema::habits::dsl::*;
+    async fn export_pomodoro_sessions(self, args: ExportArgs) -> Result<i32, AppError> {
+        args.validate()?;
 
-        let connection = &mut self.get_connection();
+        let connection = &mut self.get_connection()?;
+        entitlements::require(connection, entitlements::Feature::Export)?;
 
-        let updated_habit = UpdateHabit {
-            title: args.title,
-            description: args.description,
-            icon: args.icon,
-            is_archived: args.is_archived,
-            updated_at: chrono::Utc::now().to_rfc3339(),
-        };
+        let range = export::DateRange::parse(&args.start_date, &args.end_date)?;
+        let written = export::export_pomodoro_sessions(connection, &range, Path::new(&args.path))?;
 
-        diesel::update(habits)
-            .filter(id.eq(args.id))
-            .set(&updated_habit)
-            .execute(connection)
-            .expect("Error updating habit");
+        Ok(written as i32)
     }
 
-    async fn delete_habit(self, habit_id: String) {
-        use crate::schema::habits::dsl::*;
-        use diesel::prelude::*;
+    // --------------------------------------------------------------------------
+    // Import
+    // --------------------------------------------------------------------------
+    async fn preview_import(self, args: ImportArgs) -> Result<import::ImportReport, AppError> {
+        args.validate()?;
+
+        let connection = &mut self.get_connection()?;
+
+        let source_habits = import::importer_for(args.source).read(Path::new(&args.path))?;
+        let plan = import::plan_import(connection, args.source, source_habits)?;
+
+        Ok(plan.report)
+    }
+
+    async fn import_habits(self, args: ImportArgs) -> Result<import::ImportReport, AppError> {
+        args.validate()?;
+
+        let connection = &mut self.get_connection()?;
 
-        let connection = &mut self.get_connection();
+        let source_habits = import::importer_for(args.source).read(Path::new(&args.path))?;
+        let plan = import::plan_import(connection, args.source, source_habits)?;
+        entitlements::require_habit_slots(connection, plan.report.habits_to_create)?;
+        import::commit_import(connection, &plan)?;
+        self.data_changed();
 
-        diesel::delete(habits)
-            .filter(id.eq(habit_id))
-            .execute(connection)
-            .expect("Error deleting habit");
+        Ok(plan.report)
     }
 
     // --------------------------------------------------------------------------
-    // Habit completions
+    // Backups
     // --------------------------------------------------------------------------
-    async fn toggle_habit_completion(self, args: CreateHabitCompletionArgs) {
-        use crate::schema::habit_completions::dsl::habit_completions;
-
-        let connection = &mut self.get_connection();
-
-        let new_habit_completion = CreateHabitCompletion {
-            id: args
-                .id
-                .clone()
-                .unwrap_or_else(|| Uuid::new_v4().to_string()),
-            habit_id: args.habit_id,
-            created_at: match args.created_at {
-                Some(date_str) => NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                    .map(|date| date.and_hms(0, 0, 0))
-                    .map(|dt| dt.format("%Y-%m-%d").to_string())
-                    .unwrap_or_else(|_| Utc::now().naive_utc().format("%Y-%m-%d").to_string()),
-                None => Utc::now().naive_utc().format("%Y-%m-%d").to_string(),
-            },
-        };
+    async fn list_backups(self) -> Result<Vec<backup::BackupInfo>, AppError> {
+        Ok(backup::list_backups(&self.get_data_dir())?)
+    }
 
-        match args.id {
-            Some(id) => {
-                diesel::delete(habit_completions.find(id))
-                    .execute(connection)
-                    .expect("Error deleting habit completion");
-            }
-            None => {
-                diesel::insert_into(habit_completions)
-                    .values(&new_habit_completion)
-                    .execute(connection)
-                    .expect("Error saving new habit completion");
-            }
-        }
+    async fn restore_backup(self, file_name: String) -> Result<(), AppError> {
+        let data_dir = self.get_data_dir();
+        let db_path = db::database_path(&data_dir);
+
+        // Waiting for the connections and copying the file both block
+        let api = self.clone();
+        let result = tokio::task::spawn_blocking(move || -> Result<_, String> {
+            // Holding the lock keeps new connections from being checked out
+            // while the in-flight ones are returned.
+            let mut pool = api.pool.lock().unwrap();
+            db::wait_until_idle(
+                pool.as_ref().ok_or("Database is locked")?,
+                POOL_IDLE_TIMEOUT,
+            )?;
+
+            // Dropping the pool closes its connections before the file is replaced
+            *pool = None;
+
+            // A backup may predate the latest migrations
+            let key = api.get_key();
+            let result = backup::restore_backup(&db_path, &data_dir, &file_name, key.as_ref())
+                .and_then(|_| db::run_migrations(&db_path, &data_dir, key.as_ref()));
+
+            *pool = Some(db::open_pool(&db_path, key));
+
+            Ok(result)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        // Recorded events no longer match the restored rows
+        self.undo_stack.lock().unwrap().clear();
+
+        // The restored settings may configure the automation API differently
+        self.apply_automation_settings();
+        self.apply_shortcut_settings();
+        self.data_reloaded();
+
+        Ok(result?)
     }
 
-    async fn get_habit_completions(
+    async fn get_backup_retention(self) -> backup::BackupRetention {
+        backup::load_retention(&self.get_data_dir())
+    }
+
+    async fn update_backup_retention(
         self,
-        args: GetHabitCompletionsArgs,
-    ) -> Vec<models::HabitCompletion> {
-        use crate::schema::habit_completions::dsl::{created_at, habit_completions, habit_id};
+        retention: backup::BackupRetention,
+    ) -> Result<(), AppError> {
+        backup::save_retention(&self.get_data_dir(), &retention)
+    }
 
-        let connection = &mut self.get_connection();
+    // --------------------------------------------------------------------------
+    // Encryption
+    // --------------------------------------------------------------------------
+    async fn get_encryption_status(self) -> EncryptionStatus {
+        EncryptionStatus {
+            is_encrypted: encryption::is_enabled(&self.get_data_dir()),
+            is_unlocked: self.pool.lock().unwrap().is_some(),
+        }
+    }
+
+    async fn unlock_database(self, passphrase: String) -> Result<(), AppError> {
+        if self.pool.lock().unwrap().is_some() {
+            return Ok(());
+        }
 
-        match args.limit {
-            Some(limit) => {
-                let limit = limit as i64;
+        let data_dir = self.get_data_dir();
+        let db_path = db::database_path(&data_dir);
 
-                return habit_completions
-                    .filter(habit_id.eq(args.habit_id))
-                    .order(created_at.desc())
-                    .limit(limit)
-                    .load::<models::HabitCompletion>(connection)
-                    .expect("Error loading habit completions");
+        // Deriving the key is slow on purpose, and migrating blocks too
+        let api = self.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), String> {
+            let key = encryption::unlock(&db_path, &data_dir, &passphrase)?;
+
+            let mut pool = api.pool.lock().unwrap();
+            if pool.is_some() {
+                return Ok(());
             }
-            None => {
-                return habit_completions
-                    .filter(habit_id.eq(args.habit_id))
-                    .order(created_at.desc())
-                    .load::<models::HabitCompletion>(connection)
-                    .expect("Error loading habit completions");
+
+            db::run_migrations(&db_path, &data_dir, Some(&key))?;
+            if let Err(e) = backup::create_daily_backup_if_due(&db_path, &data_dir, Some(&key)) {
+                println!("Failed to create daily backup: {}", e);
             }
-        };
+
+            *pool = Some(db::open_pool(&db_path, Some(key.clone())));
+            *api.key.lock().unwrap() = Some(key);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        self.apply_automation_settings();
+        self.apply_shortcut_settings();
+        self.data_reloaded();
+
+        Ok(())
+    }
+
+    async fn enable_encryption(self, passphrase: String) -> Result<(), AppError> {
+        let data_dir = self.get_data_dir();
+        let db_path = db::database_path(&data_dir);
+
+        // Waiting for the connections, deriving the key and rewriting the
+        // file all block
+        let api = self.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), String> {
+            // Holding the lock keeps new connections from being checked out
+            // while the in-flight ones are returned.
+            let mut pool = api.pool.lock().unwrap();
+            db::wait_until_idle(
+                pool.as_ref().ok_or("Database is locked")?,
+                POOL_IDLE_TIMEOUT,
+            )?;
+
+            // Dropping the pool closes its connections before the file is replaced
+            *pool = None;
+
+            // The file keeps its old key when encrypting fails
+            let result = encryption::encrypt_database(&db_path, &data_dir, &passphrase);
+            let key = result.as_ref().ok().cloned().or_else(|| api.get_key());
+
+            *pool = Some(db::open_pool(&db_path, key.clone()));
+            *api.key.lock().unwrap() = key;
+
+            result.map(|_| ())
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        Ok(())
     }
 
-    async fn get_habit_completion_streak(self, id: String) -> i32 {
-        use crate::schema::habit_completions::dsl::{created_at, habit_completions, habit_id};
+    // --------------------------------------------------------------------------
+    // Settings
+    // --------------------------------------------------------------------------
+    async fn get_settings(self) -> Result<settings::Settings, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        let connection = &mut self.get_connection();
+        Ok(settings::load(connection)?)
+    }
 
-        let results = habit_completions
-            .filter(habit_id.eq(id))
-            .order(created_at.desc())
-            .load::<models::HabitCompletion>(connection)
-            .expect("Error loading habit completions");
+    async fn update_settings(
+        self,
+        new_settings: settings::Settings,
+    ) -> Result<settings::Settings, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        // Create a HashSet of dates from the results
-        let mut dates_set: Vec<NaiveDate> = results
-            .iter()
-            .filter_map(|result| NaiveDate::parse_from_str(&result.created_at, "%Y-%m-%d").ok())
-            .collect();
+        settings::save(connection, &new_settings)?;
+        let settings = settings::load(connection)?;
 
-        // sort the date_set
-        dates_set.sort();
+        self.automation.apply(&settings.automation, self.clone());
+        self.register_shortcuts(&settings.shortcuts);
 
-        let mut streak = 0;
-        let current_date = Utc::now().naive_utc().date();
-        let mut previous_date = current_date - Duration::days(1);
+        Ok(settings)
+    }
 
-        // Iterate backwards from today and check if each date exists in the HashSet
-        while dates_set.contains(&previous_date) {
-            streak += 1;
-            previous_date = previous_date - Duration::days(1);
-        }
+    async fn regenerate_automation_token(self) -> Result<settings::Settings, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        if dates_set.contains(&current_date) {
-            streak += 1;
-        }
+        let mut settings = settings::load(connection)?;
+        settings.automation.token = Some(automation::generate_token());
+        settings::save(connection, &settings)?;
+
+        self.automation.apply(&settings.automation, self.clone());
+
+        Ok(settings)
+    }
 
-        streak
+    async fn get_shortcut_errors(self) -> Vec<shortcuts::ShortcutError> {
+        self.shortcut_errors.lock().unwrap().clone()
     }
 
     // --------------------------------------------------------------------------
-    // Pomodoro
+    // History
     // --------------------------------------------------------------------------
-    async fn start_pomodoro_cycle(
-        self,
-        args: StartCycleArgs,
-    ) -> Result<models::PomodoroCycle, String> {
-        use crate::schema::pomodoro_cycles::dsl::*;
-
-        let connection = &mut self.get_connection();
-
-        let new_cycle = models::CreatePomodoroCycle {
-            id: Uuid::new_v4().to_string(),
-            focus_duration: args.focus_duration,
-            short_break_duration: args.short_break_duration,
-            long_break_duration: args.long_break_duration,
-            sessions_until_long_break: args.sessions_until_long_break,
-            auto_start_breaks: args.auto_start_breaks,
-            auto_start_pomodoros: args.auto_start_pomodoros,
-            started_at: Utc::now().to_rfc3339(),
-            updated_at: Utc::now().to_rfc3339(),
-        };
+    async fn get_history(self, entity_id: String) -> Result<Vec<models::Event>, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        diesel::insert_into(pomodoro_cycles)
-            .values(&new_cycle)
-            .execute(connection)
-            .map_err(|e| e.to_string())?;
+        Ok(events::history(connection, &entity_id)?)
+    }
 
-        pomodoro_cycles
-            .find(new_cycle.id)
-            .first(connection)
-            .map_err(|e| e.to_string())
+    async fn undo(self) -> Result<Option<String>, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        let description = self.undo_stack.lock().unwrap().undo(connection)?;
+        self.data_changed();
+
+        Ok(description)
     }
 
-    async fn get_current_cycle(self) -> Result<Option<GetPomodoroCycleWithRelationships>, String> {
-        use crate::schema::pomodoro_cycles::dsl::*;
-        use crate::schema::pomodoro_sessions::dsl as sessions_dsl;
-
-        let connection = &mut self.get_connection();
-
-        // Get the most recent in-progress cycle
-        let cycle = pomodoro_cycles
-            .filter(status.eq("IN_PROGRESS"))
-            .order(started_at.desc())
-            .first::<models::PomodoroCycle>(connection)
-            .optional()
-            .map_err(|e| e.to_string())?;
-
-        match cycle {
-            Some(cycle) => {
-                // Get all sessions for this cycle
-                let sessions = sessions_dsl::pomodoro_sessions
-                    .filter(sessions_dsl::cycle_id.eq(&cycle.id))
-                    .load::<models::PomodoroSession>(connection)
-                    .map_err(|e| e.to_string())?;
-
-                Ok(Some(GetPomodoroCycleWithRelationships {
-                    id: cycle.id,
-                    status: Status::from_str(&cycle.status),
-                    focus_duration: cycle.focus_duration,
-                    short_break_duration: cycle.short_break_duration,
-                    long_break_duration: cycle.long_break_duration,
-                    sessions_until_long_break: cycle.sessions_until_long_break,
-                    auto_start_breaks: cycle.auto_start_breaks,
-                    auto_start_pomodoros: cycle.auto_start_pomodoros,
-                    started_at: cycle.started_at,
-                    completed_at: cycle.completed_at,
-                    updated_at: cycle.updated_at,
-                    sessions,
-                }))
-            }
-            None => Ok(None),
-        }
+    async fn redo(self) -> Result<Option<String>, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        let description = self.undo_stack.lock().unwrap().redo(connection)?;
+        self.data_changed();
+
+        Ok(description)
     }
 
-    async fn update_cycle_status(
+    // --------------------------------------------------------------------------
+    // Sync
+    // --------------------------------------------------------------------------
+    async fn get_sync_config(self) -> Result<Option<sync::SyncBackendConfig>, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        Ok(sync::load_config(connection, self.get_secrets().as_ref())?)
+    }
+
+    async fn update_sync_config(
         self,
-        args: UpdateCycleArgs,
-    ) -> Result<models::PomodoroCycle, String> {
-        use crate::schema::pomodoro_cycles::dsl::*;
-
-        let connection = &mut self.get_connection();
-
-        let update = models::UpdatePomodoroCycle {
-            status: Some(args.status.to_str().to_string()),
-            completed_at: if matches!(args.status, Status::Completed | Status::Abandoned) {
-                Some(Utc::now().to_rfc3339())
-            } else {
-                None
-            },
-            updated_at: Utc::now().to_rfc3339(),
-        };
+        config: Option<sync::SyncBackendConfig>,
+    ) -> Result<(), AppError> {
+        let connection = &mut self.get_connection()?;
 
-        diesel::update(pomodoro_cycles.find(args.id.clone()))
-            .set(&update)
-            .execute(connection)
-            .map_err(|e| e.to_string())?;
+        // Turning sync off stays possible without a license
+        if config.is_some() {
+            entitlements::require(connection, entitlements::Feature::Sync)?;
+        }
 
-        pomodoro_cycles
-            .find(args.id)
-            .first(connection)
-            .map_err(|e| e.to_string())
+        Ok(sync::save_config(
+            connection,
+            self.get_secrets().as_ref(),
+            config.as_ref(),
+        )?)
     }
 
-    async fn start_session(
+    async fn sync_now(self) -> Result<sync::SyncReport, AppError> {
+        entitlements::require(&mut *self.get_connection()?, entitlements::Feature::Sync)?;
+
+        // Backends do blocking file and network IO
+        let api = self.clone();
+        let report = tokio::task::spawn_blocking(move || -> Result<_, AppError> {
+            let connection = &mut api.get_connection()?;
+
+            let secrets = api.get_secrets();
+            let config =
+                sync::load_config(connection, secrets.as_ref())?.ok_or("Sync is not configured")?;
+            let backend = sync::backend_for(&config, secrets.as_ref())?;
+
+            Ok(sync::sync(connection, backend.as_ref())?)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        self.data_reloaded();
+
+        Ok(report)
+    }
+
+    // --------------------------------------------------------------------------
+    // Webhooks
+    // --------------------------------------------------------------------------
+    async fn get_webhooks(self) -> Result<Vec<webhooks::Webhook>, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        Ok(webhooks::webhooks(connection)?)
+    }
+
+    async fn create_webhook(
         self,
-        args: CreateSessionArgs,
-    ) -> Result<models::PomodoroSession, String> {
-        use crate::schema::pomodoro_sessions::dsl::*;
+        args: webhooks::CreateWebhookArgs,
+    ) -> Result<webhooks::Webhook, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        let connection = &mut self.get_connection();
+        webhooks::create_webhook(connection, args)
+    }
 
-        let new_session = models::CreatePomodoroSession {
-            id: Uuid::new_v4().to_string(),
-            cycle_id: args.cycle_id,
-            session_type: args.session_type.to_str().to_string(),
-            started_at: Utc::now().to_rfc3339(),
-            duration_minutes: args.duration_minutes,
-        };
+    async fn update_webhook(
+        self,
+        args: webhooks::UpdateWebhookArgs,
+    ) -> Result<webhooks::Webhook, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        webhooks::update_webhook(connection, args)
+    }
 
-        diesel::insert_into(pomodoro_sessions)
-            .values(&new_session)
-            .execute(connection)
-            .map_err(|e| e.to_string())?;
+    async fn delete_webhook(self, webhook_id: String) -> Result<(), AppError> {
+        let connection = &mut self.get_connection()?;
 
-        pomodoro_sessions
-            .find(new_session.id)
-            .first(connection)
-            .map_err(|e| e.to_string())
+        webhooks::delete_webhook(connection, &webhook_id)
     }
 
-    async fn complete_session(
+    async fn get_webhook_deliveries(
         self,
-        args: CompleteSessionArgs,
-    ) -> Result<models::PomodoroSession, String> {
-        use crate::schema::pomodoro_sessions::dsl::*;
+        webhook_id: String,
+    ) -> Result<Vec<webhooks::WebhookDelivery>, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        Ok(webhooks::deliveries(
+            connection,
+            &webhook_id,
+            WEBHOOK_DELIVERIES_LIMIT,
+        )?)
+    }
 
-        let connection = &mut self.get_connection();
+    async fn test_webhook(self, webhook_id: String) -> Result<webhooks::WebhookDelivery, AppError> {
+        tokio::task::spawn_blocking(move || {
+            let connection = &mut self.get_connection()?;
+            let client = webhooks::client()?;
 
-        let update = models::UpdatePomodoroSession {
-            completed_at: Some(Utc::now().to_rfc3339()),
-            was_completed: args.was_completed,
-        };
+            webhooks::send_ping(connection, &client, &webhook_id)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
 
-        diesel::update(pomodoro_sessions.find(args.session_id.clone()))
-            .set(&update)
-            .execute(connection)
-            .map_err(|e| e.to_string())?;
+    // --------------------------------------------------------------------------
+    // Reminders
+    // --------------------------------------------------------------------------
+    async fn get_habit_reminders(
+        self,
+        habit_id: String,
+    ) -> Result<Vec<reminders::HabitReminder>, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        pomodoro_sessions
-            .find(args.session_id)
-            .first(connection)
-            .map_err(|e| e.to_string())
+        Ok(reminders::reminders(connection, &habit_id)?)
     }
 
-    async fn get_daily_stats(self, date: String) -> Result<i32, String> {
-        use crate::schema::pomodoro_sessions::dsl::*;
+    async fn create_habit_reminder(
+        self,
+        args: reminders::CreateHabitReminderArgs,
+    ) -> Result<reminders::HabitReminder, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        let connection = &mut self.get_connection();
+        let reminder = reminders::create_reminder(connection, args)?;
+        self.reminders_changed.notify_one();
+        Ok(reminder)
+    }
+
+    async fn update_habit_reminder(
+        self,
+        args: reminders::UpdateHabitReminderArgs,
+    ) -> Result<reminders::HabitReminder, AppError> {
+        let connection = &mut self.get_connection()?;
 
-        // Parse the date string to get start and end of day
-        let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| e.to_string())?;
-        let start_of_day = format!("{}T00:00:00Z", date.format("%Y-%m-%d"));
-        let end_of_day = format!("{}T23:59:59Z", date.format("%Y-%m-%d"));
+        let reminder = reminders::update_reminder(connection, args)?;
+        self.reminders_changed.notify_one();
+        Ok(reminder)
+    }
 
-        // Get all completed focus sessions for the day
-        let total_minutes: i32 = pomodoro_sessions
-            .filter(session_type.eq("FOCUS"))
-            .filter(was_completed.eq(true))
-            .filter(started_at.ge(start_of_day))
-            .filter(started_at.le(end_of_day))
-            .select(duration_minutes)
-            .get_results::<i32>(connection)
-            .map_err(|e| e.to_string())?
-            .into_iter()
-            .sum();
+    async fn delete_habit_reminder(self, reminder_id: String) -> Result<(), AppError> {
+        let connection = &mut self.get_connection()?;
 
-        Ok(total_minutes)
+        reminders::delete_reminder(connection, &reminder_id)?;
+        self.reminders_changed.notify_one();
+        Ok(())
+    }
+
+    // --------------------------------------------------------------------------
+    // Idle
+    // --------------------------------------------------------------------------
+    async fn get_unresolved_interruptions(self) -> Result<Vec<idle::Interruption>, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        Ok(idle::unresolved_interruptions(connection)?)
+    }
+
+    async fn resolve_interruption(
+        self,
+        args: idle::ResolveInterruptionArgs,
+    ) -> Result<idle::Interruption, AppError> {
+        let connection = &mut self.get_connection()?;
+
+        idle::resolve_interruption(connection, args)
     }
 }
 
 #[tokio::main]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub async fn run() {
-    let pool = Arc::new(Mutex::new(None));
-    let pool_clone = pool.clone();
+    let app_config = config::Config::load(db::default_data_dir().as_deref()).unwrap_or_else(|e| {
+        println!(
+            "Failed to load the configuration, using the built-in one: {}",
+            e
+        );
+        config::Config::resolve(config::ConfigValues::from_build().unwrap_or_default())
+    });
+
+    let api = ApiImpl::new(license::LicenseClient::from_config(&app_config.license));
+    let setup_api = api.clone();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .setup(move |app| {
-            let db_pool = db::setup_db(&app);
-            *pool_clone.lock().unwrap() = Some(db_pool);
+            let app_data_dir = db::data_dir(app);
+            let pool = db::setup_db(&app_data_dir);
+            let is_unlocked = pool.is_some();
+            *setup_api.pool.lock().unwrap() = pool;
+
+            let notifier: Arc<dyn notifications::Notifier> =
+                Arc::new(TauriNotifier(app.handle().clone()));
+            *setup_api.notifier.lock().unwrap() = Some(notifier.clone());
+            *setup_api.app_handle.lock().unwrap() = Some(app.handle().clone());
+            *setup_api.secrets.lock().unwrap() = Some(Arc::new(KeyringSecrets));
+
+            let timer_api = setup_api.clone();
+            app.listen_any(timer::TIMER_STATE_EVENT, move |event| {
+                timer_api.timer_state_changed(serde_json::from_str(event.payload()).ok());
+            });
+            #[cfg(mobile)]
+            if let Err(e) = register_notification_actions(app.handle()) {
+                println!("Failed to register the notification actions: {}", e);
+            }
+            tray::setup(app.handle(), setup_api.clone())?;
+
+            *setup_api.data_dir.lock().unwrap() = Some(app_data_dir.clone());
+
+            *setup_api.shortcut_registry.lock().unwrap() = Some(Arc::new(TauriShortcuts {
+                app: app.handle().clone(),
+                timer_state: setup_api.timer_state.clone(),
+            }));
+
+            // An encrypted database starts the server and registers the
+            // shortcuts once it is unlocked
+            if is_unlocked {
+                setup_api.apply_automation_settings();
+                setup_api.apply_shortcut_settings();
+            }
+
+            // The app may stay open for days, so keep checking for a due backup
+            let backup_pool = setup_api.pool.clone();
+            let backup_key = setup_api.key.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(DAILY_BACKUP_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    create_daily_backup(&backup_pool, &backup_key, &app_data_dir);
+                }
+            });
+
+            let license_pool = setup_api.pool.clone();
+            let license_client = setup_api.license_client.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(LICENSE_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    revalidate_license(&license_pool, &license_client).await;
+                }
+            });
+
+            let webhook_pool = setup_api.pool.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(WEBHOOK_DELIVERY_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    let pool = webhook_pool.clone();
+                    // Receivers may be slow, don't block the async runtime on them
+                    let _ = tokio::task::spawn_blocking(move || deliver_webhooks(&pool)).await;
+                }
+            });
+
+            let reminder_app = app.handle().clone();
+            let reminder_pool = setup_api.pool.clone();
+            let reminders_changed = setup_api.reminders_changed.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    let wait = fire_reminders(&reminder_app, notifier.as_ref(), &reminder_pool);
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        // An edited reminder may be due sooner
+                        _ = reminders_changed.notified() => {}
+                    }
+                }
+            });
+
+            let feed_app = app.handle().clone();
+            let feed_pool = setup_api.pool.clone();
+            let change_feed = setup_api.change_feed.clone();
+            let changes_recorded = setup_api.changes_recorded.clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    publish_recorded_changes(&feed_app, &feed_pool, &change_feed);
+                    tokio::select! {
+                        _ = tokio::time::sleep(CHANGE_FEED_INTERVAL) => {}
+                        _ = changes_recorded.notified() => {}
+                    }
+                }
+            });
+
+            let phase_api = setup_api.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(PHASE_END_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    phase_api.notify_phase_end();
+                }
+            });
+
+            let idle_app = app.handle().clone();
+            let idle_pool = setup_api.pool.clone();
+            let idle_timer_state = setup_api.timer_state.clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    check_idle(&idle_app, &idle_pool, &idle_timer_state);
+                }
+            });
+
             Ok(())
         })
-        .invoke_handler(taurpc::create_ipc_handler(ApiImpl { pool }.into_handler()))
+        .invoke_handler(taurpc::create_ipc_handler(api.into_handler()))
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,494 @@
+//! License activation against the Creem licensing API.
+//!
+//! Every device activates its own instance of the license key. The instance
+//! and the status last reported by the provider are kept in the `license`
+//! table, so the key only has to be entered once.
+//!
+//! Paid features don't need the network on every start: an active license
+//! that was validated within the last day is valid, and keeps working in a
+//! grace period of `GRACE_PERIOD_DAYS` after that while the server can't be
+//! reached.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::prelude::*;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteConnection, SqliteValue};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+
+use crate::config::LicenseConfig;
+use crate::error::AppError;
+use crate::models;
+use crate::schema::license;
+use crate::validation::{Validate, Validator};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// The table holds at most this one row.
+const ROW_ID: i32 = 1;
+/// A validation is trusted this long before the grace period starts.
+const REVALIDATION_INTERVAL_HOURS: i64 = 24;
+/// How long paid features keep working without reaching the license server.
+pub const GRACE_PERIOD_DAYS: i32 = 14;
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LicenseErrorReason {
+    /// No response, e.g. while offline.
+    Unreachable,
+    /// The provider refused the key or instance.
+    Rejected,
+    /// The provider answered with something that isn't a license.
+    InvalidResponse,
+    /// This device has no activated license to validate or deactivate.
+    NotActivated,
+    /// This build has no API key for the license server.
+    NotConfigured,
+}
+
+fn license_error(reason: LicenseErrorReason, message: impl Into<String>) -> AppError {
+    AppError::License {
+        reason,
+        message: message.into(),
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LicenseStatus {
+    Inactive,
+    Active,
+    Expired,
+    Disabled,
+}
+
+impl LicenseStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LicenseStatus::Inactive => "INACTIVE",
+            LicenseStatus::Active => "ACTIVE",
+            LicenseStatus::Expired => "EXPIRED",
+            LicenseStatus::Disabled => "DISABLED",
+        }
+    }
+}
+
+impl FromStr for LicenseStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "INACTIVE" => Ok(LicenseStatus::Inactive),
+            "ACTIVE" => Ok(LicenseStatus::Active),
+            "EXPIRED" => Ok(LicenseStatus::Expired),
+            "DISABLED" => Ok(LicenseStatus::Disabled),
+            _ => Err(format!("Unknown license status: {}", s)),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for LicenseStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for LicenseStatus {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(value.parse()?)
+    }
+}
+
+/// The provider sends statuses in lowercase.
+fn provider_status<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LicenseStatus, D::Error> {
+    let status = String::deserialize(deserializer)?;
+    status
+        .to_uppercase()
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// The activation of this device.
+#[derive(Serialize, Deserialize, specta::Type, Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = license)]
+#[serde(rename_all = "camelCase")]
+pub struct License {
+    pub license_key: String,
+    pub instance_id: String,
+    pub instance_name: String,
+    pub status: LicenseStatus,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub activated_at: DateTime<Utc>,
+    pub validated_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LicenseState {
+    /// Active and recently confirmed by the provider.
+    Valid,
+    /// Active when last confirmed, but that was over a day ago.
+    Grace,
+    /// The license, or the grace period, has run out.
+    Expired,
+    /// No license, or one the provider doesn't accept.
+    Invalid,
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LicenseStatusReport {
+    pub state: LicenseState,
+    pub license: Option<License>,
+    /// When the license stops working unless it is validated again.
+    pub grace_ends_at: Option<DateTime<Utc>>,
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivateLicenseKeyArgs {
+    pub license_key: String,
+    pub instance_name: String,
+}
+
+impl Validate for ActivateLicenseKeyArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .not_empty("licenseKey", &self.license_key)
+            .not_empty("instanceName", &self.instance_name)
+            .finish()
+    }
+}
+
+// --------------------------------------------------------------------------
+// Provider
+// --------------------------------------------------------------------------
+
+/// A license as returned by the activate, validate and deactivate endpoints.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LicenseResponse {
+    #[serde(deserialize_with = "provider_status")]
+    pub status: LicenseStatus,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub instance: Option<InstanceResponse>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct InstanceResponse {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Clone)]
+pub struct LicenseClient {
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl LicenseClient {
+    pub fn new(base_url: impl Into<String>, api_key: Option<String>) -> Self {
+        LicenseClient {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key,
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to create the HTTP client"),
+        }
+    }
+
+    pub fn from_config(config: &LicenseConfig) -> Self {
+        LicenseClient::new(&config.api_url, config.api_key.clone())
+    }
+
+    pub async fn activate(
+        &self,
+        license_key: &str,
+        instance_name: &str,
+    ) -> Result<LicenseResponse, AppError> {
+        self.post(
+            "licenses/activate",
+            json!({ "key": license_key, "instance_name": instance_name }),
+        )
+        .await
+    }
+
+    pub async fn validate(
+        &self,
+        license_key: &str,
+        instance_id: &str,
+    ) -> Result<LicenseResponse, AppError> {
+        self.post(
+            "licenses/validate",
+            json!({ "key": license_key, "instance_id": instance_id }),
+        )
+        .await
+    }
+
+    pub async fn deactivate(
+        &self,
+        license_key: &str,
+        instance_id: &str,
+    ) -> Result<LicenseResponse, AppError> {
+        self.post(
+            "licenses/deactivate",
+            json!({ "key": license_key, "instance_id": instance_id }),
+        )
+        .await
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: Value) -> Result<T, AppError> {
+        let api_key = self.api_key.as_ref().ok_or_else(|| {
+            license_error(
+                LicenseErrorReason::NotConfigured,
+                "Licensing is not configured in this build",
+            )
+        })?;
+
+        let response = self
+            .http
+            .post(format!("{}/{}", self.base_url, path))
+            .header("accept", "application/json")
+            .header("x-api-key", api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| {
+                license_error(
+                    LicenseErrorReason::Unreachable,
+                    format!("Could not reach the license server: {}", e),
+                )
+            })?;
+
+        let status = response.status();
+        let text = response.text().await.map_err(|e| {
+            license_error(
+                LicenseErrorReason::Unreachable,
+                format!("Could not read the license server response: {}", e),
+            )
+        })?;
+
+        if status.is_client_error() {
+            return Err(license_error(
+                LicenseErrorReason::Rejected,
+                error_message(&text).unwrap_or_else(|| status.to_string()),
+            ));
+        }
+        if !status.is_success() {
+            return Err(license_error(
+                LicenseErrorReason::InvalidResponse,
+                format!("The license server responded with {}", status),
+            ));
+        }
+
+        serde_json::from_str(&text).map_err(|e| {
+            license_error(
+                LicenseErrorReason::InvalidResponse,
+                format!("Unexpected license server response: {}", e),
+            )
+        })
+    }
+}
+
+/// The `message` of an error body, which is either a string or a list.
+fn error_message(body: &str) -> Option<String> {
+    let body: Value = serde_json::from_str(body).ok()?;
+    match body.get("message")? {
+        Value::String(message) => Some(message.clone()),
+        Value::Array(messages) => Some(
+            messages
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("; "),
+        ),
+        _ => None,
+    }
+}
+
+// --------------------------------------------------------------------------
+// Activation
+// --------------------------------------------------------------------------
+
+pub fn load(connection: &mut SqliteConnection) -> QueryResult<Option<License>> {
+    license::table
+        .select(License::as_select())
+        .first(connection)
+        .optional()
+}
+
+pub fn store(connection: &mut SqliteConnection, value: &License) -> QueryResult<()> {
+    diesel::replace_into(license::table)
+        .values((license::id.eq(ROW_ID), value))
+        .execute(connection)?;
+    Ok(())
+}
+
+pub fn stored(connection: &mut SqliteConnection) -> Result<License, AppError> {
+    load(connection)?.ok_or_else(|| {
+        license_error(
+            LicenseErrorReason::NotActivated,
+            "No license is activated on this device",
+        )
+    })
+}
+
+// The functions below that talk to the provider take no connection, so none
+// is held while waiting for it. Callers read and store the license around
+// them.
+
+/// Activates a new instance of the key for this device. Storing it replaces
+/// any previous activation.
+pub async fn activate(
+    client: &LicenseClient,
+    args: ActivateLicenseKeyArgs,
+) -> Result<License, AppError> {
+    args.validate()?;
+
+    let response = client
+        .activate(&args.license_key, &args.instance_name)
+        .await?;
+    let instance = response.instance.ok_or_else(|| {
+        license_error(
+            LicenseErrorReason::InvalidResponse,
+            "The license server did not return an instance",
+        )
+    })?;
+
+    let now = models::now();
+    Ok(License {
+        license_key: args.license_key,
+        instance_id: instance.id,
+        instance_name: instance.name,
+        status: response.status,
+        expires_at: response.expires_at,
+        activated_at: now,
+        validated_at: now,
+        updated_at: now,
+    })
+}
+
+/// Asks the provider for the current status of `value`, see
+/// [`store_validation`] for storing the answer.
+pub async fn validate(
+    client: &LicenseClient,
+    value: &License,
+) -> Result<LicenseResponse, AppError> {
+    client
+        .validate(&value.license_key, &value.instance_id)
+        .await
+}
+
+/// Stores the provider's answer to validating `value`. A rejected instance
+/// is stored as inactive before the error is returned, any other error
+/// leaves the stored license as it was.
+pub fn store_validation(
+    connection: &mut SqliteConnection,
+    mut value: License,
+    response: Result<LicenseResponse, AppError>,
+) -> Result<License, AppError> {
+    match response {
+        Ok(response) => {
+            value.status = response.status;
+            value.expires_at = response.expires_at;
+            value.validated_at = models::now();
+            value.updated_at = value.validated_at;
+            store(connection, &value)?;
+
+            Ok(value)
+        }
+        Err(
+            e @ AppError::License {
+                reason: LicenseErrorReason::Rejected,
+                ..
+            },
+        ) => {
+            value.status = LicenseStatus::Inactive;
+            value.updated_at = models::now();
+            store(connection, &value)?;
+
+            Err(e)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Releases the instance of `value`, so the key can be activated elsewhere.
+/// The caller forgets the license on this device whatever the outcome.
+pub async fn deactivate(client: &LicenseClient, value: &License) -> Result<(), AppError> {
+    client
+        .deactivate(&value.license_key, &value.instance_id)
+        .await?;
+    Ok(())
+}
+
+pub fn forget(connection: &mut SqliteConnection) -> QueryResult<()> {
+    diesel::delete(license::table).execute(connection)?;
+    Ok(())
+}
+
+// --------------------------------------------------------------------------
+// Status
+// --------------------------------------------------------------------------
+
+fn revalidation_due_at(value: &License) -> DateTime<Utc> {
+    value.validated_at + chrono::Duration::hours(REVALIDATION_INTERVAL_HOURS)
+}
+
+/// The state of `license` at `now`, from what the provider last reported.
+pub fn status(
+    license: Option<License>,
+    grace_period_days: i32,
+    now: DateTime<Utc>,
+) -> LicenseStatusReport {
+    let Some(value) = license else {
+        return LicenseStatusReport {
+            state: LicenseState::Invalid,
+            license: None,
+            grace_ends_at: None,
+        };
+    };
+
+    let grace_ends_at =
+        revalidation_due_at(&value) + chrono::Duration::days(grace_period_days.into());
+    let has_expired = value.expires_at.is_some_and(|expires_at| expires_at <= now);
+
+    let state = match value.status {
+        LicenseStatus::Inactive | LicenseStatus::Disabled => LicenseState::Invalid,
+        LicenseStatus::Expired => LicenseState::Expired,
+        LicenseStatus::Active if has_expired || grace_ends_at <= now => LicenseState::Expired,
+        LicenseStatus::Active if revalidation_due_at(&value) <= now => LicenseState::Grace,
+        LicenseStatus::Active => LicenseState::Valid,
+    };
+
+    LicenseStatusReport {
+        state,
+        license: Some(value),
+        grace_ends_at: Some(grace_ends_at),
+    }
+}
+
+/// The state of the stored license, without contacting the provider.
+pub fn load_status(connection: &mut SqliteConnection) -> Result<LicenseStatusReport, AppError> {
+    Ok(status(load(connection)?, GRACE_PERIOD_DAYS, Utc::now()))
+}
+
+/// The stored license if the last validation is over a day old.
+pub fn due_for_revalidation(connection: &mut SqliteConnection) -> QueryResult<Option<License>> {
+    Ok(load(connection)?.filter(|value| revalidation_due_at(value) <= Utc::now()))
+}
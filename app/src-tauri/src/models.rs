@@ -1,13 +1,138 @@
 use std::clone;
 
+use super::schema::events;
 use super::schema::habit_completions;
 use super::schema::habits;
 use super::schema::pomodoro_cycles;
+use super::schema::pomodoro_presets;
 use super::schema::pomodoro_sessions;
+use chrono::{DateTime, NaiveDate, SubsecRound, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
 use diesel::prelude::*;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteValue};
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
-#[derive(Serialize, Deserialize, Clone, Queryable, specta::Type, Selectable, Insertable, Debug)]
+/// How Diesel writes a `DateTime<Utc>` to SQLite, e.g.
+/// `2025-02-16 09:30:00.250+00:00`. Whole seconds have no fraction.
+pub const TIMESTAMP_FORMAT: &str = "%F %T%.f%:z";
+
+/// The current time, truncated to the millisecond precision timestamps are
+/// stored with.
+pub fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(3)
+}
+
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Status {
+    Completed,
+    InProgress,
+    Abandoned,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Completed => "COMPLETED",
+            Status::InProgress => "IN_PROGRESS",
+            Status::Abandoned => "ABANDONED",
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "COMPLETED" => Ok(Status::Completed),
+            "IN_PROGRESS" => Ok(Status::InProgress),
+            "ABANDONED" => Ok(Status::Abandoned),
+            _ => Err(format!("Unknown cycle status: {}", s)),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for Status {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for Status {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(value.parse()?)
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SessionType {
+    LongBreak,
+    Focus,
+    ShortBreak,
+}
+
+impl SessionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionType::LongBreak => "LONG_BREAK",
+            SessionType::Focus => "FOCUS",
+            SessionType::ShortBreak => "SHORT_BREAK",
+        }
+    }
+}
+
+impl FromStr for SessionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "LONG_BREAK" => Ok(SessionType::LongBreak),
+            "FOCUS" => Ok(SessionType::Focus),
+            "SHORT_BREAK" => Ok(SessionType::ShortBreak),
+            _ => Err(format!("Unknown session type: {}", s)),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for SessionType {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for SessionType {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(value.parse()?)
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Queryable,
+    specta::Type,
+    Selectable,
+    Insertable,
+    AsChangeset,
+    Debug,
+)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 #[diesel(table_name = habits)]
 #[serde(rename_all = "camelCase")]
@@ -17,8 +142,8 @@ pub struct Habit {
     pub description: String,
     pub icon: String,
     pub is_archived: bool,
-    pub updated_at: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Serialize, Deserialize, Clone, AsChangeset, Debug, Insertable)]
@@ -31,7 +156,7 @@ pub struct UpdateHabit {
     pub description: Option<String>,
     pub icon: Option<String>,
     pub is_archived: Option<bool>,
-    pub updated_at: String,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Queryable, Selectable, specta::Type, Debug)]
@@ -41,7 +166,7 @@ pub struct UpdateHabit {
 pub struct HabitCompletion {
     pub id: String,
     pub habit_id: String,
-    pub created_at: String,
+    pub created_at: NaiveDate,
 }
 
 #[derive(Serialize, Deserialize, Clone, Queryable, Insertable, Selectable, specta::Type, Debug)]
@@ -51,24 +176,28 @@ pub struct HabitCompletion {
 pub struct CreateHabitCompletion {
     pub id: String,
     pub habit_id: String,
-    pub created_at: String,
+    pub created_at: NaiveDate,
 }
 
-#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Clone, specta::Type)]
+#[derive(
+    Debug, Serialize, Deserialize, Queryable, Insertable, AsChangeset, Clone, specta::Type,
+)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 #[diesel(table_name = pomodoro_cycles)]
+#[diesel(treat_none_as_null = true)]
 pub struct PomodoroCycle {
     pub id: String,
-    pub status: String,
+    pub status: Status,
     pub focus_duration: i32,
     pub short_break_duration: i32,
     pub long_break_duration: i32,
     pub sessions_until_long_break: i32,
     pub auto_start_breaks: bool,
     pub auto_start_pomodoros: bool,
-    pub started_at: String,
-    pub completed_at: Option<String>,
-    pub updated_at: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+    pub preset_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Insertable)]
@@ -82,17 +211,49 @@ pub struct CreatePomodoroCycle {
     pub sessions_until_long_break: i32,
     pub auto_start_breaks: bool,
     pub auto_start_pomodoros: bool,
-    pub started_at: String,
-    pub updated_at: String,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub preset_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, AsChangeset)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 #[diesel(table_name = pomodoro_cycles)]
 pub struct UpdatePomodoroCycle {
-    pub status: Option<String>,
-    pub completed_at: Option<String>,
-    pub updated_at: String,
+    pub status: Option<Status>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Clone, specta::Type)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = pomodoro_presets)]
+pub struct PomodoroPreset {
+    pub id: String,
+    pub name: String,
+    pub focus_duration: i32,
+    pub short_break_duration: i32,
+    pub long_break_duration: i32,
+    pub sessions_until_long_break: i32,
+    pub auto_start_breaks: bool,
+    pub auto_start_pomodoros: bool,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, AsChangeset)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = pomodoro_presets)]
+pub struct UpdatePomodoroPreset {
+    pub name: Option<String>,
+    pub focus_duration: Option<i32>,
+    pub short_break_duration: Option<i32>,
+    pub long_break_duration: Option<i32>,
+    pub sessions_until_long_break: Option<i32>,
+    pub auto_start_breaks: Option<bool>,
+    pub auto_start_pomodoros: Option<bool>,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Clone, specta::Type)]
@@ -101,10 +262,10 @@ pub struct UpdatePomodoroCycle {
 pub struct PomodoroSession {
     pub id: String,
     pub cycle_id: String,
-    pub session_type: String,
-    pub started_at: String,
-    pub completed_at: Option<String>,
-    pub duration_minutes: i32,
+    pub session_type: SessionType,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub duration_ms: i32,
     pub was_completed: bool,
 }
 
@@ -114,15 +275,29 @@ pub struct PomodoroSession {
 pub struct CreatePomodoroSession {
     pub id: String,
     pub cycle_id: String,
-    pub session_type: String,
-    pub started_at: String,
-    pub duration_minutes: i32,
+    pub session_type: SessionType,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, AsChangeset, specta::Type, Clone)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 #[diesel(table_name = pomodoro_sessions)]
 pub struct UpdatePomodoroSession {
-    pub completed_at: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
     pub was_completed: bool,
 }
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Selectable, Clone, specta::Type)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = events)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub id: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub operation: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
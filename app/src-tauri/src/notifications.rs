@@ -0,0 +1,217 @@
+//! Desktop notifications for pomodoro phase changes and habit reminders.
+//!
+//! Notifications are shown through a [`Notifier`], which is the notification
+//! plugin in the app and [`crate::testing::FakeNotifier`] in tests. Whether a
+//! notification is shown, its text and its sound follow
+//! `Settings::notifications`.
+
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde_json::{json, Value};
+
+use crate::error::AppError;
+use crate::models::{PomodoroCycle, PomodoroSession, SessionType, Status};
+use crate::reminders::DueReminder;
+use crate::schema::{pomodoro_cycles, pomodoro_sessions};
+use crate::settings::{self, NotificationSettings};
+
+/// Offered on a notification to start the next phase, for cycles that don't
+/// start it automatically.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationAction {
+    StartBreak,
+    StartFocus,
+}
+
+impl NotificationAction {
+    pub const ALL: [NotificationAction; 2] = [
+        NotificationAction::StartBreak,
+        NotificationAction::StartFocus,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            NotificationAction::StartBreak => "start-break",
+            NotificationAction::StartFocus => "start-focus",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationAction::StartBreak => "Start break",
+            NotificationAction::StartFocus => "Start focus",
+        }
+    }
+}
+
+/// The action types to register with the notification plugin, each with a
+/// single button that brings the app to the foreground.
+pub fn action_types() -> Value {
+    NotificationAction::ALL
+        .iter()
+        .map(|action| {
+            json!({
+                "id": action.id(),
+                "actions": [{
+                    "id": action.id(),
+                    "title": action.label(),
+                    "requiresAuthentication": false,
+                    "foreground": true,
+                    "destructive": false,
+                    "input": false,
+                }],
+                "customDismissAction": false,
+                "allowInCarPlay": false,
+                "hiddenPreviewsShowTitle": false,
+                "hiddenPreviewsShowSubtitle": false,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub sound: bool,
+    pub action: Option<NotificationAction>,
+}
+
+pub trait Notifier: Send + Sync {
+    fn notify(&self, notification: Notification) -> Result<(), String>;
+}
+
+/// The notification for a session that ran to its end. A long break ends
+/// the cycle, which has its own notification.
+pub fn session_ended(
+    settings: &NotificationSettings,
+    session: &PomodoroSession,
+    cycle: &PomodoroCycle,
+) -> Option<Notification> {
+    if !settings.enabled || !settings.session_end || !session.was_completed {
+        return None;
+    }
+
+    let (title, body, action) = match session.session_type {
+        SessionType::Focus => (
+            "Focus session complete",
+            &settings.focus_end_message,
+            (!cycle.auto_start_breaks).then_some(NotificationAction::StartBreak),
+        ),
+        SessionType::ShortBreak => (
+            "Break is over",
+            &settings.break_end_message,
+            (!cycle.auto_start_pomodoros).then_some(NotificationAction::StartFocus),
+        ),
+        SessionType::LongBreak => return None,
+    };
+
+    Some(Notification {
+        title: title.to_string(),
+        body: body.clone(),
+        sound: settings.sound,
+        action,
+    })
+}
+
+pub fn cycle_completed(settings: &NotificationSettings) -> Option<Notification> {
+    if !settings.enabled || !settings.session_end {
+        return None;
+    }
+
+    Some(Notification {
+        title: "Pomodoro cycle complete".to_string(),
+        body: settings.cycle_end_message.clone(),
+        sound: settings.sound,
+        action: None,
+    })
+}
+
+pub fn reminder(settings: &NotificationSettings, reminder: &DueReminder) -> Option<Notification> {
+    if !settings.enabled || !settings.habit_reminders {
+        return None;
+    }
+
+    Some(Notification {
+        title: reminder.habit_title.clone(),
+        body: "Time for your habit".to_string(),
+        sound: settings.sound,
+        action: None,
+    })
+}
+
+/// Notifies that `session` ended, if the settings ask for it.
+pub fn notify_session_ended(
+    connection: &mut SqliteConnection,
+    notifier: &dyn Notifier,
+    session: &PomodoroSession,
+) -> Result<(), AppError> {
+    let settings = settings::load(connection)?;
+    let cycle = pomodoro_cycles::table
+        .find(&session.cycle_id)
+        .first::<PomodoroCycle>(connection)?;
+
+    match session_ended(&settings.notifications, session, &cycle) {
+        Some(notification) => Ok(notifier.notify(notification)?),
+        None => Ok(()),
+    }
+}
+
+/// Notifies that a cycle was completed, if the settings ask for it.
+pub fn notify_cycle_completed(
+    connection: &mut SqliteConnection,
+    notifier: &dyn Notifier,
+) -> Result<(), AppError> {
+    let settings = settings::load(connection)?;
+
+    match cycle_completed(&settings.notifications) {
+        Some(notification) => Ok(notifier.notify(notification)?),
+        None => Ok(()),
+    }
+}
+
+/// Notifies that the running `session_type` session of the current cycle ran
+/// out at `now`, before the window completes it, and returns the session.
+/// The end of a long break is the end of the cycle.
+pub fn notify_phase_ended(
+    connection: &mut SqliteConnection,
+    notifier: &dyn Notifier,
+    session_type: SessionType,
+    now: DateTime<Utc>,
+) -> Result<Option<PomodoroSession>, AppError> {
+    let Some(session) = pomodoro_sessions::table
+        .filter(pomodoro_sessions::completed_at.is_null())
+        .filter(pomodoro_sessions::session_type.eq(session_type))
+        .order(pomodoro_sessions::started_at.desc())
+        .first::<PomodoroSession>(connection)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let cycle = pomodoro_cycles::table
+        .find(&session.cycle_id)
+        .first::<PomodoroCycle>(connection)?;
+
+    // Pauses only make a session longer, so one that can't have run out yet
+    // was started after the phase that ended
+    let runs_until = session.started_at + Duration::milliseconds(session.duration_ms.into());
+    if cycle.status != Status::InProgress || runs_until > now {
+        return Ok(None);
+    }
+
+    let settings = settings::load(connection)?;
+    let ran_out = PomodoroSession {
+        was_completed: true,
+        ..session.clone()
+    };
+    let notification = match session_type {
+        SessionType::LongBreak => cycle_completed(&settings.notifications),
+        _ => session_ended(&settings.notifications, &ran_out, &cycle),
+    };
+    if let Some(notification) = notification {
+        notifier.notify(notification)?;
+    }
+
+    Ok(Some(session))
+}
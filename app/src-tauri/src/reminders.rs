@@ -0,0 +1,379 @@
+//! Reminders for habits at a local time of day, on chosen weekdays.
+//!
+//! The scheduler in the app process calls [`check`] whenever a reminder may
+//! be due. Each reminder remembers when it was last due, so a reminder missed
+//! while the computer was asleep still fires on wake, as long as it is at
+//! most `MISSED_REMINDER_WINDOW_MINUTES` old. A reminder is skipped when its
+//! habit is already completed that day, archived or deleted.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::prelude::*;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteConnection, SqliteValue};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{self, Habit};
+use crate::schema::{habit_completions, habit_reminders, habits};
+use crate::validation::{Validate, Validator};
+
+/// Reminders missed by longer than this, e.g. overnight, are dropped.
+pub const MISSED_REMINDER_WINDOW_MINUTES: i64 = 60;
+const TIME_FORMAT: &str = "%H:%M";
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    pub fn of(date: NaiveDate) -> Self {
+        Weekday::ALL[date.weekday().num_days_from_monday() as usize]
+    }
+}
+
+/// The days a reminder fires on, stored as a JSON array in week order.
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(transparent)]
+pub struct Weekdays(pub Vec<Weekday>);
+
+impl Weekdays {
+    fn new(weekdays: &[Weekday]) -> Self {
+        Weekdays(
+            Weekday::ALL
+                .into_iter()
+                .filter(|weekday| weekdays.contains(weekday))
+                .collect(),
+        )
+    }
+}
+
+impl ToSql<Text, Sqlite> for Weekdays {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(serde_json::to_string(&self.0)?);
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for Weekdays {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(Weekdays(serde_json::from_str(&value)?))
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = habit_reminders)]
+#[serde(rename_all = "camelCase")]
+pub struct HabitReminder {
+    pub id: String,
+    pub habit_id: String,
+    /// Local time of day, `HH:MM`.
+    pub time: String,
+    pub weekdays: Weekdays,
+    pub is_enabled: bool,
+    /// When the reminder was last due, whether it fired or was skipped.
+    pub last_fired_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl HabitReminder {
+    /// The first time after `after` that the reminder is due in `timezone`.
+    pub fn next_fire_at<Z: TimeZone>(
+        &self,
+        after: DateTime<Utc>,
+        timezone: &Z,
+    ) -> Option<DateTime<Utc>> {
+        let time = NaiveTime::parse_from_str(&self.time, TIME_FORMAT).ok()?;
+        let today = after.with_timezone(timezone).date_naive();
+
+        // Today's time may have passed, so look a full week past tomorrow
+        (0..=7)
+            .map(|days| today + Duration::days(days))
+            .filter(|date| self.weekdays.0.contains(&Weekday::of(*date)))
+            .filter_map(|date| to_utc(timezone, date.and_time(time)))
+            .find(|fire_at| *fire_at > after)
+    }
+
+    /// Occurrences up to this are handled. Editing a reminder doesn't fire
+    /// the ones it moves into the past.
+    fn handled_until(&self) -> DateTime<Utc> {
+        match self.last_fired_at {
+            Some(last_fired_at) => last_fired_at.max(self.updated_at),
+            None => self.updated_at,
+        }
+    }
+}
+
+/// `local` in UTC. A time skipped by a daylight saving change is moved an
+/// hour later, an ambiguous one takes the first occurrence.
+fn to_utc<Z: TimeZone>(timezone: &Z, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            timezone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|fire_at| fire_at.with_timezone(&Utc))
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateHabitReminderArgs {
+    pub habit_id: String,
+    pub time: String,
+    pub weekdays: Vec<Weekday>,
+}
+
+impl Validate for CreateHabitReminderArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.not_empty("habitId", &self.habit_id);
+        validator.time("time", &self.time);
+        validate_weekdays(&mut validator, &self.weekdays);
+        validator.finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateHabitReminderArgs {
+    pub id: String,
+    pub time: Option<String>,
+    pub weekdays: Option<Vec<Weekday>>,
+    pub is_enabled: Option<bool>,
+}
+
+impl Validate for UpdateHabitReminderArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.not_empty("id", &self.id);
+        if let Some(time) = &self.time {
+            validator.time("time", time);
+        }
+        if let Some(weekdays) = &self.weekdays {
+            validate_weekdays(&mut validator, weekdays);
+        }
+        validator.finish()
+    }
+}
+
+fn validate_weekdays(validator: &mut Validator, weekdays: &[Weekday]) {
+    if weekdays.is_empty() {
+        validator.error("weekdays", "must not be empty");
+    }
+}
+
+/// A reminder that is due now, sent to the frontend as is.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DueReminder {
+    pub reminder_id: String,
+    pub habit_id: String,
+    pub habit_title: String,
+    pub fire_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReminderCheck {
+    pub due: Vec<DueReminder>,
+    /// When the next reminder is due, `None` without enabled reminders.
+    pub next_at: Option<DateTime<Utc>>,
+}
+
+// --------------------------------------------------------------------------
+// Reminders
+// --------------------------------------------------------------------------
+
+/// The reminders of a habit, earliest in the day first.
+pub fn reminders(
+    connection: &mut SqliteConnection,
+    habit_id: &str,
+) -> QueryResult<Vec<HabitReminder>> {
+    habit_reminders::table
+        .filter(habit_reminders::habit_id.eq(habit_id))
+        .order((
+            habit_reminders::time.asc(),
+            habit_reminders::created_at.asc(),
+        ))
+        .load::<HabitReminder>(connection)
+}
+
+pub fn create_reminder(
+    connection: &mut SqliteConnection,
+    args: CreateHabitReminderArgs,
+) -> Result<HabitReminder, AppError> {
+    args.validate()?;
+
+    let habit = habits::table
+        .find(&args.habit_id)
+        .first::<Habit>(connection)
+        .optional()?;
+    if habit.is_none() {
+        return Err(format!("Unknown habit: {}", args.habit_id).into());
+    }
+
+    let now = models::now();
+    let reminder = HabitReminder {
+        id: Uuid::new_v4().to_string(),
+        habit_id: args.habit_id,
+        time: args.time,
+        weekdays: Weekdays::new(&args.weekdays),
+        is_enabled: true,
+        last_fired_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    diesel::insert_into(habit_reminders::table)
+        .values(&reminder)
+        .execute(connection)?;
+
+    Ok(reminder)
+}
+
+pub fn update_reminder(
+    connection: &mut SqliteConnection,
+    args: UpdateHabitReminderArgs,
+) -> Result<HabitReminder, AppError> {
+    args.validate()?;
+
+    let mut reminder = habit_reminders::table
+        .find(&args.id)
+        .first::<HabitReminder>(connection)?;
+
+    if let Some(time) = args.time {
+        reminder.time = time;
+    }
+    if let Some(weekdays) = args.weekdays {
+        reminder.weekdays = Weekdays::new(&weekdays);
+    }
+    if let Some(is_enabled) = args.is_enabled {
+        reminder.is_enabled = is_enabled;
+    }
+    reminder.updated_at = models::now();
+
+    diesel::update(habit_reminders::table.find(&reminder.id))
+        .set((
+            habit_reminders::time.eq(&reminder.time),
+            habit_reminders::weekdays.eq(&reminder.weekdays),
+            habit_reminders::is_enabled.eq(reminder.is_enabled),
+            habit_reminders::updated_at.eq(reminder.updated_at),
+        ))
+        .execute(connection)?;
+
+    Ok(reminder)
+}
+
+pub fn delete_reminder(
+    connection: &mut SqliteConnection,
+    reminder_id: &str,
+) -> Result<(), AppError> {
+    let deleted = diesel::delete(habit_reminders::table.find(reminder_id)).execute(connection)?;
+    if deleted == 0 {
+        return Err(format!("Unknown reminder: {}", reminder_id).into());
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------------------------------
+// Scheduling
+// --------------------------------------------------------------------------
+
+/// Marks the reminders due at `now` as fired and returns those that should
+/// be shown, along with when to check again.
+pub fn check<Z: TimeZone>(
+    connection: &mut SqliteConnection,
+    now: DateTime<Utc>,
+    timezone: &Z,
+) -> Result<ReminderCheck, AppError> {
+    let reminders = habit_reminders::table
+        .filter(habit_reminders::is_enabled.eq(true))
+        .load::<HabitReminder>(connection)?;
+    let missed_window = Duration::minutes(MISSED_REMINDER_WINDOW_MINUTES);
+    let mut check = ReminderCheck::default();
+
+    for reminder in reminders {
+        let next_at = match reminder.next_fire_at(reminder.handled_until(), timezone) {
+            Some(fire_at) if fire_at <= now => {
+                diesel::update(habit_reminders::table.find(&reminder.id))
+                    .set(habit_reminders::last_fired_at.eq(now))
+                    .execute(connection)?;
+
+                let date = fire_at.with_timezone(timezone).date_naive();
+                if now - fire_at <= missed_window {
+                    if let Some(habit) = habit_to_remind(connection, &reminder.habit_id, date)? {
+                        check.due.push(DueReminder {
+                            reminder_id: reminder.id.clone(),
+                            habit_id: habit.id,
+                            habit_title: habit.title,
+                            fire_at,
+                        });
+                    }
+                }
+
+                reminder.next_fire_at(now, timezone)
+            }
+            next_at => next_at,
+        };
+
+        if let Some(next_at) = next_at {
+            check.next_at = Some(check.next_at.map_or(next_at, |at| at.min(next_at)));
+        }
+    }
+
+    Ok(check)
+}
+
+/// The habit, unless it is gone, archived or already completed on `date`.
+fn habit_to_remind(
+    connection: &mut SqliteConnection,
+    habit_id: &str,
+    date: NaiveDate,
+) -> QueryResult<Option<Habit>> {
+    let habit = habits::table
+        .find(habit_id)
+        .filter(habits::is_archived.eq(false))
+        .first::<Habit>(connection)
+        .optional()?;
+
+    let completed = diesel::select(diesel::dsl::exists(
+        habit_completions::table
+            .filter(habit_completions::habit_id.eq(habit_id))
+            .filter(habit_completions::created_at.eq(date)),
+    ))
+    .get_result::<bool>(connection)?;
+
+    Ok(habit.filter(|_| !completed))
+}
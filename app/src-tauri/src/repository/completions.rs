@@ -0,0 +1,221 @@
+use chrono::{Duration, NaiveDate};
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::Mutation;
+use crate::error::AppError;
+use crate::events::{self, Operation};
+use crate::models::{CreateHabitCompletion, Habit, HabitCompletion};
+use crate::schema::{habit_completions, habits};
+use crate::settings;
+use crate::validation::{Validate, Validator};
+use crate::webhooks::{self, WebhookEventType};
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateHabitCompletionArgs {
+    pub id: Option<String>,
+    pub habit_id: String,
+    pub created_at: Option<String>,
+}
+
+impl Validate for CreateHabitCompletionArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.not_empty("habitId", &self.habit_id);
+        if let Some(created_at) = &self.created_at {
+            validator.date("createdAt", created_at);
+        }
+        validator.finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetHabitCompletionsArgs {
+    pub habit_id: String,
+    pub limit: Option<i32>,
+}
+
+impl Validate for GetHabitCompletionsArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.not_empty("habitId", &self.habit_id);
+        if let Some(limit) = self.limit {
+            validator.range("limit", limit, 1, i32::MAX);
+        }
+        validator.finish()
+    }
+}
+
+pub trait CompletionRepository {
+    /// Removes the completion `args.id` if given, otherwise marks the habit as
+    /// completed on `args.created_at`, today by default. Returns the completion
+    /// that was removed or created.
+    fn toggle_habit_completion(
+        &mut self,
+        args: CreateHabitCompletionArgs,
+    ) -> Result<Mutation<HabitCompletion>, AppError>;
+    /// Completions of a habit, newest first.
+    fn habit_completions(
+        &mut self,
+        args: GetHabitCompletionsArgs,
+    ) -> Result<Vec<HabitCompletion>, AppError>;
+    /// Completions of any habit on `date`.
+    fn completions_on(&mut self, date: NaiveDate) -> Result<Vec<HabitCompletion>, AppError>;
+    /// Number of consecutive days up to `today` the habit was completed on. A
+    /// streak that ended yesterday still counts until today is over.
+    fn habit_completion_streak(
+        &mut self,
+        habit_id: &str,
+        today: NaiveDate,
+    ) -> Result<i32, AppError>;
+}
+
+impl CompletionRepository for SqliteConnection {
+    fn toggle_habit_completion(
+        &mut self,
+        args: CreateHabitCompletionArgs,
+    ) -> Result<Mutation<HabitCompletion>, AppError> {
+        args.validate()?;
+
+        // Without a date the day is marked as today in the configured timezone
+        let created_at = match args
+            .created_at
+            .and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok())
+        {
+            Some(date) => date,
+            None => settings::load(self)?.today(),
+        };
+
+        let new_habit_completion = CreateHabitCompletion {
+            id: args
+                .id
+                .clone()
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            habit_id: args.habit_id,
+            created_at,
+        };
+
+        let mutation = match args.id {
+            Some(id) => self.transaction(|connection| {
+                let before = habit_completions::table
+                    .find(&id)
+                    .first::<HabitCompletion>(connection)?;
+
+                diesel::delete(habit_completions::table.find(&id)).execute(connection)?;
+
+                let event = events::record(
+                    connection,
+                    events::HABIT_COMPLETIONS,
+                    &id,
+                    Operation::Delete,
+                    Some(&before),
+                    None,
+                )?;
+
+                let habit_title = habits::table
+                    .find(&before.habit_id)
+                    .select(habits::title)
+                    .first::<String>(connection)?;
+
+                Ok::<_, diesel::result::Error>(Mutation {
+                    description: format!("Unmarked \"{}\" on {}", habit_title, before.created_at),
+                    value: before,
+                    event,
+                })
+            })?,
+            None => self.transaction(|connection| {
+                diesel::insert_into(habit_completions::table)
+                    .values(&new_habit_completion)
+                    .execute(connection)?;
+
+                let after = habit_completions::table
+                    .find(&new_habit_completion.id)
+                    .first::<HabitCompletion>(connection)?;
+
+                let event = events::record(
+                    connection,
+                    events::HABIT_COMPLETIONS,
+                    &after.id,
+                    Operation::Create,
+                    None,
+                    Some(&after),
+                )?;
+
+                let habit = habits::table
+                    .find(&after.habit_id)
+                    .first::<Habit>(connection)?;
+
+                webhooks::enqueue(
+                    connection,
+                    WebhookEventType::HabitCompleted,
+                    &json!({ "habit": habit, "completion": after }),
+                )?;
+
+                let streak = connection.habit_completion_streak(&habit.id, after.created_at)?;
+                webhooks::enqueue_streak_milestone(connection, &habit, after.created_at, streak)?;
+
+                Ok::<_, AppError>(Mutation {
+                    description: format!("Marked \"{}\" on {}", habit.title, after.created_at),
+                    value: after,
+                    event,
+                })
+            })?,
+        };
+
+        Ok(mutation)
+    }
+
+    fn habit_completions(
+        &mut self,
+        args: GetHabitCompletionsArgs,
+    ) -> Result<Vec<HabitCompletion>, AppError> {
+        args.validate()?;
+
+        let query = habit_completions::table
+            .filter(habit_completions::habit_id.eq(args.habit_id))
+            .order(habit_completions::created_at.desc());
+
+        Ok(match args.limit {
+            Some(limit) => query.limit(limit as i64).load::<HabitCompletion>(self)?,
+            None => query.load::<HabitCompletion>(self)?,
+        })
+    }
+
+    fn completions_on(&mut self, date: NaiveDate) -> Result<Vec<HabitCompletion>, AppError> {
+        Ok(habit_completions::table
+            .filter(habit_completions::created_at.eq(date))
+            .load::<HabitCompletion>(self)?)
+    }
+
+    fn habit_completion_streak(
+        &mut self,
+        habit_id: &str,
+        today: NaiveDate,
+    ) -> Result<i32, AppError> {
+        let dates: Vec<NaiveDate> = habit_completions::table
+            .filter(habit_completions::habit_id.eq(habit_id))
+            .select(habit_completions::created_at)
+            .load::<NaiveDate>(self)?;
+
+        let mut streak = 0;
+        let mut previous_date = today - Duration::days(1);
+
+        // Iterate backwards from today and check if each date exists
+        while dates.contains(&previous_date) {
+            streak += 1;
+            previous_date -= Duration::days(1);
+        }
+
+        if dates.contains(&today) {
+            streak += 1;
+        }
+
+        Ok(streak)
+    }
+}
@@ -0,0 +1,176 @@
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use uuid::Uuid;
+
+use super::Mutation;
+use crate::error::AppError;
+use crate::events::{self, Operation};
+use crate::models::{self, Habit, UpdateHabit};
+use crate::schema::habits;
+use crate::validation::{Validate, Validator};
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateHabitArgs {
+    pub title: String,
+    pub description: String,
+    pub icon: String,
+}
+
+impl Validate for CreateHabitArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new().title("title", &self.title).finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateHabitArgs {
+    pub id: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub is_archived: Option<bool>,
+}
+
+impl Validate for UpdateHabitArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.not_empty("id", &self.id);
+        if let Some(title) = &self.title {
+            validator.title("title", title);
+        }
+        validator.finish()
+    }
+}
+
+pub trait HabitRepository {
+    fn create_habit(&mut self, args: CreateHabitArgs) -> Result<Habit, AppError>;
+    /// Habits that aren't archived, newest first.
+    fn active_habits(&mut self) -> Result<Vec<Habit>, AppError>;
+    /// Archived habits, newest first.
+    fn archived_habits(&mut self) -> Result<Vec<Habit>, AppError>;
+    fn update_habit(&mut self, args: UpdateHabitArgs) -> Result<Mutation<Habit>, AppError>;
+    /// Deletes the habit and returns it as it was before.
+    fn delete_habit(&mut self, habit_id: &str) -> Result<Mutation<Habit>, AppError>;
+}
+
+impl HabitRepository for SqliteConnection {
+    fn create_habit(&mut self, args: CreateHabitArgs) -> Result<Habit, AppError> {
+        args.validate()?;
+
+        let now = models::now();
+        let new_habit = Habit {
+            id: Uuid::new_v4().to_string(),
+            title: args.title,
+            description: args.description,
+            icon: args.icon,
+            is_archived: false,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.transaction(|connection| {
+            diesel::insert_into(habits::table)
+                .values(&new_habit)
+                .execute(connection)?;
+
+            events::record(
+                connection,
+                events::HABITS,
+                &new_habit.id,
+                Operation::Create,
+                None,
+                Some(&new_habit),
+            )
+        })?;
+
+        Ok(new_habit)
+    }
+
+    fn active_habits(&mut self) -> Result<Vec<Habit>, AppError> {
+        Ok(habits::table
+            .filter(habits::is_archived.eq(false))
+            .order(habits::created_at.desc())
+            .load::<Habit>(self)?)
+    }
+
+    fn archived_habits(&mut self) -> Result<Vec<Habit>, AppError> {
+        Ok(habits::table
+            .filter(habits::is_archived.eq(true))
+            .order(habits::created_at.desc())
+            .load::<Habit>(self)?)
+    }
+
+    fn update_habit(&mut self, args: UpdateHabitArgs) -> Result<Mutation<Habit>, AppError> {
+        args.validate()?;
+
+        let updated_habit = UpdateHabit {
+            title: args.title,
+            description: args.description,
+            icon: args.icon,
+            is_archived: args.is_archived,
+            updated_at: models::now(),
+        };
+
+        let (event, before, after) = self.transaction(|connection| {
+            let before = habits::table.find(&args.id).first::<Habit>(connection)?;
+
+            diesel::update(habits::table.find(&args.id))
+                .set(&updated_habit)
+                .execute(connection)?;
+
+            let after = habits::table.find(&args.id).first::<Habit>(connection)?;
+
+            let event = events::record(
+                connection,
+                events::HABITS,
+                &args.id,
+                Operation::Update,
+                Some(&before),
+                Some(&after),
+            )?;
+
+            Ok::<_, diesel::result::Error>((event, before, after))
+        })?;
+
+        let description = match (before.is_archived, after.is_archived) {
+            (false, true) => format!("Archived \"{}\"", after.title),
+            (true, false) => format!("Unarchived \"{}\"", after.title),
+            _ => format!("Edited \"{}\"", after.title),
+        };
+
+        Ok(Mutation {
+            value: after,
+            event,
+            description,
+        })
+    }
+
+    fn delete_habit(&mut self, habit_id: &str) -> Result<Mutation<Habit>, AppError> {
+        let (event, before) = self.transaction(|connection| {
+            let before = habits::table.find(habit_id).first::<Habit>(connection)?;
+
+            diesel::delete(habits::table.find(habit_id)).execute(connection)?;
+
+            let event = events::record(
+                connection,
+                events::HABITS,
+                habit_id,
+                Operation::Delete,
+                Some(&before),
+                None,
+            )?;
+
+            Ok::<_, diesel::result::Error>((event, before))
+        })?;
+
+        Ok(Mutation {
+            description: format!("Deleted \"{}\"", before.title),
+            value: before,
+            event,
+        })
+    }
+}
@@ -0,0 +1,27 @@
+//! Data access shared by the resolvers and tests. Every repository is
+//! implemented for a plain `SqliteConnection`, so callers need neither a Tauri
+//! app nor a connection pool.
+
+use crate::models::Event;
+
+mod completions;
+mod habits;
+mod pomodoro;
+mod presets;
+
+pub use completions::{CompletionRepository, CreateHabitCompletionArgs, GetHabitCompletionsArgs};
+pub use habits::{CreateHabitArgs, HabitRepository, UpdateHabitArgs};
+pub use pomodoro::{
+    CompleteSessionArgs, CreateSessionArgs, GetPomodoroCycleWithRelationships, PomodoroRepository,
+    StartCycleArgs, UpdateCycleArgs,
+};
+pub use presets::{CreatePresetArgs, PresetRepository, UpdatePresetArgs};
+
+/// The outcome of a mutation that can be undone: the affected row, the event
+/// recorded for it and how the action is described to the user.
+#[derive(Debug, Clone)]
+pub struct Mutation<T> {
+    pub value: T,
+    pub event: Event,
+    pub description: String,
+}
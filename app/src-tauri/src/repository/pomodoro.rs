@@ -0,0 +1,454 @@
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::Mutation;
+use crate::error::AppError;
+use crate::events::{self, Operation};
+use crate::export::DateRange;
+use crate::idle::InterruptionResolution;
+use crate::models::{
+    self, CreatePomodoroCycle, CreatePomodoroSession, PomodoroCycle, PomodoroPreset,
+    PomodoroSession, SessionType, Status, UpdatePomodoroCycle, UpdatePomodoroSession,
+};
+use crate::schema::{pomodoro_cycles, pomodoro_interruptions, pomodoro_presets, pomodoro_sessions};
+use crate::settings;
+use crate::validation::{Validate, Validator};
+use crate::webhooks::{self, WebhookEventType};
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPomodoroCycleWithRelationships {
+    pub id: String,
+    pub status: Status,
+    pub focus_duration: i32,
+    pub short_break_duration: i32,
+    pub long_break_duration: i32,
+    pub sessions_until_long_break: i32,
+    pub auto_start_breaks: bool,
+    pub auto_start_pomodoros: bool,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+    pub preset_id: Option<String>,
+    pub sessions: Vec<PomodoroSession>,
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StartCycleArgs {
+    /// Values missing from the args are taken from this preset, or from the
+    /// default preset if none is given.
+    pub preset_id: Option<String>,
+    pub focus_duration: Option<i32>,
+    pub short_break_duration: Option<i32>,
+    pub long_break_duration: Option<i32>,
+    pub sessions_until_long_break: Option<i32>,
+    pub auto_start_breaks: Option<bool>,
+    pub auto_start_pomodoros: Option<bool>,
+}
+
+impl Validate for StartCycleArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        if let Some(preset_id) = &self.preset_id {
+            validator.not_empty("presetId", preset_id);
+        }
+        for (field, duration) in [
+            ("focusDuration", self.focus_duration),
+            ("shortBreakDuration", self.short_break_duration),
+            ("longBreakDuration", self.long_break_duration),
+        ] {
+            if let Some(duration) = duration {
+                validator.duration_ms(field, duration);
+            }
+        }
+        if let Some(sessions) = self.sessions_until_long_break {
+            validator.sessions_until_long_break("sessionsUntilLongBreak", sessions);
+        }
+        validator.finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCycleArgs {
+    pub id: String,
+    pub status: Status,
+}
+
+impl Validate for UpdateCycleArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new().not_empty("id", &self.id).finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSessionArgs {
+    pub cycle_id: String,
+    pub session_type: SessionType,
+    pub duration_ms: i32,
+}
+
+impl Validate for CreateSessionArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .not_empty("cycleId", &self.cycle_id)
+            .duration_ms("durationMs", self.duration_ms)
+            .finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CompleteSessionArgs {
+    pub session_id: String,
+    pub was_completed: bool,
+}
+
+impl Validate for CompleteSessionArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .not_empty("sessionId", &self.session_id)
+            .finish()
+    }
+}
+
+pub trait PomodoroRepository {
+    /// Starts a cycle from the args, falling back to a preset and then to the
+    /// settings for every value that isn't given.
+    fn start_pomodoro_cycle(&mut self, args: StartCycleArgs) -> Result<PomodoroCycle, AppError>;
+    /// The most recently started cycle that is still in progress.
+    fn current_cycle(&mut self) -> Result<Option<GetPomodoroCycleWithRelationships>, AppError>;
+    fn update_cycle_status(
+        &mut self,
+        args: UpdateCycleArgs,
+    ) -> Result<Mutation<PomodoroCycle>, AppError>;
+    fn start_session(&mut self, args: CreateSessionArgs) -> Result<PomodoroSession, AppError>;
+    fn complete_session(&mut self, args: CompleteSessionArgs) -> Result<PomodoroSession, AppError>;
+    /// Whole minutes of completed focus sessions started on the local
+    /// calendar day `date`, formatted as `%Y-%m-%d`, without the idle time
+    /// the user discarded.
+    fn daily_stats(&mut self, date: &str) -> Result<i32, AppError>;
+}
+
+impl PomodoroRepository for SqliteConnection {
+    fn start_pomodoro_cycle(&mut self, args: StartCycleArgs) -> Result<PomodoroCycle, AppError> {
+        args.validate()?;
+
+        let preset = match &args.preset_id {
+            Some(preset) => Some(
+                pomodoro_presets::table
+                    .find(preset)
+                    .first::<PomodoroPreset>(self)
+                    .optional()?
+                    .ok_or_else(|| format!("Unknown preset: {}", preset))?,
+            ),
+            None => pomodoro_presets::table
+                .filter(pomodoro_presets::is_default.eq(true))
+                .first::<PomodoroPreset>(self)
+                .optional()?,
+        };
+
+        // Only cycles that actually took values from a preset reference it
+        let uses_preset = args.preset_id.is_some()
+            || args.focus_duration.is_none()
+            || args.short_break_duration.is_none()
+            || args.long_break_duration.is_none()
+            || args.sessions_until_long_break.is_none()
+            || args.auto_start_breaks.is_none()
+            || args.auto_start_pomodoros.is_none();
+        let preset = preset.filter(|_| uses_preset);
+
+        // Settings fill in whatever neither the args nor a preset provide
+        let defaults = settings::load(self)?;
+
+        let new_cycle = CreatePomodoroCycle {
+            id: Uuid::new_v4().to_string(),
+            focus_duration: args
+                .focus_duration
+                .or(preset.as_ref().map(|p| p.focus_duration))
+                .unwrap_or(defaults.focus_duration),
+            short_break_duration: args
+                .short_break_duration
+                .or(preset.as_ref().map(|p| p.short_break_duration))
+                .unwrap_or(defaults.short_break_duration),
+            long_break_duration: args
+                .long_break_duration
+                .or(preset.as_ref().map(|p| p.long_break_duration))
+                .unwrap_or(defaults.long_break_duration),
+            sessions_until_long_break: args
+                .sessions_until_long_break
+                .or(preset.as_ref().map(|p| p.sessions_until_long_break))
+                .unwrap_or(defaults.sessions_until_long_break),
+            auto_start_breaks: args
+                .auto_start_breaks
+                .or(preset.as_ref().map(|p| p.auto_start_breaks))
+                .unwrap_or(defaults.auto_start_breaks),
+            auto_start_pomodoros: args
+                .auto_start_pomodoros
+                .or(preset.as_ref().map(|p| p.auto_start_pomodoros))
+                .unwrap_or(defaults.auto_start_pomodoros),
+            started_at: models::now(),
+            updated_at: models::now(),
+            preset_id: preset.map(|p| p.id),
+        };
+
+        self.transaction::<_, AppError, _>(|connection| {
+            diesel::insert_into(pomodoro_cycles::table)
+                .values(&new_cycle)
+                .execute(connection)?;
+
+            let cycle = pomodoro_cycles::table
+                .find(&new_cycle.id)
+                .first::<PomodoroCycle>(connection)?;
+
+            events::record(
+                connection,
+                events::POMODORO_CYCLES,
+                &cycle.id,
+                Operation::Create,
+                None,
+                Some(&cycle),
+            )?;
+
+            Ok(cycle)
+        })
+    }
+
+    fn current_cycle(&mut self) -> Result<Option<GetPomodoroCycleWithRelationships>, AppError> {
+        let Some(cycle) = pomodoro_cycles::table
+            .filter(pomodoro_cycles::status.eq(Status::InProgress))
+            .order(pomodoro_cycles::started_at.desc())
+            .first::<PomodoroCycle>(self)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let sessions = pomodoro_sessions::table
+            .filter(pomodoro_sessions::cycle_id.eq(&cycle.id))
+            .load::<PomodoroSession>(self)?;
+
+        Ok(Some(GetPomodoroCycleWithRelationships {
+            id: cycle.id,
+            status: cycle.status,
+            focus_duration: cycle.focus_duration,
+            short_break_duration: cycle.short_break_duration,
+            long_break_duration: cycle.long_break_duration,
+            sessions_until_long_break: cycle.sessions_until_long_break,
+            auto_start_breaks: cycle.auto_start_breaks,
+            auto_start_pomodoros: cycle.auto_start_pomodoros,
+            started_at: cycle.started_at,
+            completed_at: cycle.completed_at,
+            updated_at: cycle.updated_at,
+            preset_id: cycle.preset_id,
+            sessions,
+        }))
+    }
+
+    fn update_cycle_status(
+        &mut self,
+        args: UpdateCycleArgs,
+    ) -> Result<Mutation<PomodoroCycle>, AppError> {
+        args.validate()?;
+
+        let update = UpdatePomodoroCycle {
+            status: Some(args.status),
+            completed_at: if matches!(args.status, Status::Completed | Status::Abandoned) {
+                Some(models::now())
+            } else {
+                None
+            },
+            updated_at: models::now(),
+        };
+
+        self.transaction::<_, AppError, _>(|connection| {
+            let before = pomodoro_cycles::table
+                .find(&args.id)
+                .first::<PomodoroCycle>(connection)?;
+
+            diesel::update(pomodoro_cycles::table.find(&args.id))
+                .set(&update)
+                .execute(connection)?;
+
+            let after = pomodoro_cycles::table
+                .find(&args.id)
+                .first::<PomodoroCycle>(connection)?;
+
+            let event = events::record(
+                connection,
+                events::POMODORO_CYCLES,
+                &args.id,
+                Operation::Update,
+                Some(&before),
+                Some(&after),
+            )?;
+
+            if before.status != Status::Completed && after.status == Status::Completed {
+                webhooks::enqueue(
+                    connection,
+                    WebhookEventType::CycleCompleted,
+                    &json!({ "cycle": after }),
+                )?;
+            }
+
+            Ok(Mutation {
+                value: after,
+                event,
+                description: format!(
+                    "Marked pomodoro cycle as {}",
+                    args.status.as_str().to_lowercase()
+                ),
+            })
+        })
+    }
+
+    fn start_session(&mut self, args: CreateSessionArgs) -> Result<PomodoroSession, AppError> {
+        args.validate()?;
+
+        let cycle_exists = pomodoro_cycles::table
+            .find(&args.cycle_id)
+            .count()
+            .get_result::<i64>(self)?
+            > 0;
+        if !cycle_exists {
+            Validator::new()
+                .error("cycleId", format!("unknown cycle {}", args.cycle_id))
+                .finish()?;
+        }
+
+        let new_session = CreatePomodoroSession {
+            id: Uuid::new_v4().to_string(),
+            cycle_id: args.cycle_id,
+            session_type: args.session_type,
+            started_at: models::now(),
+            duration_ms: args.duration_ms,
+        };
+
+        self.transaction::<_, AppError, _>(|connection| {
+            diesel::insert_into(pomodoro_sessions::table)
+                .values(&new_session)
+                .execute(connection)?;
+
+            let session = pomodoro_sessions::table
+                .find(&new_session.id)
+                .first::<PomodoroSession>(connection)?;
+
+            events::record(
+                connection,
+                events::POMODORO_SESSIONS,
+                &session.id,
+                Operation::Create,
+                None,
+                Some(&session),
+            )?;
+
+            webhooks::enqueue(
+                connection,
+                WebhookEventType::SessionStarted,
+                &json!({ "session": session }),
+            )?;
+
+            Ok(session)
+        })
+    }
+
+    fn complete_session(&mut self, args: CompleteSessionArgs) -> Result<PomodoroSession, AppError> {
+        args.validate()?;
+
+        let update = UpdatePomodoroSession {
+            completed_at: Some(models::now()),
+            was_completed: args.was_completed,
+        };
+
+        self.transaction::<_, AppError, _>(|connection| {
+            let before = pomodoro_sessions::table
+                .find(&args.session_id)
+                .first::<PomodoroSession>(connection)?;
+
+            // The timer and a notification action can both end a session
+            if before.completed_at.is_some() {
+                return Ok(before);
+            }
+
+            diesel::update(pomodoro_sessions::table.find(&args.session_id))
+                .set(&update)
+                .execute(connection)?;
+
+            let after = pomodoro_sessions::table
+                .find(&args.session_id)
+                .first::<PomodoroSession>(connection)?;
+
+            events::record(
+                connection,
+                events::POMODORO_SESSIONS,
+                &args.session_id,
+                Operation::Update,
+                Some(&before),
+                Some(&after),
+            )?;
+
+            // Skipped sessions are completed too, but not reported
+            if after.was_completed {
+                webhooks::enqueue(
+                    connection,
+                    WebhookEventType::SessionCompleted,
+                    &json!({ "session": after }),
+                )?;
+            }
+
+            Ok(after)
+        })
+    }
+
+    fn daily_stats(&mut self, date: &str) -> Result<i32, AppError> {
+        // The day is a calendar day in the configured timezone, sessions are
+        // stored in UTC
+        let day = DateRange::parse(date, date)?;
+        let (start_of_day, end_of_day) = match settings::load(self)?
+            .timezone
+            .as_deref()
+            .map(str::parse::<Tz>)
+        {
+            Some(Ok(timezone)) => day.bounds(&timezone),
+            _ => day.bounds(&Local),
+        };
+
+        let sessions = pomodoro_sessions::table
+            .filter(pomodoro_sessions::session_type.eq(SessionType::Focus))
+            .filter(pomodoro_sessions::was_completed.eq(true))
+            .filter(pomodoro_sessions::started_at.ge(start_of_day))
+            .filter(pomodoro_sessions::started_at.lt(end_of_day));
+
+        let total_ms: i64 = sessions
+            .select(pomodoro_sessions::duration_ms)
+            .load::<i32>(self)?
+            .into_iter()
+            .map(i64::from)
+            .sum();
+        let discarded_ms: i64 = pomodoro_interruptions::table
+            .filter(pomodoro_interruptions::resolution.eq(InterruptionResolution::Discard))
+            .filter(
+                pomodoro_interruptions::session_id.eq_any(sessions.select(pomodoro_sessions::id)),
+            )
+            .select(pomodoro_interruptions::counted_ms)
+            .load::<i32>(self)?
+            .into_iter()
+            .map(i64::from)
+            .sum();
+        let total_ms = (total_ms - discarded_ms).max(0);
+
+        // Stats are reported in whole minutes
+        Ok((total_ms / 60_000) as i32)
+    }
+}
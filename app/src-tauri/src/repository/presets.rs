@@ -0,0 +1,232 @@
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::events::{self, Operation};
+use crate::models::{self, PomodoroPreset, UpdatePomodoroPreset};
+use crate::schema::pomodoro_presets;
+use crate::validation::{Validate, Validator};
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePresetArgs {
+    pub name: String,
+    pub focus_duration: i32,
+    pub short_break_duration: i32,
+    pub long_break_duration: i32,
+    pub sessions_until_long_break: i32,
+    pub auto_start_breaks: bool,
+    pub auto_start_pomodoros: bool,
+}
+
+impl Validate for CreatePresetArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        Validator::new()
+            .title("name", &self.name)
+            .duration_ms("focusDuration", self.focus_duration)
+            .duration_ms("shortBreakDuration", self.short_break_duration)
+            .duration_ms("longBreakDuration", self.long_break_duration)
+            .sessions_until_long_break("sessionsUntilLongBreak", self.sessions_until_long_break)
+            .finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePresetArgs {
+    pub id: String,
+    pub name: Option<String>,
+    pub focus_duration: Option<i32>,
+    pub short_break_duration: Option<i32>,
+    pub long_break_duration: Option<i32>,
+    pub sessions_until_long_break: Option<i32>,
+    pub auto_start_breaks: Option<bool>,
+    pub auto_start_pomodoros: Option<bool>,
+}
+
+impl Validate for UpdatePresetArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.not_empty("id", &self.id);
+        if let Some(name) = &self.name {
+            validator.title("name", name);
+        }
+        for (field, duration) in [
+            ("focusDuration", self.focus_duration),
+            ("shortBreakDuration", self.short_break_duration),
+            ("longBreakDuration", self.long_break_duration),
+        ] {
+            if let Some(duration) = duration {
+                validator.duration_ms(field, duration);
+            }
+        }
+        if let Some(sessions) = self.sessions_until_long_break {
+            validator.sessions_until_long_break("sessionsUntilLongBreak", sessions);
+        }
+        validator.finish()
+    }
+}
+
+pub trait PresetRepository {
+    /// All presets, the default one first.
+    fn presets(&mut self) -> Result<Vec<PomodoroPreset>, AppError>;
+    fn create_preset(&mut self, args: CreatePresetArgs) -> Result<PomodoroPreset, AppError>;
+    fn update_preset(&mut self, args: UpdatePresetArgs) -> Result<PomodoroPreset, AppError>;
+    /// Deletes any preset but the default one.
+    fn delete_preset(&mut self, preset_id: &str) -> Result<(), AppError>;
+    fn set_default_preset(&mut self, preset_id: &str) -> Result<(), AppError>;
+}
+
+impl PresetRepository for SqliteConnection {
+    fn presets(&mut self) -> Result<Vec<PomodoroPreset>, AppError> {
+        Ok(pomodoro_presets::table
+            .order((
+                pomodoro_presets::is_default.desc(),
+                pomodoro_presets::name.asc(),
+            ))
+            .load::<PomodoroPreset>(self)?)
+    }
+
+    fn create_preset(&mut self, args: CreatePresetArgs) -> Result<PomodoroPreset, AppError> {
+        args.validate()?;
+
+        let new_preset = PomodoroPreset {
+            id: Uuid::new_v4().to_string(),
+            name: args.name,
+            focus_duration: args.focus_duration,
+            short_break_duration: args.short_break_duration,
+            long_break_duration: args.long_break_duration,
+            sessions_until_long_break: args.sessions_until_long_break,
+            auto_start_breaks: args.auto_start_breaks,
+            auto_start_pomodoros: args.auto_start_pomodoros,
+            is_default: false,
+            created_at: models::now(),
+            updated_at: models::now(),
+        };
+
+        self.transaction(|connection| {
+            diesel::insert_into(pomodoro_presets::table)
+                .values(&new_preset)
+                .execute(connection)?;
+
+            events::record(
+                connection,
+                events::POMODORO_PRESETS,
+                &new_preset.id,
+                Operation::Create,
+                None,
+                Some(&new_preset),
+            )
+        })?;
+
+        Ok(new_preset)
+    }
+
+    fn update_preset(&mut self, args: UpdatePresetArgs) -> Result<PomodoroPreset, AppError> {
+        args.validate()?;
+
+        let update = UpdatePomodoroPreset {
+            name: args.name,
+            focus_duration: args.focus_duration,
+            short_break_duration: args.short_break_duration,
+            long_break_duration: args.long_break_duration,
+            sessions_until_long_break: args.sessions_until_long_break,
+            auto_start_breaks: args.auto_start_breaks,
+            auto_start_pomodoros: args.auto_start_pomodoros,
+            updated_at: models::now(),
+        };
+
+        self.transaction::<_, AppError, _>(|connection| {
+            let before = pomodoro_presets::table
+                .find(&args.id)
+                .first::<PomodoroPreset>(connection)?;
+
+            diesel::update(pomodoro_presets::table.find(&args.id))
+                .set(&update)
+                .execute(connection)?;
+
+            let after = pomodoro_presets::table
+                .find(&args.id)
+                .first::<PomodoroPreset>(connection)?;
+
+            events::record(
+                connection,
+                events::POMODORO_PRESETS,
+                &args.id,
+                Operation::Update,
+                Some(&before),
+                Some(&after),
+            )?;
+
+            Ok(after)
+        })
+    }
+
+    fn delete_preset(&mut self, preset_id: &str) -> Result<(), AppError> {
+        let before = pomodoro_presets::table
+            .find(preset_id)
+            .first::<PomodoroPreset>(self)?;
+
+        if before.is_default {
+            return Err("The default preset cannot be deleted".into());
+        }
+
+        // Cycles keep their preset_id, so stats can still tell them apart
+        self.transaction(|connection| {
+            diesel::delete(pomodoro_presets::table.find(preset_id)).execute(connection)?;
+
+            events::record(
+                connection,
+                events::POMODORO_PRESETS,
+                preset_id,
+                Operation::Delete,
+                Some(&before),
+                None,
+            )
+        })?;
+
+        Ok(())
+    }
+
+    fn set_default_preset(&mut self, preset_id: &str) -> Result<(), AppError> {
+        self.transaction::<_, AppError, _>(|connection| {
+            let presets = pomodoro_presets::table.load::<PomodoroPreset>(connection)?;
+            if !presets.iter().any(|preset| preset.id == preset_id) {
+                return Err(format!("Unknown preset: {}", preset_id).into());
+            }
+
+            // Exactly one preset is the default at any time
+            for before in presets {
+                let make_default = before.id == preset_id;
+                if before.is_default == make_default {
+                    continue;
+                }
+
+                diesel::update(pomodoro_presets::table.find(&before.id))
+                    .set((
+                        pomodoro_presets::is_default.eq(make_default),
+                        pomodoro_presets::updated_at.eq(models::now()),
+                    ))
+                    .execute(connection)?;
+
+                let after = pomodoro_presets::table
+                    .find(&before.id)
+                    .first::<PomodoroPreset>(connection)?;
+
+                events::record(
+                    connection,
+                    events::POMODORO_PRESETS,
+                    &before.id,
+                    Operation::Update,
+                    Some(&before),
+                    Some(&after),
+                )?;
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -1,10 +1,35 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    events (id) {
+        id -> Text,
+        entity -> Text,
+        entity_id -> Text,
+        operation -> Text,
+        before -> Nullable<Text>,
+        after -> Nullable<Text>,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
 diesel::table! {
     habit_completions (id) {
         id -> Text,
         habit_id -> Text,
-        created_at -> Timestamp,
+        created_at -> Date,
+    }
+}
+
+diesel::table! {
+    habit_reminders (id) {
+        id -> Text,
+        habit_id -> Text,
+        time -> Text,
+        weekdays -> Text,
+        is_enabled -> Bool,
+        last_fired_at -> Nullable<TimestamptzSqlite>,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
     }
 }
 
@@ -15,8 +40,22 @@ diesel::table! {
         description -> Text,
         icon -> Text,
         is_archived -> Bool,
-        created_at -> Timestamp,
-        updated_at -> Timestamp,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    license (id) {
+        id -> Integer,
+        license_key -> Text,
+        instance_id -> Text,
+        instance_name -> Text,
+        status -> Text,
+        expires_at -> Nullable<TimestamptzSqlite>,
+        activated_at -> TimestamptzSqlite,
+        validated_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
     }
 }
 
@@ -30,9 +69,38 @@ diesel::table! {
         sessions_until_long_break -> Integer,
         auto_start_breaks -> Bool,
         auto_start_pomodoros -> Bool,
-        started_at -> Timestamp,
-        completed_at -> Nullable<Timestamp>,
-        updated_at -> Timestamp,
+        started_at -> TimestamptzSqlite,
+        completed_at -> Nullable<TimestamptzSqlite>,
+        updated_at -> TimestamptzSqlite,
+        preset_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    pomodoro_interruptions (id) {
+        id -> Text,
+        session_id -> Text,
+        started_at -> TimestamptzSqlite,
+        ended_at -> Nullable<TimestamptzSqlite>,
+        counted_ms -> Integer,
+        resolution -> Nullable<Text>,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    pomodoro_presets (id) {
+        id -> Text,
+        name -> Text,
+        focus_duration -> Integer,
+        short_break_duration -> Integer,
+        long_break_duration -> Integer,
+        sessions_until_long_break -> Integer,
+        auto_start_breaks -> Bool,
+        auto_start_pomodoros -> Bool,
+        is_default -> Bool,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
     }
 }
 
@@ -41,19 +109,109 @@ diesel::table! {
         id -> Text,
         cycle_id -> Text,
         session_type -> Text,
-        started_at -> Timestamp,
-        completed_at -> Nullable<Timestamp>,
-        duration_minutes -> Integer,
+        started_at -> TimestamptzSqlite,
+        completed_at -> Nullable<TimestamptzSqlite>,
+        duration_ms -> Integer,
         was_completed -> Bool,
     }
 }
 
+diesel::table! {
+    settings (key) {
+        key -> Text,
+        value -> Text,
+        updated_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    streak_milestones (habit_id, started_on, streak) {
+        habit_id -> Text,
+        started_on -> Date,
+        streak -> Integer,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    sync_batches (device_id, name) {
+        device_id -> Text,
+        name -> Text,
+        pulled_at -> Text,
+    }
+}
+
+diesel::table! {
+    sync_changes (seq) {
+        seq -> Integer,
+        change_id -> Text,
+        device_id -> Text,
+        entity -> Text,
+        entity_id -> Text,
+        operation -> Text,
+        data -> Nullable<Text>,
+        changed_at -> Text,
+        pushed -> Bool,
+    }
+}
+
+diesel::table! {
+    sync_state (device_id) {
+        device_id -> Text,
+        applying_remote -> Bool,
+        backend -> Nullable<Text>,
+        last_synced_at -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Text,
+        webhook_id -> Text,
+        event_type -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Integer,
+        next_attempt_at -> TimestamptzSqlite,
+        last_error -> Nullable<Text>,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    webhooks (id) {
+        id -> Text,
+        url -> Text,
+        secret -> Text,
+        event_types -> Text,
+        is_enabled -> Bool,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
+    }
+}
+
 diesel::joinable!(habit_completions -> habits (habit_id));
+diesel::joinable!(habit_reminders -> habits (habit_id));
+diesel::joinable!(pomodoro_cycles -> pomodoro_presets (preset_id));
+diesel::joinable!(pomodoro_interruptions -> pomodoro_sessions (session_id));
 diesel::joinable!(pomodoro_sessions -> pomodoro_cycles (cycle_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    events,
     habit_completions,
+    habit_reminders,
     habits,
+    license,
     pomodoro_cycles,
+    pomodoro_interruptions,
+    pomodoro_presets,
     pomodoro_sessions,
+    settings,
+    streak_milestones,
+    sync_batches,
+    sync_changes,
+    sync_state,
+    webhook_deliveries,
+    webhooks,
 );
@@ -0,0 +1,334 @@
+use std::net::IpAddr;
+
+use chrono::{Local, NaiveDate, Utc};
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::AppError;
+use crate::models;
+use crate::schema::settings;
+use crate::shortcuts::{self, ShortcutAction};
+use crate::validation::{Validate, Validator};
+
+const MIN_IDLE_THRESHOLD_MS: i32 = 60 * 1000;
+const MAX_IDLE_THRESHOLD_MS: i32 = 60 * 60 * 1000;
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Theme {
+    Light,
+    Dark,
+    System,
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WeekStart {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub sound: bool,
+    pub session_end: bool,
+    pub habit_reminders: bool,
+    /// Shown when a focus session ends.
+    pub focus_end_message: String,
+    /// Shown when a short break ends.
+    pub break_end_message: String,
+    /// Shown when the long break ends a cycle.
+    pub cycle_end_message: String,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            enabled: true,
+            sound: true,
+            session_end: true,
+            habit_reminders: true,
+            focus_end_message: "Time for a break.".to_string(),
+            break_end_message: "Break is over, ready to focus?".to_string(),
+            cycle_end_message: "Pomodoro cycle completed, great work!".to_string(),
+        }
+    }
+}
+
+/// The local HTTP API for scripts and automation tools. Off by default.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutomationSettings {
+    pub enabled: bool,
+    /// Must be a loopback address, the API is never reachable from outside.
+    pub bind_address: String,
+    pub port: u16,
+    /// Expected as `Authorization: Bearer <token>`, generated by the app.
+    pub token: Option<String>,
+}
+
+impl Default for AutomationSettings {
+    fn default() -> Self {
+        AutomationSettings {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 7424,
+            token: None,
+        }
+    }
+}
+
+/// Pausing focus sessions while away from the computer.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IdleSettings {
+    pub enabled: bool,
+    /// Time without input after which a running focus session is paused.
+    pub threshold_ms: i32,
+}
+
+impl Default for IdleSettings {
+    fn default() -> Self {
+        IdleSettings {
+            enabled: true,
+            threshold_ms: 5 * 60 * 1000,
+        }
+    }
+}
+
+/// Global keyboard shortcuts, see `shortcuts::parse` for the format. `None`
+/// leaves an action without a shortcut.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShortcutSettings {
+    pub start_pause: Option<String>,
+    pub skip: Option<String>,
+    pub log_habit: Option<String>,
+}
+
+impl ShortcutSettings {
+    /// The actions that have a shortcut.
+    pub fn bindings(&self) -> Vec<(ShortcutAction, &str)> {
+        [
+            (ShortcutAction::StartPause, &self.start_pause),
+            (ShortcutAction::Skip, &self.skip),
+            (ShortcutAction::LogHabit, &self.log_habit),
+        ]
+        .into_iter()
+        .filter_map(|(action, binding)| Some((action, binding.as_deref()?)))
+        .collect()
+    }
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        ShortcutSettings {
+            start_pause: Some("CommandOrControl+Alt+P".to_string()),
+            skip: Some("CommandOrControl+Alt+S".to_string()),
+            log_habit: Some("CommandOrControl+Alt+L".to_string()),
+        }
+    }
+}
+
+/// User preferences. Durations are in milliseconds, like in `StartCycleArgs`.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    pub focus_duration: i32,
+    pub short_break_duration: i32,
+    pub long_break_duration: i32,
+    pub sessions_until_long_break: i32,
+    pub auto_start_breaks: bool,
+    pub auto_start_pomodoros: bool,
+    /// IANA name such as `Europe/Berlin`, `None` follows the system timezone.
+    pub timezone: Option<String>,
+    pub week_start: WeekStart,
+    pub theme: Theme,
+    pub notifications: NotificationSettings,
+    pub automation: AutomationSettings,
+    pub shortcuts: ShortcutSettings,
+    pub idle: IdleSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            focus_duration: 25 * 60 * 1000,
+            short_break_duration: 5 * 60 * 1000,
+            long_break_duration: 15 * 60 * 1000,
+            sessions_until_long_break: 4,
+            auto_start_breaks: true,
+            auto_start_pomodoros: true,
+            timezone: None,
+            week_start: WeekStart::Sunday,
+            theme: Theme::Light,
+            notifications: NotificationSettings::default(),
+            automation: AutomationSettings::default(),
+            shortcuts: ShortcutSettings::default(),
+            idle: IdleSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// The current calendar day in the configured timezone.
+    pub fn today(&self) -> NaiveDate {
+        match self.timezone.as_deref().map(str::parse::<Tz>) {
+            Some(Ok(timezone)) => Utc::now().with_timezone(&timezone).date_naive(),
+            _ => Local::now().date_naive(),
+        }
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator
+            .duration_ms("focusDuration", self.focus_duration)
+            .duration_ms("shortBreakDuration", self.short_break_duration)
+            .duration_ms("longBreakDuration", self.long_break_duration)
+            .sessions_until_long_break("sessionsUntilLongBreak", self.sessions_until_long_break)
+            .range(
+                "idle.thresholdMs",
+                self.idle.threshold_ms,
+                MIN_IDLE_THRESHOLD_MS,
+                MAX_IDLE_THRESHOLD_MS,
+            );
+
+        if let Some(timezone) = &self.timezone {
+            if timezone.parse::<Tz>().is_err() {
+                validator.error("timezone", format!("unknown timezone {}", timezone));
+            }
+        }
+
+        let notifications = &self.notifications;
+        validator
+            .not_empty(
+                "notifications.focusEndMessage",
+                &notifications.focus_end_message,
+            )
+            .not_empty(
+                "notifications.breakEndMessage",
+                &notifications.break_end_message,
+            )
+            .not_empty(
+                "notifications.cycleEndMessage",
+                &notifications.cycle_end_message,
+            );
+
+        let automation = &self.automation;
+        match automation.bind_address.parse::<IpAddr>() {
+            Ok(address) if address.is_loopback() => {}
+            _ => {
+                validator.error(
+                    "automation.bindAddress",
+                    "must be a loopback address such as 127.0.0.1",
+                );
+            }
+        }
+        if automation.port < 1024 {
+            validator.error("automation.port", "must be at least 1024");
+        }
+        if automation.enabled && automation.token.is_none() {
+            validator.error("automation.token", "must be generated before enabling");
+        }
+
+        for (field, message) in shortcuts::validate(&self.shortcuts) {
+            validator.error(field, message);
+        }
+
+        validator.finish()
+    }
+}
+
+/// Reads the stored settings on top of the defaults.
+///
+/// Rows whose key is no longer known, or whose value doesn't fit the current
+/// type, are dropped from the table so it converges on the current shape.
+pub fn load(connection: &mut SqliteConnection) -> Result<Settings, String> {
+    let rows = settings::table
+        .select((settings::key, settings::value))
+        .load::<(String, String)>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let defaults = match serde_json::to_value(Settings::default()) {
+        Ok(Value::Object(defaults)) => defaults,
+        _ => return Err("Failed to serialize default settings".to_string()),
+    };
+
+    let mut merged = defaults.clone();
+    let mut stale_keys = Vec::new();
+
+    for (key, value) in rows {
+        if !defaults.contains_key(&key) {
+            println!("Dropping unknown setting: {}", key);
+            stale_keys.push(key);
+            continue;
+        }
+
+        match serde_json::from_str::<Value>(&value) {
+            Ok(value) if fits(&defaults, &key, &value) => {
+                merged.insert(key, value);
+            }
+            _ => {
+                println!("Dropping invalid value for setting: {}", key);
+                stale_keys.push(key);
+            }
+        }
+    }
+
+    if !stale_keys.is_empty() {
+        diesel::delete(settings::table.filter(settings::key.eq_any(&stale_keys)))
+            .execute(connection)
+            .map_err(|e| e.to_string())?;
+    }
+
+    serde_json::from_value(Value::Object(merged)).map_err(|e| e.to_string())
+}
+
+/// Validates and stores `new_settings`, replacing all previous values.
+pub fn save(connection: &mut SqliteConnection, new_settings: &Settings) -> Result<(), AppError> {
+    new_settings.validate()?;
+
+    let values: Map<String, Value> = match serde_json::to_value(new_settings) {
+        Ok(Value::Object(values)) => values,
+        _ => return Err("Failed to serialize settings".into()),
+    };
+    let updated_at = models::now();
+
+    connection
+        .transaction::<_, diesel::result::Error, _>(|connection| {
+            for (key, value) in &values {
+                diesel::replace_into(settings::table)
+                    .values((
+                        settings::key.eq(key),
+                        settings::value.eq(value.to_string()),
+                        settings::updated_at.eq(updated_at),
+                    ))
+                    .execute(connection)?;
+            }
+            Ok(())
+        })
+        .map_err(AppError::from)
+}
+
+/// Whether a stored value can take the place of `key` without failing to
+/// deserialize or validate the whole struct.
+fn fits(defaults: &Map<String, Value>, key: &str, value: &Value) -> bool {
+    let mut candidate = defaults.clone();
+    candidate.insert(key.to_string(), value.clone());
+
+    serde_json::from_value::<Settings>(Value::Object(candidate))
+        .map(|settings| settings.validate().is_ok())
+        .unwrap_or(false)
+}
@@ -0,0 +1,214 @@
+//! Global keyboard shortcuts, which work while the window is in the
+//! background.
+//!
+//! Bindings are stored in `Settings::shortcuts` as text such as
+//! `CommandOrControl+Alt+P` and registered through a [`ShortcutRegistry`],
+//! which is the global shortcut plugin in the app and
+//! [`crate::testing::FakeShortcutRegistry`] in tests.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::ShortcutSettings;
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ShortcutAction {
+    /// Starts the pomodoro timer, or pauses or resumes it while it runs.
+    StartPause,
+    /// Ends the current pomodoro phase early.
+    Skip,
+    /// Opens the quick log window.
+    LogHabit,
+}
+
+impl ShortcutAction {
+    /// The settings field holding the binding, for validation errors.
+    pub fn field(&self) -> &'static str {
+        match self {
+            ShortcutAction::StartPause => "shortcuts.startPause",
+            ShortcutAction::Skip => "shortcuts.skip",
+            ShortcutAction::LogHabit => "shortcuts.logHabit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+    /// Command on macOS, the Windows key elsewhere.
+    Super,
+}
+
+/// A parsed binding. Equal shortcuts are the same keys, however they were
+/// written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcut {
+    /// Sorted, without duplicates.
+    pub modifiers: Vec<Modifier>,
+    pub key: String,
+}
+
+/// Keys other than letters, digits and function keys, as written in bindings.
+const NAMED_KEYS: [&str; 17] = [
+    "Space",
+    "Enter",
+    "Tab",
+    "Escape",
+    "Backspace",
+    "Delete",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+    "Comma",
+    "Period",
+];
+
+/// Parses a binding such as `CommandOrControl+Shift+L`: any modifiers, then
+/// a single key. `CommandOrControl` is Command on macOS and Ctrl elsewhere.
+pub fn parse(binding: &str) -> Result<Shortcut, String> {
+    let parts: Vec<&str> = binding.split('+').map(str::trim).collect();
+    let (key, modifier_names) = parts.split_last().ok_or("must not be empty")?;
+
+    let mut modifiers = Vec::new();
+    for name in modifier_names {
+        let modifier = match name.to_lowercase().as_str() {
+            "ctrl" | "control" => Modifier::Ctrl,
+            "alt" | "option" => Modifier::Alt,
+            "shift" => Modifier::Shift,
+            "super" | "cmd" | "command" | "meta" => Modifier::Super,
+            "commandorcontrol" | "cmdorctrl" if cfg!(target_os = "macos") => Modifier::Super,
+            "commandorcontrol" | "cmdorctrl" => Modifier::Ctrl,
+            _ => return Err(format!("unknown modifier {}", name)),
+        };
+        if !modifiers.contains(&modifier) {
+            modifiers.push(modifier);
+        }
+    }
+    modifiers.sort();
+
+    // Without one of these every press of the key would be taken from other
+    // apps
+    if !modifiers
+        .iter()
+        .any(|modifier| *modifier != Modifier::Shift)
+    {
+        return Err("must include Ctrl, Alt or Command".to_string());
+    }
+
+    Ok(Shortcut {
+        modifiers,
+        key: parse_key(key)?,
+    })
+}
+
+fn parse_key(key: &str) -> Result<String, String> {
+    let upper = key.to_uppercase();
+    let is_character = upper.len() == 1 && upper.chars().all(|c| c.is_ascii_alphanumeric());
+    let is_function_key = upper
+        .strip_prefix('F')
+        .and_then(|number| number.parse::<u8>().ok())
+        .is_some_and(|number| (1..=24).contains(&number));
+
+    if is_character || is_function_key {
+        return Ok(upper);
+    }
+
+    NAMED_KEYS
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(key))
+        .map(|name| name.to_string())
+        .ok_or_else(|| {
+            if key.is_empty() {
+                "must end with a key".to_string()
+            } else {
+                format!("unknown key {}", key)
+            }
+        })
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            let name = match modifier {
+                Modifier::Ctrl => "Ctrl",
+                Modifier::Alt => "Alt",
+                Modifier::Shift => "Shift",
+                Modifier::Super => "Super",
+            };
+            write!(f, "{}+", name)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Checks every binding, and that no two actions share a shortcut. Errors are
+/// `(settings field, message)`.
+pub fn validate(settings: &ShortcutSettings) -> Vec<(&'static str, String)> {
+    let mut errors = Vec::new();
+    let mut bound: Vec<(ShortcutAction, Shortcut)> = Vec::new();
+
+    for (action, binding) in settings.bindings() {
+        match parse(binding) {
+            Ok(shortcut) => match bound.iter().find(|(_, other)| *other == shortcut) {
+                Some((other_action, _)) => errors.push((
+                    action.field(),
+                    format!("conflicts with {}", other_action.field()),
+                )),
+                None => bound.push((action, shortcut)),
+            },
+            Err(e) => errors.push((action.field(), e)),
+        }
+    }
+
+    errors
+}
+
+pub trait ShortcutRegistry: Send + Sync {
+    fn unregister_all(&self) -> Result<(), String>;
+    fn register(&self, shortcut: &Shortcut, action: ShortcutAction) -> Result<(), String>;
+}
+
+/// A binding that couldn't be registered, usually because another app holds
+/// the same shortcut.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutError {
+    pub action: ShortcutAction,
+    pub binding: String,
+    pub message: String,
+}
+
+/// Replaces the registered shortcuts with the ones in `settings`. Bindings
+/// that fail don't keep the others from being registered.
+pub fn register(
+    registry: &dyn ShortcutRegistry,
+    settings: &ShortcutSettings,
+) -> Vec<ShortcutError> {
+    if let Err(e) = registry.unregister_all() {
+        println!("Failed to unregister shortcuts: {}", e);
+    }
+
+    settings
+        .bindings()
+        .into_iter()
+        .filter_map(|(action, binding)| {
+            let result = parse(binding).and_then(|shortcut| registry.register(&shortcut, action));
+
+            result.err().map(|message| ShortcutError {
+                action,
+                binding: binding.to_string(),
+                message,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::SyncBackend;
+
+/// Stores batches in a local directory that another tool, such as Syncthing
+/// or Dropbox, keeps in sync between machines.
+pub struct FolderBackend {
+    root: PathBuf,
+}
+
+impl FolderBackend {
+    pub fn new(root: &str) -> Self {
+        FolderBackend {
+            root: PathBuf::from(root),
+        }
+    }
+}
+
+impl SyncBackend for FolderBackend {
+    fn list_devices(&self) -> Result<Vec<String>, String> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut devices: Vec<String> = fs::read_dir(&self.root)
+            .map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .collect();
+
+        devices.sort();
+        Ok(devices)
+    }
+
+    fn list_batches(&self, device_id: &str) -> Result<Vec<String>, String> {
+        let directory = self.root.join(device_id);
+        if !directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut batches: Vec<String> = fs::read_dir(&directory)
+            .map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|name| name.ends_with(".json") && !name.starts_with('.'))
+            .collect();
+
+        batches.sort();
+        Ok(batches)
+    }
+
+    fn read_batch(&self, device_id: &str, name: &str) -> Result<Vec<u8>, String> {
+        fs::read(self.root.join(device_id).join(name)).map_err(|e| e.to_string())
+    }
+
+    fn write_batch(&self, device_id: &str, name: &str, contents: &[u8]) -> Result<(), String> {
+        let directory = self.root.join(device_id);
+        fs::create_dir_all(&directory).map_err(|e| e.to_string())?;
+
+        // Written under a hidden name first, so other devices and sync tools
+        // never see a half-written batch.
+        let temporary = directory.join(format!(".{}.tmp", name));
+        fs::write(&temporary, contents).map_err(|e| e.to_string())?;
+        fs::rename(&temporary, directory.join(name)).map_err(|e| e.to_string())
+    }
+}
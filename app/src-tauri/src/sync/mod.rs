@@ -0,0 +1,597 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SubsecRound, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::sqlite::SqliteConnection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models;
+use crate::schema::{habit_completions, sync_batches, sync_changes, sync_state};
+
+mod folder;
+mod webdav;
+
+pub use folder::FolderBackend;
+pub use webdav::WebDavBackend;
+
+/// Remote storage shared by all devices. Every device only ever writes into
+/// its own directory, so backends never have to handle concurrent writes.
+pub trait SyncBackend {
+    /// Ids of all devices that have pushed at least one batch.
+    fn list_devices(&self) -> Result<Vec<String>, String>;
+    fn list_batches(&self, device_id: &str) -> Result<Vec<String>, String>;
+    fn read_batch(&self, device_id: &str, name: &str) -> Result<Vec<u8>, String>;
+    fn write_batch(&self, device_id: &str, name: &str, contents: &[u8]) -> Result<(), String>;
+}
+
+/// Keeps credentials of the backends out of the database, e.g. in the
+/// keychain of the operating system.
+pub trait SecretStore: Send + Sync {
+    fn get(&self, name: &str) -> Result<Option<String>, String>;
+    fn set(&self, name: &str, secret: &str) -> Result<(), String>;
+    /// Succeeds if there is no secret named `name`.
+    fn delete(&self, name: &str) -> Result<(), String>;
+}
+
+const WEBDAV_PASSWORD: &str = "sync.webdav.password";
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SyncBackendConfig {
+    /// A directory kept in sync by another tool, e.g. Syncthing or Dropbox.
+    Folder { path: String },
+    #[serde(rename = "WEBDAV")]
+    WebDav {
+        url: String,
+        username: String,
+        /// Only set when saving, the password is kept in the [`SecretStore`]
+        /// and never loaded. Saving without one keeps the stored password.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+}
+
+pub fn backend_for(
+    config: &SyncBackendConfig,
+    secrets: &dyn SecretStore,
+) -> Result<Box<dyn SyncBackend>, String> {
+    match config {
+        SyncBackendConfig::Folder { path } => Ok(Box::new(FolderBackend::new(path))),
+        SyncBackendConfig::WebDav { url, username, .. } => {
+            let password = secrets
+                .get(WEBDAV_PASSWORD)?
+                .ok_or("The WebDAV password is missing, please enter it again")?;
+            Ok(Box::new(WebDavBackend::new(url, username, &password)?))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub pushed: i32,
+    pub pulled: i32,
+    pub applied: i32,
+    pub skipped: i32,
+}
+
+/// A single row mutation, as stored in `sync_changes` and in pushed batches.
+#[derive(Serialize, Deserialize, Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = sync_changes)]
+pub struct Change {
+    pub change_id: String,
+    pub device_id: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub operation: String,
+    pub data: Option<String>,
+    pub changed_at: String,
+}
+
+impl Change {
+    /// Conflicts are resolved by last writer wins. Ties on the timestamp are
+    /// broken by device and change id so every device picks the same winner.
+    fn cmp_version(&self, other: &Change) -> Ordering {
+        (&self.changed_at, &self.device_id, &self.change_id).cmp(&(
+            &other.changed_at,
+            &other.device_id,
+            &other.change_id,
+        ))
+    }
+}
+
+/// Columns of every synced table, in the order they are written to JSON by
+/// the `sync_*` triggers.
+fn synced_columns(entity: &str) -> Option<&'static [&'static str]> {
+    match entity {
+        "habits" => Some(&[
+            "id",
+            "title",
+            "description",
+            "icon",
+            "is_archived",
+            "created_at",
+            "updated_at",
+        ]),
+        "habit_completions" => Some(&["id", "habit_id", "created_at"]),
+        "pomodoro_cycles" => Some(&[
+            "id",
+            "status",
+            "focus_duration",
+            "short_break_duration",
+            "long_break_duration",
+            "sessions_until_long_break",
+            "auto_start_breaks",
+            "auto_start_pomodoros",
+            "started_at",
+            "completed_at",
+            "updated_at",
+            "preset_id",
+        ]),
+        "pomodoro_presets" => Some(&[
+            "id",
+            "name",
+            "focus_duration",
+            "short_break_duration",
+            "long_break_duration",
+            "sessions_until_long_break",
+            "auto_start_breaks",
+            "auto_start_pomodoros",
+            "is_default",
+            "created_at",
+            "updated_at",
+        ]),
+        "pomodoro_interruptions" => Some(&[
+            "id",
+            "session_id",
+            "started_at",
+            "ended_at",
+            "counted_ms",
+            "resolution",
+            "created_at",
+        ]),
+        "pomodoro_sessions" => Some(&[
+            "id",
+            "cycle_id",
+            "session_type",
+            "started_at",
+            "completed_at",
+            "duration_ms",
+            "was_completed",
+        ]),
+        _ => None,
+    }
+}
+
+/// The configured backend, without its password.
+pub fn load_config(
+    connection: &mut SqliteConnection,
+    secrets: &dyn SecretStore,
+) -> Result<Option<SyncBackendConfig>, String> {
+    let backend = sync_state::table
+        .select(sync_state::backend)
+        .first::<Option<String>>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let config = backend
+        .map(|backend| serde_json::from_str::<SyncBackendConfig>(&backend))
+        .transpose()
+        .map_err(|e| e.to_string())?;
+
+    // Older versions stored the password along with the config
+    if let Some(SyncBackendConfig::WebDav {
+        password: Some(_), ..
+    }) = &config
+    {
+        save_config(connection, secrets, config.as_ref())?;
+        return load_config(connection, secrets);
+    }
+
+    Ok(config)
+}
+
+/// Stores `config`, with its password in `secrets`. Turning sync off or
+/// switching backends forgets the password.
+pub fn save_config(
+    connection: &mut SqliteConnection,
+    secrets: &dyn SecretStore,
+    config: Option<&SyncBackendConfig>,
+) -> Result<(), String> {
+    let config = match config {
+        Some(SyncBackendConfig::WebDav {
+            url,
+            username,
+            password,
+        }) => {
+            if let Some(password) = password {
+                secrets.set(WEBDAV_PASSWORD, password)?;
+            }
+            Some(SyncBackendConfig::WebDav {
+                url: url.clone(),
+                username: username.clone(),
+                password: None,
+            })
+        }
+        config => {
+            secrets.delete(WEBDAV_PASSWORD)?;
+            config.cloned()
+        }
+    };
+
+    let backend = config
+        .map(|config| serde_json::to_string(&config).map_err(|e| e.to_string()))
+        .transpose()?;
+
+    diesel::update(sync_state::table)
+        .set(sync_state::backend.eq(backend))
+        .execute(connection)
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Pushes local changes to `backend`, then pulls and applies the changes of
+/// every other device.
+pub fn sync(
+    connection: &mut SqliteConnection,
+    backend: &dyn SyncBackend,
+) -> Result<SyncReport, String> {
+    let device_id = sync_state::table
+        .select(sync_state::device_id)
+        .first::<String>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let pushed = push(connection, backend, &device_id)?;
+    let mut report = pull(connection, backend, &device_id)?;
+    report.pushed = pushed;
+
+    diesel::update(sync_state::table)
+        .set(sync_state::last_synced_at.eq(Some(Utc::now().to_rfc3339())))
+        .execute(connection)
+        .map_err(|e| e.to_string())?;
+
+    Ok(report)
+}
+
+fn push(
+    connection: &mut SqliteConnection,
+    backend: &dyn SyncBackend,
+    device_id: &str,
+) -> Result<i32, String> {
+    let pending = sync_changes::table
+        .filter(sync_changes::device_id.eq(device_id))
+        .filter(sync_changes::pushed.eq(false))
+        .order(sync_changes::seq.asc())
+        .select((sync_changes::seq, Change::as_select()))
+        .load::<(i32, Change)>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let last_seq = match pending.last() {
+        Some((seq, _)) => *seq,
+        None => return Ok(0),
+    };
+    let changes: Vec<Change> = pending.into_iter().map(|(_, change)| change).collect();
+
+    // Names only have to be unique per device, the timestamp keeps them
+    // readable when browsing the sync folder.
+    let name = format!(
+        "{}-{}.json",
+        Utc::now().format("%Y%m%dT%H%M%S%3f"),
+        Uuid::new_v4()
+    );
+    let contents = serde_json::to_vec(&changes).map_err(|e| e.to_string())?;
+    backend.write_batch(device_id, &name, &contents)?;
+
+    diesel::update(sync_changes::table)
+        .filter(sync_changes::device_id.eq(device_id))
+        .filter(sync_changes::seq.le(last_seq))
+        .set(sync_changes::pushed.eq(true))
+        .execute(connection)
+        .map_err(|e| e.to_string())?;
+
+    Ok(changes.len() as i32)
+}
+
+fn pull(
+    connection: &mut SqliteConnection,
+    backend: &dyn SyncBackend,
+    device_id: &str,
+) -> Result<SyncReport, String> {
+    let mut report = SyncReport {
+        pushed: 0,
+        pulled: 0,
+        applied: 0,
+        skipped: 0,
+    };
+    let mut batches = Vec::new();
+    let mut changes = Vec::new();
+
+    for remote_device in backend.list_devices()? {
+        if remote_device == device_id {
+            continue;
+        }
+
+        let pulled_batches = sync_batches::table
+            .filter(sync_batches::device_id.eq(&remote_device))
+            .select(sync_batches::name)
+            .load::<String>(connection)
+            .map_err(|e| e.to_string())?;
+
+        for name in backend.list_batches(&remote_device)? {
+            if pulled_batches.contains(&name) {
+                continue;
+            }
+
+            let contents = backend.read_batch(&remote_device, &name)?;
+            let mut batch: Vec<Change> =
+                serde_json::from_slice(&contents).map_err(|e| e.to_string())?;
+            for change in &mut batch {
+                upgrade_legacy_change(change);
+                key_completion(connection, change)?;
+            }
+
+            changes.extend(batch);
+            batches.push((remote_device.clone(), name));
+        }
+    }
+
+    report.pulled = changes.len() as i32;
+
+    // Applying in version order makes the outcome independent of the order
+    // devices and batches were listed in.
+    changes.sort_by(|a, b| a.cmp_version(b));
+
+    connection
+        .transaction::<_, Box<dyn std::error::Error + Send + Sync>, _>(|connection| {
+            set_applying_remote(connection, true)?;
+
+            for change in &changes {
+                if apply_change(connection, change)? {
+                    report.applied += 1;
+                } else {
+                    report.skipped += 1;
+                }
+            }
+
+            set_applying_remote(connection, false)?;
+
+            let pulled_at = Utc::now().to_rfc3339();
+            for (remote_device, name) in &batches {
+                diesel::insert_into(sync_batches::table)
+                    .values((
+                        sync_batches::device_id.eq(remote_device),
+                        sync_batches::name.eq(name),
+                        sync_batches::pulled_at.eq(&pulled_at),
+                    ))
+                    .execute(connection)
+                    .map_err(|e| e.to_string())?;
+            }
+
+            Ok(())
+        })
+        .map_err(|e| e.to_string())?;
+
+    Ok(report)
+}
+
+/// Devices that haven't been updated yet still push changes in older shapes:
+/// session durations in minutes under the column name used before durations
+/// were normalized, and timestamps in whatever format they were written in.
+fn upgrade_legacy_change(change: &mut Change) {
+    let Some(Ok(serde_json::Value::Object(mut row))) = change
+        .data
+        .as_deref()
+        .map(serde_json::from_str::<serde_json::Value>)
+    else {
+        return;
+    };
+    let mut upgraded = false;
+
+    if change.entity == "pomodoro_sessions" {
+        if let Some(minutes) = row.remove("duration_minutes") {
+            let duration_ms = minutes.as_i64().map(|minutes| minutes * 60_000);
+            row.insert("duration_ms".to_string(), duration_ms.into());
+            upgraded = true;
+        }
+    }
+
+    for (column, value) in row.iter_mut() {
+        let Some(text) = value.as_str() else {
+            continue;
+        };
+        let normalized = if change.entity == "habit_completions" && column == "created_at" {
+            normalize_date(text)
+        } else if column.ends_with("_at") {
+            normalize_timestamp(text)
+        } else {
+            continue;
+        };
+
+        if let Some(normalized) = normalized.filter(|normalized| normalized != text) {
+            *value = normalized.into();
+            upgraded = true;
+        }
+    }
+
+    if upgraded {
+        change.data = Some(serde_json::Value::Object(row).to_string());
+    }
+}
+
+/// Completions are synced under `<habit_id>/<day>` rather than their row id,
+/// so two devices ticking the same day converge on one completion. Devices
+/// that haven't been updated yet still log them by row id, deletes without
+/// a row take the day of the local completion.
+fn key_completion(connection: &mut SqliteConnection, change: &mut Change) -> Result<(), String> {
+    if change.entity != "habit_completions" || change.entity_id.contains('/') {
+        return Ok(());
+    }
+
+    let key = match change
+        .data
+        .as_deref()
+        .map(serde_json::from_str::<serde_json::Value>)
+    {
+        Some(Ok(row)) => row["habit_id"]
+            .as_str()
+            .zip(row["created_at"].as_str())
+            .map(|(habit_id, day)| completion_key(habit_id, day)),
+        _ => habit_completions::table
+            .find(&change.entity_id)
+            .select((habit_completions::habit_id, habit_completions::created_at))
+            .first::<(String, NaiveDate)>(connection)
+            .optional()
+            .map_err(|e| e.to_string())?
+            .map(|(habit_id, day)| completion_key(&habit_id, &day.format("%Y-%m-%d").to_string())),
+    };
+
+    if let Some(key) = key {
+        change.entity_id = key;
+    }
+
+    Ok(())
+}
+
+fn completion_key(habit_id: &str, day: &str) -> String {
+    format!("{}/{}", habit_id, day)
+}
+
+/// Rewrites RFC 3339 and SQLite `CURRENT_TIMESTAMP` values the way Diesel
+/// writes a `DateTime<Utc>`.
+fn normalize_timestamp(value: &str) -> Option<String> {
+    let timestamp = DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%F %T%.f%:z"))
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%F %T%.f").map(|t| t.and_utc()))
+        .ok()?;
+
+    Some(
+        timestamp
+            .trunc_subsecs(3)
+            .format(models::TIMESTAMP_FORMAT)
+            .to_string(),
+    )
+}
+
+/// Completions used to be stored with a time of day by SQLite defaults.
+fn normalize_date(value: &str) -> Option<String> {
+    value
+        .get(..10)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// While set, the `sync_*` triggers don't log changes, so remote changes are
+/// not echoed back as local ones.
+fn set_applying_remote(connection: &mut SqliteConnection, applying: bool) -> Result<(), String> {
+    diesel::update(sync_state::table)
+        .set(sync_state::applying_remote.eq(applying))
+        .execute(connection)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Applies `change` unless a newer change to the same row is already known.
+/// Returns whether the row was written.
+fn apply_change(connection: &mut SqliteConnection, change: &Change) -> Result<bool, String> {
+    let already_known = sync_changes::table
+        .filter(sync_changes::change_id.eq(&change.change_id))
+        .count()
+        .get_result::<i64>(connection)
+        .map_err(|e| e.to_string())?
+        > 0;
+    if already_known {
+        return Ok(false);
+    }
+
+    let latest = sync_changes::table
+        .filter(sync_changes::entity.eq(&change.entity))
+        .filter(sync_changes::entity_id.eq(&change.entity_id))
+        .order((
+            sync_changes::changed_at.desc(),
+            sync_changes::device_id.desc(),
+            sync_changes::change_id.desc(),
+        ))
+        .select(Change::as_select())
+        .first::<Change>(connection)
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let is_newer = latest
+        .map(|latest| change.cmp_version(&latest) == Ordering::Greater)
+        .unwrap_or(true);
+
+    if is_newer {
+        write_row(connection, change)?;
+    }
+
+    // Older changes are still recorded, so they are not pulled again.
+    diesel::insert_into(sync_changes::table)
+        .values((change, sync_changes::pushed.eq(true)))
+        .execute(connection)
+        .map_err(|e| e.to_string())?;
+
+    Ok(is_newer)
+}
+
+fn write_row(connection: &mut SqliteConnection, change: &Change) -> Result<(), String> {
+    // The entity name ends up in SQL, so only known tables are accepted.
+    let columns = synced_columns(&change.entity)
+        .ok_or_else(|| format!("Unknown entity: {}", change.entity))?;
+
+    // Whichever row holds the day on this device gives way to the change,
+    // the row id may differ from the one on the device that made it.
+    if change.entity == "habit_completions" {
+        if let Some((habit_id, day)) = change.entity_id.rsplit_once('/') {
+            diesel::sql_query(
+                "DELETE FROM habit_completions WHERE habit_id = ? AND created_at = ?",
+            )
+            .bind::<Text, _>(habit_id)
+            .bind::<Text, _>(day)
+            .execute(connection)
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    match (change.operation.as_str(), &change.data) {
+        ("INSERT" | "UPDATE", Some(data)) => {
+            let values = columns
+                .iter()
+                .map(|column| format!("json_extract(row, '$.{}')", column))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let updates = columns
+                .iter()
+                .skip(1)
+                .map(|column| format!("{0} = excluded.{0}", column))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            // `WHERE true` keeps SQLite from parsing `ON CONFLICT` as a join
+            // constraint of the SELECT.
+            diesel::sql_query(format!(
+                "INSERT INTO {} ({}) SELECT {} FROM (SELECT ? AS row) WHERE true \
+                 ON CONFLICT(id) DO UPDATE SET {}",
+                change.entity,
+                columns.join(", "),
+                values,
+                updates
+            ))
+            .bind::<Text, _>(data)
+            .execute(connection)
+            .map_err(|e| e.to_string())?;
+        }
+        ("DELETE", _) => {
+            diesel::sql_query(format!("DELETE FROM {} WHERE id = ?", change.entity))
+                .bind::<Text, _>(&change.entity_id)
+                .execute(connection)
+                .map_err(|e| e.to_string())?;
+        }
+        (operation, _) => return Err(format!("Invalid change operation: {}", operation)),
+    }
+
+    Ok(())
+}
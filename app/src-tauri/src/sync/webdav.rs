@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::{Method, StatusCode, Url};
+
+use super::SyncBackend;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#;
+
+/// Stores batches on a WebDAV server, e.g. Nextcloud, under `url`.
+pub struct WebDavBackend {
+    client: Client,
+    base_url: Url,
+    username: String,
+    password: String,
+}
+
+impl WebDavBackend {
+    pub fn new(url: &str, username: &str, password: &str) -> Result<Self, String> {
+        // Relative paths are resolved against the base, which only works for
+        // collection URLs ending in a slash.
+        let url = if url.ends_with('/') {
+            url.to_string()
+        } else {
+            format!("{}/", url)
+        };
+
+        Ok(WebDavBackend {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .map_err(|e| e.to_string())?,
+            base_url: Url::parse(&url).map_err(|e| e.to_string())?,
+            username: username.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder, String> {
+        let url = self.base_url.join(path).map_err(|e| e.to_string())?;
+        Ok(self
+            .client
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password)))
+    }
+
+    /// Lists the names of the direct children of the collection at `path`.
+    fn list(&self, path: &str) -> Result<Vec<String>, String> {
+        let collection = self.base_url.join(path).map_err(|e| e.to_string())?;
+        let response = self
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), path)?
+            .header("Depth", "1")
+            .header("Content-Type", "application/xml")
+            .body(PROPFIND_BODY)
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if response.status() != StatusCode::MULTI_STATUS {
+            return Err(format!("WebDAV listing failed: {}", response.status()));
+        }
+
+        let body = response.text().map_err(|e| e.to_string())?;
+        let collection_path = collection.path().trim_end_matches('/').to_string();
+
+        let mut names: Vec<String> = extract_hrefs(&body)
+            .into_iter()
+            .filter_map(|href| {
+                // Servers return either absolute URLs or absolute paths
+                let href_path = match Url::parse(&href) {
+                    Ok(url) => url.path().to_string(),
+                    Err(_) => href,
+                };
+                let href_path = href_path.trim_end_matches('/');
+
+                // The collection itself is part of its own listing
+                if href_path == collection_path {
+                    return None;
+                }
+                href_path.rsplit('/').next().map(str::to_string)
+            })
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        names.sort();
+        Ok(names)
+    }
+
+    /// Creates the collection at `path`, succeeding if it already exists.
+    fn create_collection(&self, path: &str) -> Result<(), String> {
+        let response = self
+            .request(Method::from_bytes(b"MKCOL").unwrap(), path)?
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::METHOD_NOT_ALLOWED => Ok(()),
+            status => Err(format!("WebDAV MKCOL failed: {}", status)),
+        }
+    }
+}
+
+impl SyncBackend for WebDavBackend {
+    fn list_devices(&self) -> Result<Vec<String>, String> {
+        self.list("")
+    }
+
+    fn list_batches(&self, device_id: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .list(&format!("{}/", device_id))?
+            .into_iter()
+            .filter(|name| name.ends_with(".json"))
+            .collect())
+    }
+
+    fn read_batch(&self, device_id: &str, name: &str) -> Result<Vec<u8>, String> {
+        let response = self
+            .request(Method::GET, &format!("{}/{}", device_id, name))?
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("WebDAV download failed: {}", response.status()));
+        }
+
+        response
+            .bytes()
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| e.to_string())
+    }
+
+    fn write_batch(&self, device_id: &str, name: &str, contents: &[u8]) -> Result<(), String> {
+        self.create_collection("")?;
+        self.create_collection(&format!("{}/", device_id))?;
+
+        let response = self
+            .request(Method::PUT, &format!("{}/{}", device_id, name))?
+            .body(contents.to_vec())
+            .send()
+            .map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!("WebDAV upload failed: {}", response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Pulls the contents of all `<href>` elements out of a PROPFIND response,
+/// whatever namespace prefix the server uses.
+fn extract_hrefs(body: &str) -> Vec<String> {
+    let mut hrefs = Vec::new();
+    let mut rest = body;
+
+    while let Some(start) = rest.find("href>") {
+        let is_opening_tag = rest[..start]
+            .rfind('<')
+            .map(|tag_start| !rest[tag_start + 1..].starts_with('/'))
+            .unwrap_or(false);
+        rest = &rest[start + "href>".len()..];
+
+        if !is_opening_tag {
+            continue;
+        }
+        if let Some(end) = rest.find('<') {
+            hrefs.push(rest[..end].trim().to_string());
+            rest = &rest[end..];
+        }
+    }
+
+    hrefs
+}
@@ -0,0 +1,222 @@
+//! Helpers for tests that need a database or files, without a Tauri app.
+//! Only built with the `test-util` feature.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use diesel_migrations::MigrationHarness;
+use uuid::Uuid;
+
+use crate::db::{self, MIGRATIONS};
+use crate::idle::IdleSource;
+use crate::license::LicenseClient;
+use crate::notifications::{Notification, Notifier};
+use crate::shortcuts::{Shortcut, ShortcutAction, ShortcutRegistry};
+use crate::sync::SecretStore;
+use crate::ApiImpl;
+
+/// Opens a private in-memory database with all migrations applied.
+pub fn connection() -> SqliteConnection {
+    let mut connection =
+        SqliteConnection::establish(":memory:").expect("Failed to open in-memory database");
+    connection
+        .run_pending_migrations(MIGRATIONS)
+        .expect("Failed to run database migrations");
+    connection
+}
+
+/// Creates an empty directory that no other test uses.
+pub fn temp_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("achievealchemy-test-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).expect("Failed to create temporary directory");
+    dir
+}
+
+/// The resolvers of the app on the database in `data_dir`, opened like at
+/// startup. There is no window, notifications go to a [`FakeNotifier`],
+/// secrets to a [`FakeSecretStore`] and the license server can't be reached.
+pub fn api(data_dir: &Path) -> ApiImpl {
+    api_with_license_client(data_dir, LicenseClient::new("http://127.0.0.1:0", None))
+}
+
+/// Like [`api`], with the license server at `license_client`.
+pub fn api_with_license_client(data_dir: &Path, license_client: LicenseClient) -> ApiImpl {
+    let api = ApiImpl::new(license_client);
+    *api.pool.lock().unwrap() = db::setup_db(data_dir);
+    *api.data_dir.lock().unwrap() = Some(data_dir.to_path_buf());
+    *api.notifier.lock().unwrap() = Some(Arc::new(FakeNotifier::default()));
+    *api.secrets.lock().unwrap() = Some(Arc::new(FakeSecretStore::default()));
+    api
+}
+
+/// A [`Notifier`] that records notifications instead of showing them.
+#[derive(Clone, Default)]
+pub struct FakeNotifier {
+    sent: Arc<Mutex<Vec<Notification>>>,
+}
+
+impl FakeNotifier {
+    pub fn sent(&self) -> Vec<Notification> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl Notifier for FakeNotifier {
+    fn notify(&self, notification: Notification) -> Result<(), String> {
+        self.sent.lock().unwrap().push(notification);
+        Ok(())
+    }
+}
+
+/// A [`ShortcutRegistry`] that records registered shortcuts.
+#[derive(Clone, Default)]
+pub struct FakeShortcutRegistry {
+    taken: Vec<String>,
+    registered: Arc<Mutex<Vec<(String, ShortcutAction)>>>,
+}
+
+impl FakeShortcutRegistry {
+    /// A registry where `taken`, written like `Ctrl+Alt+P`, fail as if
+    /// another app held them.
+    pub fn with_taken(taken: &[&str]) -> Self {
+        FakeShortcutRegistry {
+            taken: taken.iter().map(|shortcut| shortcut.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn registered(&self) -> Vec<(String, ShortcutAction)> {
+        self.registered.lock().unwrap().clone()
+    }
+}
+
+impl ShortcutRegistry for FakeShortcutRegistry {
+    fn unregister_all(&self) -> Result<(), String> {
+        self.registered.lock().unwrap().clear();
+        Ok(())
+    }
+
+    fn register(&self, shortcut: &Shortcut, action: ShortcutAction) -> Result<(), String> {
+        let shortcut = shortcut.to_string();
+        if self.taken.contains(&shortcut) {
+            return Err(format!("{} is already registered", shortcut));
+        }
+
+        self.registered.lock().unwrap().push((shortcut, action));
+        Ok(())
+    }
+}
+
+/// An [`IdleSource`] whose idle time is set by the test.
+#[derive(Clone, Default)]
+pub struct FakeIdleSource {
+    idle_time: Arc<Mutex<Duration>>,
+}
+
+impl FakeIdleSource {
+    pub fn set(&self, idle_time: Duration) {
+        *self.idle_time.lock().unwrap() = idle_time;
+    }
+}
+
+impl IdleSource for FakeIdleSource {
+    fn idle_time(&self) -> Result<Duration, String> {
+        Ok(*self.idle_time.lock().unwrap())
+    }
+}
+
+/// A [`SecretStore`] that keeps secrets in memory.
+#[derive(Clone, Default)]
+pub struct FakeSecretStore {
+    secrets: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl SecretStore for FakeSecretStore {
+    fn get(&self, name: &str) -> Result<Option<String>, String> {
+        Ok(self.secrets.lock().unwrap().get(name).cloned())
+    }
+
+    fn set(&self, name: &str, secret: &str) -> Result<(), String> {
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<(), String> {
+        self.secrets.lock().unwrap().remove(name);
+        Ok(())
+    }
+}
+
+/// A request received by [`http_stub`]. Header names are lowercase.
+pub struct StubRequest {
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// Starts an HTTP server on a free local port that answers each request with
+/// the next of `responses`, as status and JSON body, then stops. Returns its
+/// base URL along with the requests it got.
+pub fn http_stub(responses: Vec<(u16, String)>) -> (String, Receiver<StubRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind the stub server");
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for (status, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap_or_default().to_string();
+
+            let mut headers = HashMap::new();
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(": ") {
+                    Some((name, value)) => {
+                        headers.insert(name.to_lowercase(), value.to_string());
+                    }
+                    None => break,
+                }
+            }
+
+            let length = headers
+                .get("content-length")
+                .map_or(0, |length| length.parse().unwrap());
+            let mut request_body = vec![0; length];
+            reader.read_exact(&mut request_body).unwrap();
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+
+            let _ = sender.send(StubRequest {
+                path,
+                headers,
+                body: String::from_utf8(request_body).unwrap(),
+            });
+        }
+    });
+
+    (url, receiver)
+}
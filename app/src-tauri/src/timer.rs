@@ -0,0 +1,133 @@
+//! The pomodoro timer as seen from outside the window.
+//!
+//! The timer runs in the window, which emits its state as
+//! `TIMER_STATE_EVENT` whenever it changes. Controls outside the window, like
+//! the tray, drive it by emitting a [`TimerCommand`] as `TIMER_COMMAND_EVENT`.
+//! The backend keeps its own [`PhaseClock`], as the timer of a hidden window
+//! may be throttled.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::SessionType;
+
+pub const TIMER_STATE_EVENT: &str = "pomodoro-timer-state";
+pub const TIMER_COMMAND_EVENT: &str = "pomodoro-timer-command";
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerState {
+    pub mode: SessionType,
+    /// Milliseconds left in the current phase.
+    pub time_left: i32,
+    pub is_running: bool,
+    pub is_paused: bool,
+}
+
+impl TimerState {
+    pub fn is_ticking(&self) -> bool {
+        self.is_running && !self.is_paused
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_running && self.is_paused
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TimerCommand {
+    Start,
+    Pause,
+    Resume,
+    /// Ends the current phase early and moves on to the next one.
+    Skip,
+}
+
+impl TimerCommand {
+    /// What a single start/pause control does, given the last reported
+    /// state of the timer.
+    pub fn start_or_pause(state: Option<&TimerState>) -> TimerCommand {
+        match state {
+            Some(state) if state.is_ticking() => TimerCommand::Pause,
+            Some(state) if state.is_paused() => TimerCommand::Resume,
+            _ => TimerCommand::Start,
+        }
+    }
+}
+
+/// When the current phase of the timer ends, following the states the
+/// window reports.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhaseClock {
+    state: Option<TimerState>,
+    ends_at: Option<DateTime<Utc>>,
+    ended: bool,
+}
+
+impl PhaseClock {
+    /// Follows `state`, reported at `now`. The end is only set when a phase
+    /// starts or resumes, so a window that falls behind doesn't delay it.
+    pub fn update(&mut self, state: Option<TimerState>, now: DateTime<Utc>) {
+        let same_phase = match (&self.state, &state) {
+            (Some(previous), Some(next)) => {
+                previous.is_ticking()
+                    && next.is_ticking()
+                    && previous.mode == next.mode
+                    && next.time_left <= previous.time_left
+            }
+            _ => false,
+        };
+
+        if !same_phase {
+            self.ends_at = state
+                .as_ref()
+                .filter(|state| state.is_ticking())
+                .map(|state| now + Duration::milliseconds(state.time_left.into()));
+            self.ended = false;
+        }
+        self.state = state;
+    }
+
+    pub fn ends_at(&self) -> Option<DateTime<Utc>> {
+        self.ends_at
+    }
+
+    /// The mode of the phase if it ended by `now`, only once per phase.
+    pub fn take_ended(&mut self, now: DateTime<Utc>) -> Option<SessionType> {
+        match (self.ends_at, &self.state) {
+            (Some(ends_at), Some(state)) if ends_at <= now && !self.ended => {
+                self.ended = true;
+                Some(state.mode)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A one-line summary of the timer such as `Focus, 12:34 left`.
+pub fn label(state: Option<&TimerState>) -> String {
+    let state = match state {
+        Some(state) if state.is_running => state,
+        _ => return "Timer stopped".to_string(),
+    };
+
+    let mode = match state.mode {
+        SessionType::Focus => "Focus",
+        SessionType::ShortBreak => "Short break",
+        SessionType::LongBreak => "Long break",
+    };
+    let time_left = format_time(state.time_left);
+
+    if state.is_paused {
+        format!("{} paused, {} left", mode, time_left)
+    } else {
+        format!("{}, {} left", mode, time_left)
+    }
+}
+
+/// `ms` as `MM:SS`, like the timer in the window.
+pub fn format_time(ms: i32) -> String {
+    let seconds = ms.max(0) / 1000;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
@@ -0,0 +1,237 @@
+//! The tray icon, for working with the window hidden.
+//!
+//! Its menu shows the pomodoro timer with start, pause and skip controls, and
+//! today's active habits with a checkmark once completed. The timer items
+//! follow `timer::TIMER_STATE_EVENT`; the menu is rebuilt on
+//! `HABITS_CHANGED_EVENT`.
+
+use std::sync::{Arc, Mutex};
+
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+
+use crate::error::AppError;
+use crate::models::Habit;
+use crate::repository::{CompletionRepository, CreateHabitCompletionArgs, HabitRepository};
+use crate::settings;
+use crate::timer::{self, TimerCommand, TimerState};
+use crate::{Api, ApiImpl, HABITS_CHANGED_EVENT};
+
+const TRAY_ID: &str = "main";
+const START_ID: &str = "start";
+const PAUSE_ID: &str = "pause";
+const SKIP_ID: &str = "skip";
+const SHOW_ID: &str = "show";
+const QUIT_ID: &str = "quit";
+/// Followed by the habit id.
+const HABIT_ID_PREFIX: &str = "habit:";
+
+/// The items that change with the timer, replaced when the menu is rebuilt.
+struct TimerItems {
+    label: MenuItem<Wry>,
+    start: MenuItem<Wry>,
+    pause: MenuItem<Wry>,
+    skip: MenuItem<Wry>,
+}
+
+impl TimerItems {
+    fn new(app: &AppHandle) -> tauri::Result<Self> {
+        Ok(TimerItems {
+            label: MenuItem::new(app, timer::label(None), false, None::<&str>)?,
+            start: MenuItem::with_id(app, START_ID, "Start", true, None::<&str>)?,
+            pause: MenuItem::with_id(app, PAUSE_ID, "Pause", false, None::<&str>)?,
+            skip: MenuItem::with_id(app, SKIP_ID, "Skip", false, None::<&str>)?,
+        })
+    }
+
+    fn update(&self, state: Option<&TimerState>) -> tauri::Result<()> {
+        let is_ticking = state.is_some_and(TimerState::is_ticking);
+        let is_paused = state.is_some_and(TimerState::is_paused);
+
+        self.label.set_text(timer::label(state))?;
+        self.start
+            .set_text(if is_paused { "Resume" } else { "Start" })?;
+        self.start.set_enabled(!is_ticking)?;
+        self.pause.set_enabled(is_ticking)?;
+        self.skip.set_enabled(is_ticking || is_paused)
+    }
+}
+
+/// Adds the tray icon. The database may still be locked, its habits are
+/// listed after the next change.
+pub fn setup(app: &AppHandle, api: ApiImpl) -> tauri::Result<()> {
+    let (menu, items) = build_menu(app, &api)?;
+    let items = Arc::new(Mutex::new(items));
+
+    let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("AchieveAlchemy")
+        .menu(&menu);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    let menu_api = api.clone();
+    tray.on_menu_event(move |app, event| on_menu_event(app, &menu_api, event))
+        .build(app)?;
+
+    let timer_items = items.clone();
+    app.listen_any(timer::TIMER_STATE_EVENT, move |event| {
+        let state = serde_json::from_str::<TimerState>(event.payload()).ok();
+        if let Err(e) = timer_items.lock().unwrap().update(state.as_ref()) {
+            println!("Failed to update the tray: {}", e);
+        }
+    });
+
+    let handle = app.clone();
+    app.listen_any(HABITS_CHANGED_EVENT, move |_| {
+        if let Err(e) = rebuild_menu(&handle, &api, &items) {
+            println!("Failed to update the tray: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+fn build_menu(app: &AppHandle, api: &ApiImpl) -> tauri::Result<(Menu<Wry>, TimerItems)> {
+    let items = TimerItems::new(app)?;
+    items.update(api.timer_state.lock().unwrap().as_ref())?;
+
+    let menu = Menu::new(app)?;
+    menu.append(&items.label)?;
+    menu.append(&items.start)?;
+    menu.append(&items.pause)?;
+    menu.append(&items.skip)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    match today_habits(api) {
+        Ok(habits) => {
+            for (habit, is_completed) in habits {
+                menu.append(&CheckMenuItem::with_id(
+                    app,
+                    format!("{}{}", HABIT_ID_PREFIX, habit.id),
+                    &habit.title,
+                    true,
+                    is_completed,
+                    None::<&str>,
+                )?)?;
+            }
+            menu.append(&PredefinedMenuItem::separator(app)?)?;
+        }
+        Err(e) => println!("Failed to list habits in the tray: {}", e),
+    }
+
+    menu.append(&MenuItem::with_id(
+        app,
+        SHOW_ID,
+        "Show window",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        QUIT_ID,
+        "Quit",
+        true,
+        None::<&str>,
+    )?)?;
+
+    Ok((menu, items))
+}
+
+fn rebuild_menu(app: &AppHandle, api: &ApiImpl, items: &Mutex<TimerItems>) -> tauri::Result<()> {
+    let (menu, new_items) = build_menu(app, api)?;
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(menu))?;
+    }
+    *items.lock().unwrap() = new_items;
+
+    Ok(())
+}
+
+/// Active habits, and whether each is completed today. Empty while the
+/// database is locked.
+fn today_habits(api: &ApiImpl) -> Result<Vec<(Habit, bool)>, AppError> {
+    let connection = match api.pool.lock().unwrap().as_ref() {
+        Some(pool) => pool.get(),
+        None => return Ok(Vec::new()),
+    };
+    let connection = &mut connection.map_err(|e| e.to_string())?;
+
+    let today = settings::load(connection)?.today();
+    let completions = connection.completions_on(today)?;
+
+    Ok(connection
+        .active_habits()?
+        .into_iter()
+        .map(|habit| {
+            let is_completed = completions
+                .iter()
+                .any(|completion| completion.habit_id == habit.id);
+            (habit, is_completed)
+        })
+        .collect())
+}
+
+fn on_menu_event(app: &AppHandle, api: &ApiImpl, event: MenuEvent) {
+    let command = match event.id().as_ref() {
+        START_ID => {
+            let state = api.timer_state.lock().unwrap();
+            match state.as_ref() {
+                Some(state) if state.is_paused() => Some(TimerCommand::Resume),
+                _ => Some(TimerCommand::Start),
+            }
+        }
+        PAUSE_ID => Some(TimerCommand::Pause),
+        SKIP_ID => Some(TimerCommand::Skip),
+        SHOW_ID => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            None
+        }
+        QUIT_ID => {
+            app.exit(0);
+            None
+        }
+        id => {
+            if let Some(habit_id) = id.strip_prefix(HABIT_ID_PREFIX) {
+                let api = api.clone();
+                let habit_id = habit_id.to_string();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = toggle_today(api, habit_id).await {
+                        println!("Failed to toggle the habit from the tray: {}", e);
+                    }
+                });
+            }
+            None
+        }
+    };
+
+    if let Some(command) = command {
+        if let Err(e) = app.emit(timer::TIMER_COMMAND_EVENT, command) {
+            println!("Failed to send the timer command: {}", e);
+        }
+    }
+}
+
+/// Marks the habit as done today, or unmarks it, through the same resolver
+/// as the window.
+async fn toggle_today(api: ApiImpl, habit_id: String) -> Result<(), AppError> {
+    let (today, completion) = {
+        let connection = &mut api.get_connection()?;
+        let today = settings::load(connection)?.today();
+        let completion = connection
+            .completions_on(today)?
+            .into_iter()
+            .find(|completion| completion.habit_id == habit_id);
+        (today, completion)
+    };
+
+    api.toggle_habit_completion(CreateHabitCompletionArgs {
+        id: completion.map(|completion| completion.id),
+        habit_id,
+        created_at: Some(today.format("%Y-%m-%d").to_string()),
+    })
+    .await
+}
@@ -0,0 +1,253 @@
+use std::error::Error;
+
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::entitlements;
+use crate::error::AppError;
+use crate::events::{self, Operation};
+use crate::models::{CreateHabitCompletion, Event, Habit, PomodoroCycle};
+use crate::schema::{habit_completions, habits, pomodoro_cycles};
+
+/// How many actions each stack keeps before the oldest are dropped.
+const MAX_ACTIONS: usize = 50;
+
+type UndoResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// A user action together with the events it recorded.
+#[derive(Debug, Clone)]
+struct Action {
+    description: String,
+    events: Vec<Event>,
+}
+
+/// Undo and redo stacks of the current session. They only live in memory, so
+/// a restart starts with nothing to undo.
+#[derive(Debug, Default)]
+pub struct UndoStack {
+    undo: Vec<Action>,
+    redo: Vec<Action>,
+}
+
+impl UndoStack {
+    /// Makes `events` undoable as one action. A new action invalidates
+    /// everything that could be redone.
+    pub fn push(&mut self, description: String, events: Vec<Event>) {
+        self.redo.clear();
+        push_bounded(
+            &mut self.undo,
+            Action {
+                description,
+                events,
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Reverts the most recent action and returns its description, or `None`
+    /// if there is nothing to undo.
+    pub fn undo(&mut self, connection: &mut SqliteConnection) -> Result<Option<String>, AppError> {
+        let Some(action) = self.undo.pop() else {
+            return Ok(None);
+        };
+
+        let reverted = revert_action(connection, &action)?;
+        push_bounded(&mut self.redo, reverted);
+
+        Ok(Some(action.description))
+    }
+
+    /// Reapplies the most recently undone action and returns its description,
+    /// or `None` if there is nothing to redo.
+    pub fn redo(&mut self, connection: &mut SqliteConnection) -> Result<Option<String>, AppError> {
+        let Some(action) = self.redo.pop() else {
+            return Ok(None);
+        };
+
+        // Reverting the events recorded by the undo restores the original state
+        let reapplied = revert_action(connection, &action)?;
+        push_bounded(&mut self.undo, reapplied);
+
+        Ok(Some(action.description))
+    }
+}
+
+fn push_bounded(stack: &mut Vec<Action>, action: Action) {
+    stack.push(action);
+    if stack.len() > MAX_ACTIONS {
+        stack.remove(0);
+    }
+}
+
+/// Reverts the events of `action` in reverse order, in a single transaction.
+/// Returns an action made of the events recorded while doing so.
+fn revert_action(connection: &mut SqliteConnection, action: &Action) -> Result<Action, AppError> {
+    let events = connection.transaction::<_, AppError, _>(|connection| {
+        let events = action
+            .events
+            .iter()
+            .rev()
+            .map(|event| revert_event(connection, event))
+            .collect::<UndoResult<Vec<_>>>()
+            .map_err(|e| AppError::from(e.to_string()))?;
+
+        // Bringing a deleted or archived habit back takes a slot, like
+        // creating one does
+        if events.iter().any(reactivates_habit) {
+            entitlements::require_habit_slots(connection, 0)?;
+        }
+
+        Ok(events)
+    })?;
+
+    Ok(Action {
+        description: action.description.clone(),
+        events,
+    })
+}
+
+/// Puts the row back into the state before `event` and records that as a new
+/// event, so the history stays complete.
+fn revert_event(connection: &mut SqliteConnection, event: &Event) -> UndoResult<Event> {
+    write_state(
+        connection,
+        &event.entity,
+        &event.entity_id,
+        event.after.as_deref(),
+        event.before.as_deref(),
+    )?;
+
+    let operation = match (&event.before, &event.after) {
+        (None, _) => Operation::Delete,
+        (_, None) => Operation::Create,
+        _ => Operation::Update,
+    };
+
+    Ok(events::record_json(
+        connection,
+        &event.entity,
+        &event.entity_id,
+        operation,
+        event.after.clone(),
+        event.before.clone(),
+    )?)
+}
+
+fn reactivates_habit(event: &Event) -> bool {
+    let is_active = |state: Option<&str>| {
+        state
+            .and_then(|state| serde_json::from_str::<Habit>(state).ok())
+            .is_some_and(|habit| !habit.is_archived)
+    };
+
+    event.entity == events::HABITS
+        && is_active(event.after.as_deref())
+        && !is_active(event.before.as_deref())
+}
+
+/// Writes the serialized row `state`, or deletes the row if it is `None`.
+/// Fails if the row is not in the `expected` state anymore, e.g. because it
+/// was changed by the CLI or a sync since.
+fn write_state(
+    connection: &mut SqliteConnection,
+    entity: &str,
+    entity_id: &str,
+    expected: Option<&str>,
+    state: Option<&str>,
+) -> UndoResult<()> {
+    match entity {
+        events::HABITS => {
+            let current = habits::table
+                .find(entity_id)
+                .first::<Habit>(connection)
+                .optional()?;
+            ensure_unchanged(entity, current, expected)?;
+
+            match state {
+                Some(state) => {
+                    let habit: Habit = serde_json::from_str(state)?;
+                    diesel::insert_into(habits::table)
+                        .values(&habit)
+                        .on_conflict(habits::id)
+                        .do_update()
+                        .set(&habit)
+                        .execute(connection)?;
+                }
+                None => {
+                    diesel::delete(habits::table.find(entity_id)).execute(connection)?;
+                }
+            }
+        }
+        events::HABIT_COMPLETIONS => {
+            let current = habit_completions::table
+                .find(entity_id)
+                .select(CreateHabitCompletion::as_select())
+                .first(connection)
+                .optional()?;
+            ensure_unchanged(entity, current, expected)?;
+
+            match state {
+                Some(state) => {
+                    let completion: CreateHabitCompletion = serde_json::from_str(state)?;
+                    diesel::insert_into(habit_completions::table)
+                        .values(&completion)
+                        .execute(connection)?;
+                }
+                None => {
+                    diesel::delete(habit_completions::table.find(entity_id)).execute(connection)?;
+                }
+            }
+        }
+        events::POMODORO_CYCLES => {
+            let current = pomodoro_cycles::table
+                .find(entity_id)
+                .first::<PomodoroCycle>(connection)
+                .optional()?;
+            ensure_unchanged(entity, current, expected)?;
+
+            match state {
+                Some(state) => {
+                    let cycle: PomodoroCycle = serde_json::from_str(state)?;
+                    diesel::insert_into(pomodoro_cycles::table)
+                        .values(&cycle)
+                        .on_conflict(pomodoro_cycles::id)
+                        .do_update()
+                        .set(&cycle)
+                        .execute(connection)?;
+                }
+                None => {
+                    diesel::delete(pomodoro_cycles::table.find(entity_id)).execute(connection)?;
+                }
+            }
+        }
+        entity => return Err(format!("Cannot undo changes to {}", entity).into()),
+    }
+
+    Ok(())
+}
+
+/// Compares the rows as JSON, so both sides are in the same format.
+fn ensure_unchanged<T: Serialize + DeserializeOwned>(
+    entity: &str,
+    current: Option<T>,
+    expected: Option<&str>,
+) -> UndoResult<()> {
+    let expected = expected.map(serde_json::from_str::<T>).transpose()?;
+    let current = current.map(serde_json::to_value).transpose()?;
+
+    if current != expected.map(serde_json::to_value).transpose()? {
+        return Err(format!(
+            "The {} changed since, so this can't be undone",
+            entity.replace('_', " ")
+        )
+        .into());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Longest duration accepted for any pomodoro phase, in milliseconds.
+pub const MAX_DURATION_MS: i32 = 4 * 60 * 60 * 1000;
+pub const MAX_SESSIONS_UNTIL_LONG_BREAK: i32 = 12;
+pub const MAX_TITLE_LENGTH: usize = 100;
+
+/// A problem with a single argument field. `field` uses the camelCase name the
+/// frontend sends.
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Implemented by every `*Args` type, checked before a resolver touches the
+/// database.
+pub trait Validate {
+    fn validate(&self) -> Result<(), AppError>;
+}
+
+/// Collects field errors so all problems are reported at once.
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, field: &str, message: impl Into<String>) -> &mut Self {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn not_empty(&mut self, field: &str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.error(field, "must not be empty");
+        }
+        self
+    }
+
+    /// A human-entered name: not blank and at most `MAX_TITLE_LENGTH` chars.
+    pub fn title(&mut self, field: &str, value: &str) -> &mut Self {
+        if value.trim().is_empty() {
+            self.error(field, "must not be empty");
+        } else if value.chars().count() > MAX_TITLE_LENGTH {
+            self.error(
+                field,
+                format!("must be at most {} characters", MAX_TITLE_LENGTH),
+            );
+        }
+        self
+    }
+
+    pub fn range(&mut self, field: &str, value: i32, min: i32, max: i32) -> &mut Self {
+        if value < min || value > max {
+            self.error(field, format!("must be between {} and {}", min, max));
+        }
+        self
+    }
+
+    pub fn duration_ms(&mut self, field: &str, value: i32) -> &mut Self {
+        self.range(field, value, 1, MAX_DURATION_MS)
+    }
+
+    pub fn sessions_until_long_break(&mut self, field: &str, value: i32) -> &mut Self {
+        self.range(field, value, 1, MAX_SESSIONS_UNTIL_LONG_BREAK)
+    }
+
+    /// A calendar day formatted as `%Y-%m-%d`.
+    pub fn date(&mut self, field: &str, value: &str) -> Option<NaiveDate> {
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+        if date.is_none() {
+            self.error(field, "must be a date formatted as YYYY-MM-DD");
+        }
+        date
+    }
+
+    /// A time of day formatted as `%H:%M`.
+    pub fn time(&mut self, field: &str, value: &str) -> Option<NaiveTime> {
+        let time = NaiveTime::parse_from_str(value, "%H:%M").ok();
+        if time.is_none() {
+            self.error(field, "must be a time formatted as HH:MM");
+        }
+        time
+    }
+
+    pub fn finish(&mut self) -> Result<(), AppError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation {
+                errors: std::mem::take(&mut self.errors),
+            })
+        }
+    }
+}
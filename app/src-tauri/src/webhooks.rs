@@ -0,0 +1,633 @@
+//! Outgoing webhooks for habit and pomodoro events.
+//!
+//! Events are written to `webhook_deliveries` in the same transaction as the
+//! change that caused them, and delivered later by [`deliver_due`], which
+//! retries failed deliveries with exponential backoff. Every request carries
+//! the HMAC-SHA256 of its body, keyed with the webhook's secret, as
+//! `X-AchieveAlchemy-Signature-256: sha256=<hex>`.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteConnection, SqliteValue};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{self, Habit};
+use crate::schema::{streak_milestones, webhook_deliveries, webhooks};
+use crate::validation::{Validate, Validator};
+
+pub const SIGNATURE_HEADER: &str = "X-AchieveAlchemy-Signature-256";
+pub const EVENT_HEADER: &str = "X-AchieveAlchemy-Event";
+pub const DELIVERY_HEADER: &str = "X-AchieveAlchemy-Delivery";
+
+/// Streak lengths, in days, that fire `STREAK_MILESTONE`.
+const STREAK_MILESTONES: &[i32] = &[3, 7, 14, 30, 60, 100, 200, 365, 500, 1000];
+/// A delivery is given up after this many failed attempts, about 2 days.
+const MAX_ATTEMPTS: i32 = 10;
+const FIRST_RETRY_DELAY_SECS: i64 = 30;
+const MAX_RETRY_DELAY_SECS: i64 = 6 * 60 * 60;
+/// Delivered and failed deliveries are kept this long for troubleshooting.
+const FINISHED_RETENTION_DAYS: i64 = 7;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const SECRET_LENGTH: usize = 32;
+
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WebhookEventType {
+    HabitCompleted,
+    StreakMilestone,
+    SessionStarted,
+    SessionCompleted,
+    CycleCompleted,
+    /// Only sent by `send_ping`, webhooks don't subscribe to it.
+    Ping,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::HabitCompleted => "HABIT_COMPLETED",
+            WebhookEventType::StreakMilestone => "STREAK_MILESTONE",
+            WebhookEventType::SessionStarted => "SESSION_STARTED",
+            WebhookEventType::SessionCompleted => "SESSION_COMPLETED",
+            WebhookEventType::CycleCompleted => "CYCLE_COMPLETED",
+            WebhookEventType::Ping => "PING",
+        }
+    }
+}
+
+impl FromStr for WebhookEventType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "HABIT_COMPLETED" => Ok(WebhookEventType::HabitCompleted),
+            "STREAK_MILESTONE" => Ok(WebhookEventType::StreakMilestone),
+            "SESSION_STARTED" => Ok(WebhookEventType::SessionStarted),
+            "SESSION_COMPLETED" => Ok(WebhookEventType::SessionCompleted),
+            "CYCLE_COMPLETED" => Ok(WebhookEventType::CycleCompleted),
+            "PING" => Ok(WebhookEventType::Ping),
+            _ => Err(format!("Unknown webhook event type: {}", s)),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for WebhookEventType {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for WebhookEventType {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(value.parse()?)
+    }
+}
+
+/// The event types a webhook subscribes to, stored as a JSON array.
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(transparent)]
+pub struct EventTypes(pub Vec<WebhookEventType>);
+
+impl ToSql<Text, Sqlite> for EventTypes {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(serde_json::to_string(&self.0)?);
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for EventTypes {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(EventTypes(serde_json::from_str(&value)?))
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, specta::Type, AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "PENDING",
+            DeliveryStatus::Delivered => "DELIVERED",
+            DeliveryStatus::Failed => "FAILED",
+        }
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PENDING" => Ok(DeliveryStatus::Pending),
+            "DELIVERED" => Ok(DeliveryStatus::Delivered),
+            "FAILED" => Ok(DeliveryStatus::Failed),
+            _ => Err(format!("Unknown delivery status: {}", s)),
+        }
+    }
+}
+
+impl ToSql<Text, Sqlite> for DeliveryStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.as_str());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for DeliveryStatus {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let value = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(value.parse()?)
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = webhooks)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    pub secret: String,
+    pub event_types: EventTypes,
+    pub is_enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(table_name = webhook_deliveries)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event_type: WebhookEventType,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookArgs {
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    /// Generated if not given.
+    pub secret: Option<String>,
+}
+
+impl Validate for CreateWebhookArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validate_url(&mut validator, &self.url);
+        validate_event_types(&mut validator, &self.event_types);
+        if let Some(secret) = &self.secret {
+            validator.not_empty("secret", secret);
+        }
+        validator.finish()
+    }
+}
+
+#[taurpc::ipc_type]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookArgs {
+    pub id: String,
+    pub url: Option<String>,
+    pub event_types: Option<Vec<WebhookEventType>>,
+    pub is_enabled: Option<bool>,
+}
+
+impl Validate for UpdateWebhookArgs {
+    fn validate(&self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+        validator.not_empty("id", &self.id);
+        if let Some(url) = &self.url {
+            validate_url(&mut validator, url);
+        }
+        if let Some(event_types) = &self.event_types {
+            validate_event_types(&mut validator, event_types);
+        }
+        validator.finish()
+    }
+}
+
+fn validate_url(validator: &mut Validator, url: &str) {
+    match Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => {
+            validator.error("url", "must be an http or https URL");
+        }
+    }
+}
+
+fn validate_event_types(validator: &mut Validator, event_types: &[WebhookEventType]) {
+    if event_types.is_empty() {
+        validator.error("eventTypes", "must not be empty");
+    }
+    if event_types.contains(&WebhookEventType::Ping) {
+        validator.error("eventTypes", "PING cannot be subscribed to");
+    }
+}
+
+#[derive(Serialize, Deserialize, specta::Type, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeliveryReport {
+    pub delivered: i32,
+    pub retrying: i32,
+    pub failed: i32,
+}
+
+// --------------------------------------------------------------------------
+// Subscriptions
+// --------------------------------------------------------------------------
+
+pub fn webhooks(connection: &mut SqliteConnection) -> QueryResult<Vec<Webhook>> {
+    webhooks::table
+        .order(webhooks::created_at.asc())
+        .load::<Webhook>(connection)
+}
+
+pub fn create_webhook(
+    connection: &mut SqliteConnection,
+    args: CreateWebhookArgs,
+) -> Result<Webhook, AppError> {
+    args.validate()?;
+
+    let now = models::now();
+    let webhook = Webhook {
+        id: Uuid::new_v4().to_string(),
+        url: args.url,
+        secret: args.secret.unwrap_or_else(generate_secret),
+        event_types: EventTypes(args.event_types),
+        is_enabled: true,
+        created_at: now,
+        updated_at: now,
+    };
+
+    diesel::insert_into(webhooks::table)
+        .values(&webhook)
+        .execute(connection)?;
+
+    Ok(webhook)
+}
+
+pub fn update_webhook(
+    connection: &mut SqliteConnection,
+    args: UpdateWebhookArgs,
+) -> Result<Webhook, AppError> {
+    args.validate()?;
+
+    let mut webhook = webhooks::table
+        .find(&args.id)
+        .first::<Webhook>(connection)?;
+
+    if let Some(url) = args.url {
+        webhook.url = url;
+    }
+    if let Some(event_types) = args.event_types {
+        webhook.event_types = EventTypes(event_types);
+    }
+    if let Some(is_enabled) = args.is_enabled {
+        webhook.is_enabled = is_enabled;
+    }
+    webhook.updated_at = models::now();
+
+    diesel::update(webhooks::table.find(&webhook.id))
+        .set((
+            webhooks::url.eq(&webhook.url),
+            webhooks::event_types.eq(&webhook.event_types),
+            webhooks::is_enabled.eq(webhook.is_enabled),
+            webhooks::updated_at.eq(webhook.updated_at),
+        ))
+        .execute(connection)?;
+
+    Ok(webhook)
+}
+
+/// Deletes the webhook together with its pending and past deliveries.
+pub fn delete_webhook(connection: &mut SqliteConnection, webhook_id: &str) -> Result<(), AppError> {
+    connection.transaction::<_, AppError, _>(|connection| {
+        diesel::delete(
+            webhook_deliveries::table.filter(webhook_deliveries::webhook_id.eq(webhook_id)),
+        )
+        .execute(connection)?;
+
+        let deleted = diesel::delete(webhooks::table.find(webhook_id)).execute(connection)?;
+        if deleted == 0 {
+            return Err(format!("Unknown webhook: {}", webhook_id).into());
+        }
+
+        Ok(())
+    })
+}
+
+/// The most recent deliveries of a webhook, newest first.
+pub fn deliveries(
+    connection: &mut SqliteConnection,
+    webhook_id: &str,
+    limit: i64,
+) -> QueryResult<Vec<WebhookDelivery>> {
+    webhook_deliveries::table
+        .filter(webhook_deliveries::webhook_id.eq(webhook_id))
+        .order(webhook_deliveries::created_at.desc())
+        .limit(limit)
+        .load::<WebhookDelivery>(connection)
+}
+
+fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_LENGTH];
+    OsRng.fill_bytes(&mut secret);
+    hex::encode(secret)
+}
+
+// --------------------------------------------------------------------------
+// Queue
+// --------------------------------------------------------------------------
+
+/// Queues `STREAK_MILESTONE` when a streak of `habit` ending on `day`
+/// reaches a milestone. Each milestone of a streak fires once, even if a day
+/// is unmarked and marked again.
+pub fn enqueue_streak_milestone(
+    connection: &mut SqliteConnection,
+    habit: &Habit,
+    day: NaiveDate,
+    streak: i32,
+) -> QueryResult<()> {
+    if !STREAK_MILESTONES.contains(&streak) {
+        return Ok(());
+    }
+
+    let started_on = day - chrono::Duration::days(i64::from(streak - 1));
+    let recorded = diesel::insert_or_ignore_into(streak_milestones::table)
+        .values((
+            streak_milestones::habit_id.eq(&habit.id),
+            streak_milestones::started_on.eq(started_on),
+            streak_milestones::streak.eq(streak),
+            streak_milestones::created_at.eq(models::now()),
+        ))
+        .execute(connection)?;
+
+    if recorded == 0 {
+        return Ok(());
+    }
+
+    enqueue(
+        connection,
+        WebhookEventType::StreakMilestone,
+        &json!({ "habit": habit, "streak": streak }),
+    )
+}
+
+/// Queues `data` for every enabled webhook subscribed to `event_type`. Meant
+/// to run in the same transaction as the change that caused the event.
+pub fn enqueue<T: Serialize>(
+    connection: &mut SqliteConnection,
+    event_type: WebhookEventType,
+    data: &T,
+) -> QueryResult<()> {
+    let subscribers: Vec<Webhook> = webhooks::table
+        .filter(webhooks::is_enabled.eq(true))
+        .load::<Webhook>(connection)?
+        .into_iter()
+        .filter(|webhook| webhook.event_types.0.contains(&event_type))
+        .collect();
+
+    for webhook in subscribers {
+        insert_delivery(connection, &webhook.id, event_type, data)?;
+    }
+
+    Ok(())
+}
+
+fn insert_delivery<T: Serialize>(
+    connection: &mut SqliteConnection,
+    webhook_id: &str,
+    event_type: WebhookEventType,
+    data: &T,
+) -> QueryResult<WebhookDelivery> {
+    let id = Uuid::new_v4().to_string();
+    let now = models::now();
+
+    // The delivery id doubles as the event id, receivers can use it to
+    // recognize retries of a request they already handled
+    let payload = json!({
+        "id": id,
+        "type": event_type,
+        "createdAt": now,
+        "data": data,
+    });
+
+    let delivery = WebhookDelivery {
+        id,
+        webhook_id: webhook_id.to_string(),
+        event_type,
+        payload: serde_json::to_string(&payload)
+            .map_err(|e| Error::SerializationError(Box::new(e)))?,
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        next_attempt_at: now,
+        last_error: None,
+        created_at: now,
+    };
+
+    diesel::insert_into(webhook_deliveries::table)
+        .values(&delivery)
+        .execute(connection)?;
+
+    Ok(delivery)
+}
+
+// --------------------------------------------------------------------------
+// Delivery
+// --------------------------------------------------------------------------
+
+pub fn client() -> Result<Client, String> {
+    Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("AchieveAlchemy/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Attempts every pending delivery that is due, and drops finished ones
+/// past their retention. Does blocking network IO.
+pub fn deliver_due(
+    connection: &mut SqliteConnection,
+    client: &Client,
+) -> Result<DeliveryReport, String> {
+    let now = models::now();
+
+    let due = webhook_deliveries::table
+        .inner_join(webhooks::table)
+        .filter(webhook_deliveries::status.eq(DeliveryStatus::Pending))
+        .filter(webhook_deliveries::next_attempt_at.le(now))
+        .filter(webhooks::is_enabled.eq(true))
+        .order(webhook_deliveries::created_at.asc())
+        .select((WebhookDelivery::as_select(), Webhook::as_select()))
+        .load::<(WebhookDelivery, Webhook)>(connection)
+        .map_err(|e| e.to_string())?;
+
+    let mut report = DeliveryReport::default();
+    for (delivery, webhook) in due {
+        let delivery = attempt(connection, client, &webhook, delivery)?;
+        match delivery.status {
+            DeliveryStatus::Delivered => report.delivered += 1,
+            DeliveryStatus::Pending => report.retrying += 1,
+            DeliveryStatus::Failed => report.failed += 1,
+        }
+    }
+
+    let cutoff = now - chrono::Duration::days(FINISHED_RETENTION_DAYS);
+    diesel::delete(
+        webhook_deliveries::table
+            .filter(webhook_deliveries::status.ne(DeliveryStatus::Pending))
+            .filter(webhook_deliveries::created_at.lt(cutoff)),
+    )
+    .execute(connection)
+    .map_err(|e| e.to_string())?;
+
+    Ok(report)
+}
+
+/// Sends a `PING` to the webhook right away, whether or not it is enabled,
+/// and returns the delivery. A failed ping is retried like any delivery.
+pub fn send_ping(
+    connection: &mut SqliteConnection,
+    client: &Client,
+    webhook_id: &str,
+) -> Result<WebhookDelivery, AppError> {
+    let webhook = webhooks::table
+        .find(webhook_id)
+        .first::<Webhook>(connection)?;
+
+    let delivery = insert_delivery(
+        connection,
+        &webhook.id,
+        WebhookEventType::Ping,
+        &json!({ "webhookId": webhook.id }),
+    )?;
+
+    Ok(attempt(connection, client, &webhook, delivery)?)
+}
+
+/// Posts the delivery once and stores the outcome.
+fn attempt(
+    connection: &mut SqliteConnection,
+    client: &Client,
+    webhook: &Webhook,
+    mut delivery: WebhookDelivery,
+) -> Result<WebhookDelivery, String> {
+    let result = client
+        .post(&webhook.url)
+        .header("Content-Type", "application/json")
+        .header(EVENT_HEADER, delivery.event_type.as_str())
+        .header(DELIVERY_HEADER, &delivery.id)
+        .header(
+            SIGNATURE_HEADER,
+            format!(
+                "sha256={}",
+                sign(&webhook.secret, delivery.payload.as_bytes())
+            ),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .map_err(|e| e.to_string())
+        .and_then(|response| {
+            let status = response.status();
+            if status.is_success() {
+                Ok(())
+            } else {
+                Err(format!("Receiver responded with {}", status))
+            }
+        });
+
+    delivery.attempts += 1;
+    match result {
+        Ok(()) => {
+            delivery.status = DeliveryStatus::Delivered;
+            delivery.last_error = None;
+        }
+        Err(e) => {
+            delivery.last_error = Some(e);
+            if delivery.attempts >= MAX_ATTEMPTS {
+                delivery.status = DeliveryStatus::Failed;
+            } else {
+                delivery.next_attempt_at =
+                    models::now() + chrono::Duration::seconds(retry_delay_secs(delivery.attempts));
+            }
+        }
+    }
+
+    diesel::update(webhook_deliveries::table.find(&delivery.id))
+        .set((
+            webhook_deliveries::status.eq(delivery.status),
+            webhook_deliveries::attempts.eq(delivery.attempts),
+            webhook_deliveries::next_attempt_at.eq(delivery.next_attempt_at),
+            webhook_deliveries::last_error.eq(&delivery.last_error),
+        ))
+        .execute(connection)
+        .map_err(|e| e.to_string())?;
+
+    Ok(delivery)
+}
+
+/// 30 seconds after the first failure, doubling up to 6 hours.
+fn retry_delay_secs(attempts: i32) -> i64 {
+    let exponent = (attempts - 1).clamp(0, 20) as u32;
+    (FIRST_RETRY_DELAY_SECS << exponent).min(MAX_RETRY_DELAY_SECS)
+}
+
+// --------------------------------------------------------------------------
+// Signatures
+// --------------------------------------------------------------------------
+
+/// Hex encoded HMAC-SHA256 of `body`, keyed with `secret`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    hex::encode(hmac_sha256(secret.as_bytes(), body))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
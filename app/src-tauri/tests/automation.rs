@@ -0,0 +1,266 @@
+use std::net::TcpListener;
+use std::time::Duration;
+
+use achievealchemy_lib::automation::AutomationServer;
+use achievealchemy_lib::settings::{AutomationSettings, Settings};
+use achievealchemy_lib::testing;
+use achievealchemy_lib::{Api, ApiImpl};
+use reqwest::StatusCode;
+use serde_json::{json, Map, Value};
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+async fn is_listening(port: u16) -> bool {
+    for _ in 0..40 {
+        if tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_ok()
+        {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
+
+/// Enables the automation API of `api` on a free port. Returns its base URL
+/// and token.
+async fn serve(api: &ApiImpl) -> (String, String) {
+    let settings = api.clone().regenerate_automation_token().await.unwrap();
+    let token = settings.automation.token.clone().unwrap();
+    let port = free_port();
+
+    api.clone()
+        .update_settings(Settings {
+            automation: AutomationSettings {
+                enabled: true,
+                port,
+                ..settings.automation.clone()
+            },
+            ..settings
+        })
+        .await
+        .unwrap();
+    assert!(is_listening(port).await);
+
+    (format!("http://127.0.0.1:{}", port), token)
+}
+
+async fn call(url: &str, token: &str, procedure: &str, body: Value) -> (StatusCode, Value) {
+    let response = reqwest::Client::new()
+        .post(format!("{}/api/{}", url, procedure))
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    let status = response.status();
+
+    (status, response.json().await.unwrap())
+}
+
+/// Procedures of `Api` with their parameter names, read from its declaration.
+fn api_procedures() -> Vec<(String, Vec<String>)> {
+    let source = include_str!("../src/lib.rs");
+    let start = source.find("pub trait Api {").unwrap();
+    let end = start + source[start..].find("\n}\n").unwrap();
+    let declarations: Vec<&str> = source[start..end].split("async fn ").collect();
+
+    declarations
+        .windows(2)
+        // Events are emitted, not called
+        .filter(|pair| !pair[0].trim_end().ends_with("#[taurpc(event)]"))
+        .map(|pair| {
+            let (name, rest) = pair[1].split_once('(').unwrap();
+            let (params, _) = rest.split_once(')').unwrap();
+            let params = params
+                .split(',')
+                .filter_map(|param| param.split_once(':'))
+                .map(|(param, _)| param.trim().to_string())
+                .collect();
+            (name.to_string(), params)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn requests_need_the_token() {
+    let api = testing::api(&testing::temp_dir());
+    let (url, token) = serve(&api).await;
+    let client = reqwest::Client::new();
+    let endpoint = format!("{}/api/get_active_habits", url);
+
+    let missing = client.post(&endpoint).send().await.unwrap();
+    let wrong = client
+        .post(&endpoint)
+        .bearer_auth(format!("{}0", &token[1..]))
+        .send()
+        .await
+        .unwrap();
+    let valid = client
+        .post(&endpoint)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(valid.status(), StatusCode::OK);
+    assert_eq!(valid.json::<Value>().await.unwrap(), json!([]));
+}
+
+#[tokio::test]
+async fn procedures_are_called_with_named_params() {
+    let api = testing::api(&testing::temp_dir());
+    let (url, token) = serve(&api).await;
+    let habit = json!({ "title": "Read", "description": "", "icon": "CircleCheck" });
+
+    let (status, _) = call(&url, &token, "create_habit", json!({ "args": habit })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, habits) = call(&url, &token, "get_active_habits", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(habits[0]["title"], "Read");
+
+    let (status, error) = call(&url, &token, "create_habit", json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["message"].as_str().unwrap().contains("args"));
+
+    let invalid = json!({ "args": { "title": "", "description": "", "icon": "CircleCheck" } });
+    let (status, error) = call(&url, &token, "create_habit", invalid).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error["kind"], "VALIDATION");
+
+    let (status, _) = call(&url, &token, "drop_database", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn only_loopback_addresses_are_served() {
+    let api = testing::api(&testing::temp_dir());
+    let server = AutomationServer::default();
+    let port = free_port();
+
+    // Saving these settings fails, this is what an older row could hold
+    server.apply(
+        &AutomationSettings {
+            enabled: true,
+            bind_address: "0.0.0.0".to_string(),
+            port,
+            token: Some("token".to_string()),
+        },
+        api,
+    );
+
+    assert!(!is_listening(port).await);
+}
+
+#[tokio::test]
+async fn every_procedure_of_the_api_is_exposed() {
+    let api = testing::api(&testing::temp_dir());
+    let (url, _) = serve(&api).await;
+    let document: Value = reqwest::get(format!("{}/openapi.json", url))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let procedures = api_procedures();
+    assert!(procedures.len() > 50);
+    assert_eq!(
+        document["paths"].as_object().unwrap().len(),
+        procedures.len()
+    );
+    for (name, params) in procedures {
+        let operation = &document["paths"][format!("/api/{}", name)]["post"];
+        let properties = operation["requestBody"]["content"]["application/json"]["schema"]
+            ["properties"]
+            .as_object()
+            .unwrap_or_else(|| panic!("{} is not exposed", name));
+
+        let exposed: Vec<&String> = properties.keys().collect();
+        assert_eq!(exposed, params.iter().collect::<Vec<_>>(), "{}", name);
+    }
+}
+
+// The bindings are only written by debug builds of the app, so they can fall
+// behind the API
+#[test]
+fn the_bindings_have_every_procedure() {
+    let bindings = include_str!("../../src/bindings.ts");
+    let (_, args_map) = bindings.split_once("const ARGS_MAP = { '': '").unwrap();
+    let (args_map, _) = args_map.split_once("' }").unwrap();
+    let args_map: Map<String, Value> = serde_json::from_str(args_map).unwrap();
+
+    for (name, params) in api_procedures() {
+        assert_eq!(args_map.get(&name), Some(&json!(params)), "{}", name);
+    }
+    assert_eq!(args_map.get("data_changed"), Some(&json!(["change"])));
+}
+
+#[tokio::test]
+async fn the_openapi_document_has_the_types_of_the_bindings() {
+    let api = testing::api(&testing::temp_dir());
+    let (url, _) = serve(&api).await;
+    let document: Value = reqwest::get(format!("{}/openapi.json", url))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let request = |name: &str| {
+        document["paths"][format!("/api/{}", name)]["post"]["requestBody"]["content"]
+            ["application/json"]["schema"]
+            .clone()
+    };
+    let reply = |name: &str| {
+        document["paths"][format!("/api/{}", name)]["post"]["responses"]["200"]["content"]
+            ["application/json"]["schema"]
+            .clone()
+    };
+    let schemas = &document["components"]["schemas"];
+
+    let create_habit = request("create_habit");
+    assert_eq!(
+        create_habit["properties"]["args"]["$ref"],
+        "#/components/schemas/CreateHabitArgs"
+    );
+    assert_eq!(create_habit["required"], json!(["args"]));
+    assert_eq!(
+        schemas["CreateHabitArgs"]["properties"]["title"]["type"],
+        "string"
+    );
+
+    assert_eq!(
+        reply("get_active_habits"),
+        json!({ "type": "array", "items": { "$ref": "#/components/schemas/Habit" } })
+    );
+    assert_eq!(
+        schemas["Habit"]["properties"]["isArchived"]["type"],
+        "boolean"
+    );
+
+    // Optional parameters may be left out
+    let sync_config = request("update_sync_config");
+    assert_eq!(sync_config["required"], json!([]));
+    assert_eq!(sync_config["properties"]["config"]["nullable"], true);
+    assert_eq!(
+        schemas["SyncBackendConfig"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+
+    assert_eq!(
+        schemas["SessionType"],
+        json!({ "type": "string", "enum": ["LONG_BREAK", "FOCUS", "SHORT_BREAK"] })
+    );
+}
@@ -0,0 +1,132 @@
+use std::fs;
+
+use achievealchemy_lib::backup::{self, BackupKind, BackupRetention};
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::repository::CreateHabitArgs;
+use achievealchemy_lib::testing;
+use achievealchemy_lib::Api;
+
+fn create_args(title: &str) -> CreateHabitArgs {
+    CreateHabitArgs {
+        title: title.to_string(),
+        description: String::new(),
+        icon: "CircleCheck".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn opening_the_database_takes_a_daily_backup() {
+    let api = testing::api(&testing::temp_dir());
+
+    let backups = api.list_backups().await.unwrap();
+
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].kind, BackupKind::Daily);
+    assert!(backups[0].size_bytes > 0.0);
+}
+
+#[tokio::test]
+async fn restoring_a_backup_brings_back_its_data() {
+    let api = testing::api(&testing::temp_dir());
+    let daily = api.clone().list_backups().await.unwrap().remove(0);
+    api.clone().create_habit(create_args("Read")).await.unwrap();
+
+    api.clone().restore_backup(daily.file_name).await.unwrap();
+
+    assert!(api.clone().get_active_habits().await.unwrap().is_empty());
+    let kinds: Vec<_> = api
+        .list_backups()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|backup| backup.kind)
+        .collect();
+    assert!(kinds.contains(&BackupKind::PreRestore));
+}
+
+#[tokio::test]
+async fn only_listed_backups_can_be_restored() {
+    let api = testing::api(&testing::temp_dir());
+    api.clone().create_habit(create_args("Read")).await.unwrap();
+
+    for file_name in ["db-missing.sqlite", "../db.sqlite"] {
+        assert!(api
+            .clone()
+            .restore_backup(file_name.to_string())
+            .await
+            .is_err());
+    }
+
+    assert_eq!(api.get_active_habits().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn retention_deletes_the_oldest_backups() {
+    let data_dir = testing::temp_dir();
+    let api = testing::api(&data_dir);
+    let today = api.clone().list_backups().await.unwrap().remove(0);
+    for day in ["20240101", "20240102", "20240103"] {
+        let file_name = format!("db-{}-000000-daily.sqlite", day);
+        fs::write(backup::backup_dir(&data_dir).join(file_name), "snapshot").unwrap();
+    }
+
+    api.clone()
+        .update_backup_retention(BackupRetention {
+            daily: 2,
+            ..BackupRetention::default()
+        })
+        .await
+        .unwrap();
+
+    let kept: Vec<_> = api
+        .clone()
+        .list_backups()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|backup| backup.file_name)
+        .collect();
+    assert_eq!(kept.len(), 2);
+    assert!(kept.contains(&today.file_name));
+    assert!(kept.contains(&"db-20240103-000000-daily.sqlite".to_string()));
+    assert_eq!(api.get_backup_retention().await.daily, 2);
+}
+
+#[tokio::test]
+async fn every_kind_keeps_at_least_one_backup() {
+    let api = testing::api(&testing::temp_dir());
+
+    let result = api
+        .clone()
+        .update_backup_retention(BackupRetention {
+            pre_restore: 0,
+            ..BackupRetention::default()
+        })
+        .await;
+
+    match result {
+        Err(AppError::Validation { errors }) => assert_eq!(errors[0].field, "preRestore"),
+        other => panic!("Expected a validation error, got {:?}", other),
+    }
+    assert_eq!(api.clone().get_backup_retention().await.pre_restore, 5);
+    assert_eq!(api.list_backups().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn backups_in_the_same_second_are_all_kept() {
+    let data_dir = testing::temp_dir();
+    let _api = testing::api(&data_dir);
+    let db_path = data_dir.join("db.sqlite");
+
+    for _ in 0..3 {
+        backup::create_backup(&db_path, &data_dir, BackupKind::PreMigration, None).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+
+    let pre_migration = backup::list_backups(&data_dir)
+        .unwrap()
+        .into_iter()
+        .filter(|backup| backup.kind == BackupKind::PreMigration)
+        .count();
+    assert_eq!(pre_migration, 3);
+}
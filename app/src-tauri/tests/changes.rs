@@ -0,0 +1,154 @@
+use achievealchemy_lib::changes::{ChangeFeed, DataChange};
+use achievealchemy_lib::models::SessionType;
+use achievealchemy_lib::repository::{
+    CompleteSessionArgs, CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs,
+    CreateSessionArgs, HabitRepository, PomodoroRepository, StartCycleArgs, UpdateHabitArgs,
+};
+use achievealchemy_lib::testing;
+use serde_json::json;
+
+fn create_args(title: &str) -> CreateHabitArgs {
+    CreateHabitArgs {
+        title: title.to_string(),
+        description: String::new(),
+        icon: "CircleCheck".to_string(),
+    }
+}
+
+fn toggle_args(id: Option<String>, habit_id: &str) -> CreateHabitCompletionArgs {
+    CreateHabitCompletionArgs {
+        id,
+        habit_id: habit_id.to_string(),
+        created_at: Some("2025-02-20".to_string()),
+    }
+}
+
+#[test]
+fn the_feed_starts_after_the_existing_history() {
+    let mut connection = testing::connection();
+    connection.create_habit(create_args("Read")).unwrap();
+
+    let mut feed = ChangeFeed::new(&mut connection).unwrap();
+
+    assert!(feed.poll(&mut connection).unwrap().is_empty());
+}
+
+#[test]
+fn habit_changes_are_read_once_in_order() {
+    let mut connection = testing::connection();
+    let mut feed = ChangeFeed::new(&mut connection).unwrap();
+
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+    connection
+        .update_habit(UpdateHabitArgs {
+            id: habit.id.clone(),
+            title: Some("Read more".to_string()),
+            description: None,
+            icon: None,
+            is_archived: None,
+        })
+        .unwrap();
+    connection.delete_habit(&habit.id).unwrap();
+
+    let changes = feed.poll(&mut connection).unwrap();
+
+    assert_eq!(changes.len(), 3);
+    assert!(matches!(&changes[0], DataChange::HabitCreated { habit: h } if h.title == "Read"));
+    assert!(matches!(&changes[1], DataChange::HabitUpdated { habit: h } if h.title == "Read more"));
+    assert!(matches!(&changes[2], DataChange::HabitDeleted { habit_id } if *habit_id == habit.id));
+    assert!(feed.poll(&mut connection).unwrap().is_empty());
+}
+
+#[test]
+fn toggling_a_completion_reports_whether_the_day_is_marked() {
+    let mut connection = testing::connection();
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+    let mut feed = ChangeFeed::new(&mut connection).unwrap();
+
+    let completion = connection
+        .toggle_habit_completion(toggle_args(None, &habit.id))
+        .unwrap()
+        .value;
+    connection
+        .toggle_habit_completion(toggle_args(Some(completion.id.clone()), &habit.id))
+        .unwrap();
+
+    let changes = feed.poll(&mut connection).unwrap();
+
+    let toggles: Vec<_> = changes
+        .iter()
+        .map(|change| match change {
+            DataChange::CompletionToggled {
+                completion,
+                completed,
+            } => (completion.id.clone(), *completed),
+            other => panic!("Expected a toggled completion, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        toggles,
+        [(completion.id.clone(), true), (completion.id, false)]
+    );
+    assert!(changes.iter().all(DataChange::affects_habits));
+}
+
+#[test]
+fn completed_sessions_are_reported() {
+    let mut connection = testing::connection();
+    let mut feed = ChangeFeed::new(&mut connection).unwrap();
+
+    let cycle = connection
+        .start_pomodoro_cycle(StartCycleArgs {
+            preset_id: None,
+            focus_duration: None,
+            short_break_duration: None,
+            long_break_duration: None,
+            sessions_until_long_break: None,
+            auto_start_breaks: None,
+            auto_start_pomodoros: None,
+        })
+        .unwrap();
+    let session = connection
+        .start_session(CreateSessionArgs {
+            cycle_id: cycle.id.clone(),
+            session_type: SessionType::Focus,
+            duration_ms: 1_500_000,
+        })
+        .unwrap();
+    connection
+        .complete_session(CompleteSessionArgs {
+            session_id: session.id.clone(),
+            was_completed: true,
+        })
+        .unwrap();
+
+    let changes = feed.poll(&mut connection).unwrap();
+
+    assert_eq!(changes.len(), 3);
+    assert!(matches!(&changes[0], DataChange::CycleStarted { cycle: c } if c.id == cycle.id));
+    assert!(matches!(&changes[1], DataChange::SessionStarted { session: s } if s.id == session.id));
+    match &changes[2] {
+        DataChange::SessionCompleted { session: s } => {
+            assert_eq!(s.id, session.id);
+            assert!(s.was_completed);
+        }
+        other => panic!("Expected a completed session, got {:?}", other),
+    }
+    assert!(!changes.iter().any(DataChange::affects_habits));
+}
+
+#[test]
+fn changes_are_tagged_with_their_event_name() {
+    let deleted = DataChange::HabitDeleted {
+        habit_id: "habit-1".to_string(),
+    };
+
+    assert_eq!(
+        serde_json::to_value(&deleted).unwrap(),
+        json!({ "type": "habit.deleted", "habitId": "habit-1" })
+    );
+    assert_eq!(
+        serde_json::to_value(DataChange::DataReloaded).unwrap(),
+        json!({ "type": "data.reloaded" })
+    );
+}
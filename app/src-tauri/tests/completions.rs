@@ -0,0 +1,196 @@
+use achievealchemy_lib::repository::{
+    CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs, GetHabitCompletionsArgs,
+    HabitRepository,
+};
+use achievealchemy_lib::settings::{self, Settings};
+use achievealchemy_lib::testing;
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use diesel::sqlite::SqliteConnection;
+
+fn create_habit(connection: &mut SqliteConnection) -> String {
+    connection
+        .create_habit(CreateHabitArgs {
+            title: "Stretch".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .unwrap()
+        .id
+}
+
+fn complete(connection: &mut SqliteConnection, habit_id: &str, date: &str) -> String {
+    connection
+        .toggle_habit_completion(CreateHabitCompletionArgs {
+            id: None,
+            habit_id: habit_id.to_string(),
+            created_at: Some(date.to_string()),
+        })
+        .unwrap()
+        .value
+        .id
+}
+
+fn completions(connection: &mut SqliteConnection, habit_id: &str) -> Vec<NaiveDate> {
+    connection
+        .habit_completions(GetHabitCompletionsArgs {
+            habit_id: habit_id.to_string(),
+            limit: None,
+        })
+        .unwrap()
+        .into_iter()
+        .map(|completion| completion.created_at)
+        .collect()
+}
+
+fn date(value: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn toggling_without_an_id_marks_the_day() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+
+    let mutation = connection
+        .toggle_habit_completion(CreateHabitCompletionArgs {
+            id: None,
+            habit_id: habit_id.clone(),
+            created_at: Some("2025-03-01".to_string()),
+        })
+        .unwrap();
+
+    assert_eq!(mutation.value.created_at, date("2025-03-01"));
+    assert_eq!(mutation.description, "Marked \"Stretch\" on 2025-03-01");
+    assert_eq!(
+        completions(&mut connection, &habit_id),
+        [date("2025-03-01")]
+    );
+}
+
+#[test]
+fn toggling_with_an_id_unmarks_the_day() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+    let completion_id = complete(&mut connection, &habit_id, "2025-03-01");
+
+    let mutation = connection
+        .toggle_habit_completion(CreateHabitCompletionArgs {
+            id: Some(completion_id),
+            habit_id: habit_id.clone(),
+            created_at: None,
+        })
+        .unwrap();
+
+    assert_eq!(mutation.description, "Unmarked \"Stretch\" on 2025-03-01");
+    assert!(completions(&mut connection, &habit_id).is_empty());
+}
+
+#[test]
+fn toggle_rejects_a_malformed_date() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+
+    let result = connection.toggle_habit_completion(CreateHabitCompletionArgs {
+        id: None,
+        habit_id,
+        created_at: Some("03/01/2025".to_string()),
+    });
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn completions_are_newest_first_and_limited() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+    for day in ["2025-03-01", "2025-03-03", "2025-03-02"] {
+        complete(&mut connection, &habit_id, day);
+    }
+
+    let latest = connection
+        .habit_completions(GetHabitCompletionsArgs {
+            habit_id: habit_id.clone(),
+            limit: Some(2),
+        })
+        .unwrap();
+
+    let dates: Vec<NaiveDate> = latest.iter().map(|c| c.created_at).collect();
+    assert_eq!(dates, [date("2025-03-03"), date("2025-03-02")]);
+}
+
+#[test]
+fn completions_on_a_day_cover_every_habit() {
+    let mut connection = testing::connection();
+    let first = create_habit(&mut connection);
+    let second = create_habit(&mut connection);
+    complete(&mut connection, &first, "2025-03-01");
+    complete(&mut connection, &first, "2025-03-02");
+    complete(&mut connection, &second, "2025-03-02");
+
+    let mut habit_ids: Vec<String> = connection
+        .completions_on(date("2025-03-02"))
+        .unwrap()
+        .into_iter()
+        .map(|completion| completion.habit_id)
+        .collect();
+    habit_ids.sort();
+
+    let mut expected = vec![first, second];
+    expected.sort();
+    assert_eq!(habit_ids, expected);
+}
+
+#[test]
+fn streak_counts_consecutive_days_up_to_today() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+    for day in ["2025-03-01", "2025-03-03", "2025-03-04", "2025-03-05"] {
+        complete(&mut connection, &habit_id, day);
+    }
+
+    let streak = |connection: &mut SqliteConnection, today: &str| {
+        connection
+            .habit_completion_streak(&habit_id, date(today))
+            .unwrap()
+    };
+
+    assert_eq!(streak(&mut connection, "2025-03-05"), 3);
+    // Not done yet today, the streak up to yesterday still counts
+    assert_eq!(streak(&mut connection, "2025-03-06"), 3);
+    assert_eq!(streak(&mut connection, "2025-03-07"), 0);
+}
+
+#[test]
+fn a_day_without_a_date_is_today_in_the_configured_timezone() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+    // UTC+14 is always a day or more ahead of UTC-12, so no single day fits both
+    for timezone in ["Pacific/Kiritimati", "Etc/GMT+12"] {
+        let new_settings = Settings {
+            timezone: Some(timezone.to_string()),
+            ..settings::load(&mut connection).unwrap()
+        };
+        settings::save(&mut connection, &new_settings).unwrap();
+
+        let completion = connection
+            .toggle_habit_completion(CreateHabitCompletionArgs {
+                id: None,
+                habit_id: habit_id.clone(),
+                created_at: None,
+            })
+            .unwrap()
+            .value;
+        let today = Utc::now()
+            .with_timezone(&timezone.parse::<Tz>().unwrap())
+            .date_naive();
+
+        assert_eq!(completion.created_at, today);
+        assert_eq!(
+            connection
+                .habit_completion_streak(&habit_id, today)
+                .unwrap(),
+            1
+        );
+    }
+}
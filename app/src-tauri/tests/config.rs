@@ -0,0 +1,74 @@
+use std::fs;
+
+use achievealchemy_lib::config::{
+    Config, ConfigValues, Environment, CONFIG_FILE_NAME, PRODUCTION_LICENSE_API_URL,
+    TEST_LICENSE_API_URL,
+};
+use achievealchemy_lib::testing;
+
+#[test]
+fn production_has_no_test_credentials() {
+    let production = Config::resolve(ConfigValues {
+        environment: Some(Environment::Production),
+        ..Default::default()
+    });
+
+    assert_eq!(production.license.api_url, PRODUCTION_LICENSE_API_URL);
+    assert_ne!(production.license.api_url, TEST_LICENSE_API_URL);
+    // Not even the test key, in any profile
+    assert_eq!(production.license.api_key, None);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn debug_builds_default_to_the_test_api() {
+    let config = Config::resolve(ConfigValues::default());
+
+    assert_eq!(config.environment, Environment::Test);
+    assert_eq!(config.license.api_url, TEST_LICENSE_API_URL);
+    assert!(config.license.api_key.is_some());
+}
+
+#[test]
+fn configured_values_override_the_defaults() {
+    let config = Config::resolve(ConfigValues {
+        environment: Some(Environment::Test),
+        license_api_url: Some("http://localhost:8080".to_string()),
+        license_api_key: Some("local-key".to_string()),
+    });
+
+    assert_eq!(config.license.api_url, "http://localhost:8080");
+    assert_eq!(config.license.api_key.as_deref(), Some("local-key"));
+}
+
+#[test]
+fn the_config_file_is_read_from_the_data_directory() {
+    let dir = testing::temp_dir();
+    assert_eq!(ConfigValues::read(&dir).unwrap(), ConfigValues::default());
+
+    fs::write(
+        dir.join(CONFIG_FILE_NAME),
+        r#"{ "environment": "production", "licenseApiKey": "live-key" }"#,
+    )
+    .unwrap();
+    let config = Config::load(Some(&dir)).unwrap();
+
+    assert_eq!(config.environment, Environment::Production);
+    assert_eq!(config.license.api_url, PRODUCTION_LICENSE_API_URL);
+    assert_eq!(config.license.api_key.as_deref(), Some("live-key"));
+}
+
+#[test]
+fn an_invalid_config_file_is_an_error() {
+    let dir = testing::temp_dir();
+
+    fs::write(
+        dir.join(CONFIG_FILE_NAME),
+        r#"{ "environment": "staging" }"#,
+    )
+    .unwrap();
+    assert!(Config::load(Some(&dir)).is_err());
+
+    fs::write(dir.join(CONFIG_FILE_NAME), r#"{ "licenseKey": "KEY-123" }"#).unwrap();
+    assert!(Config::load(Some(&dir)).is_err());
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::Path;
+
+use achievealchemy_lib::backup;
+use achievealchemy_lib::repository::CreateHabitArgs;
+use achievealchemy_lib::testing;
+use achievealchemy_lib::{Api, ApiImpl};
+
+const PASSPHRASE: &str = "correct horse battery";
+/// Every unencrypted SQLite file starts with this.
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+async fn create_habit(api: &ApiImpl, title: &str) {
+    api.clone()
+        .create_habit(CreateHabitArgs {
+            title: title.to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .await
+        .unwrap();
+}
+
+fn is_plaintext(path: &Path) -> bool {
+    fs::read(path).unwrap().starts_with(SQLITE_HEADER)
+}
+
+#[tokio::test]
+async fn an_encrypted_database_opens_locked_until_unlocked() {
+    let data_dir = testing::temp_dir();
+    let api = testing::api(&data_dir);
+    create_habit(&api, "Read").await;
+
+    api.clone()
+        .enable_encryption(PASSPHRASE.to_string())
+        .await
+        .unwrap();
+    let status = api.get_encryption_status().await;
+    assert!(status.is_encrypted);
+    assert!(status.is_unlocked);
+
+    // Like the next start of the app
+    let api = testing::api(&data_dir);
+    let status = api.clone().get_encryption_status().await;
+    assert!(status.is_encrypted);
+    assert!(!status.is_unlocked);
+
+    api.clone()
+        .unlock_database(PASSPHRASE.to_string())
+        .await
+        .unwrap();
+
+    assert!(api.clone().get_encryption_status().await.is_unlocked);
+    let habits = api.get_active_habits().await.unwrap();
+    assert_eq!(habits.len(), 1);
+    assert_eq!(habits[0].title, "Read");
+}
+
+#[tokio::test]
+async fn a_wrong_passphrase_keeps_the_database_locked() {
+    let data_dir = testing::temp_dir();
+    testing::api(&data_dir)
+        .enable_encryption(PASSPHRASE.to_string())
+        .await
+        .unwrap();
+    let api = testing::api(&data_dir);
+
+    let result = api
+        .clone()
+        .unlock_database("wrong horse battery".to_string())
+        .await;
+
+    assert_eq!(result.unwrap_err().to_string(), "Incorrect passphrase");
+    assert!(!api.clone().get_encryption_status().await.is_unlocked);
+    assert_eq!(
+        api.get_active_habits().await.unwrap_err().to_string(),
+        "Database is locked"
+    );
+}
+
+#[tokio::test]
+async fn the_database_and_its_backups_are_encrypted() {
+    let data_dir = testing::temp_dir();
+    let api = testing::api(&data_dir);
+    create_habit(&api, "Read").await;
+    let daily = api.clone().list_backups().await.unwrap().remove(0);
+    let backup_path = backup::backup_dir(&data_dir).join(&daily.file_name);
+    assert!(is_plaintext(&backup_path));
+
+    api.clone()
+        .enable_encryption(PASSPHRASE.to_string())
+        .await
+        .unwrap();
+
+    assert!(!is_plaintext(&data_dir.join("db.sqlite")));
+    assert!(!is_plaintext(&backup_path));
+
+    // Restoring opens the snapshot with the same key
+    api.clone().restore_backup(daily.file_name).await.unwrap();
+    assert!(api.get_active_habits().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn encryption_needs_a_long_passphrase_and_is_enabled_once() {
+    let api = testing::api(&testing::temp_dir());
+
+    assert!(api
+        .clone()
+        .enable_encryption("short".to_string())
+        .await
+        .is_err());
+    assert!(!api.clone().get_encryption_status().await.is_encrypted);
+
+    api.clone()
+        .enable_encryption(PASSPHRASE.to_string())
+        .await
+        .unwrap();
+    assert!(api
+        .clone()
+        .enable_encryption(PASSPHRASE.to_string())
+        .await
+        .is_err());
+
+    // Still usable with the first key
+    create_habit(&api, "Read").await;
+}
@@ -0,0 +1,201 @@
+use achievealchemy_lib::entitlements::{self, Entitlements, Feature, FREE_HABIT_LIMIT};
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::license::{self, ActivateLicenseKeyArgs, LicenseClient, LicenseState};
+use achievealchemy_lib::repository::{CreateHabitArgs, HabitRepository, UpdateHabitArgs};
+use achievealchemy_lib::testing;
+use achievealchemy_lib::undo::UndoStack;
+use achievealchemy_lib::Api;
+use diesel::sqlite::SqliteConnection;
+use serde_json::json;
+
+fn create_habits(connection: &mut SqliteConnection, count: i32) {
+    for i in 0..count {
+        connection
+            .create_habit(CreateHabitArgs {
+                title: format!("Habit {}", i),
+                description: String::new(),
+                icon: "CircleCheck".to_string(),
+            })
+            .unwrap();
+    }
+}
+
+async fn activate(connection: &mut SqliteConnection) {
+    let body = json!({
+        "id": "lic_1",
+        "status": "active",
+        "key": "KEY-123",
+        "activation": 1,
+        "activation_limit": 3,
+        "expires_at": null,
+        "instance": { "id": "ins_42", "name": "Laptop", "status": "active" },
+    });
+    let (url, _requests) = testing::http_stub(vec![(200, body.to_string())]);
+
+    let value = license::activate(
+        &LicenseClient::new(url, Some("secret-key".to_string())),
+        ActivateLicenseKeyArgs {
+            license_key: "KEY-123".to_string(),
+            instance_name: "Laptop".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    license::store(connection, &value).unwrap();
+}
+
+fn required_feature(result: Result<(), AppError>) -> Feature {
+    match result {
+        Err(AppError::LicenseRequired { feature, .. }) => feature,
+        other => panic!("Expected a license required error, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_usable_license_unlocks_every_feature() {
+    for state in [LicenseState::Valid, LicenseState::Grace] {
+        let entitlements = Entitlements::for_state(state);
+
+        assert_eq!(entitlements.features, Feature::ALL.to_vec());
+        assert_eq!(entitlements.habit_limit, None);
+    }
+}
+
+#[test]
+fn without_a_license_only_a_few_habits_are_allowed() {
+    for state in [LicenseState::Expired, LicenseState::Invalid] {
+        let entitlements = Entitlements::for_state(state);
+
+        assert!(entitlements.features.is_empty());
+        assert_eq!(entitlements.habit_limit, Some(FREE_HABIT_LIMIT));
+        assert_eq!(
+            required_feature(entitlements.require(Feature::Export)),
+            Feature::Export
+        );
+    }
+}
+
+#[test]
+fn habits_past_the_free_limit_need_a_license() {
+    let mut connection = testing::connection();
+    create_habits(&mut connection, FREE_HABIT_LIMIT - 1);
+
+    assert!(entitlements::require_habit_slots(&mut connection, 1).is_ok());
+    assert_eq!(
+        required_feature(entitlements::require_habit_slots(&mut connection, 2)),
+        Feature::UnlimitedHabits
+    );
+}
+
+#[tokio::test]
+async fn an_activated_license_lifts_the_limits() {
+    let mut connection = testing::connection();
+    create_habits(&mut connection, FREE_HABIT_LIMIT);
+    assert!(entitlements::require(&mut connection, Feature::Sync).is_err());
+
+    activate(&mut connection).await;
+
+    assert!(entitlements::require(&mut connection, Feature::Sync).is_ok());
+    assert!(entitlements::require_habit_slots(&mut connection, 10).is_ok());
+    assert_eq!(
+        entitlements::load(&mut connection).unwrap().license_state,
+        LicenseState::Valid
+    );
+}
+
+#[tokio::test]
+async fn unarchiving_a_habit_needs_a_free_slot() {
+    let api = testing::api(&testing::temp_dir());
+    for i in 0..FREE_HABIT_LIMIT {
+        api.clone()
+            .create_habit(CreateHabitArgs {
+                title: format!("Habit {}", i),
+                description: String::new(),
+                icon: "CircleCheck".to_string(),
+            })
+            .await
+            .unwrap();
+    }
+    let habits = api.clone().get_active_habits().await.unwrap();
+    let update = |id: &str, is_archived: bool| UpdateHabitArgs {
+        id: id.to_string(),
+        title: None,
+        description: None,
+        icon: None,
+        is_archived: Some(is_archived),
+    };
+
+    api.clone()
+        .update_habit(update(&habits[0].id, true))
+        .await
+        .unwrap();
+    api.clone()
+        .create_habit(CreateHabitArgs {
+            title: "Another".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        required_feature(api.clone().update_habit(update(&habits[0].id, false)).await),
+        Feature::UnlimitedHabits
+    );
+    // Habits that are already active keep working
+    assert!(api
+        .clone()
+        .update_habit(update(&habits[1].id, false))
+        .await
+        .is_ok());
+}
+
+#[test]
+fn undo_and_redo_need_a_free_slot_to_bring_a_habit_back() {
+    let mut connection = testing::connection();
+    let mut undo_stack = UndoStack::default();
+    let update = |id: &str, is_archived: bool| UpdateHabitArgs {
+        id: id.to_string(),
+        title: None,
+        description: None,
+        icon: None,
+        is_archived: Some(is_archived),
+    };
+    create_habits(&mut connection, FREE_HABIT_LIMIT);
+    let habit = connection.active_habits().unwrap().remove(0);
+    connection.update_habit(update(&habit.id, true)).unwrap();
+
+    // Redoing an unarchive after the slot was taken
+    let unarchived = connection.update_habit(update(&habit.id, false)).unwrap();
+    undo_stack.push(unarchived.description, vec![unarchived.event]);
+    undo_stack.undo(&mut connection).unwrap();
+    create_habits(&mut connection, 1);
+    assert_eq!(
+        required_feature(undo_stack.redo(&mut connection).map(|_| ())),
+        Feature::UnlimitedHabits
+    );
+
+    // Undoing a delete after the slot was taken
+    let active = connection.active_habits().unwrap();
+    let deleted = connection.delete_habit(&active[0].id).unwrap();
+    undo_stack.push(deleted.description, vec![deleted.event]);
+    create_habits(&mut connection, 1);
+    assert_eq!(
+        required_feature(undo_stack.undo(&mut connection).map(|_| ())),
+        Feature::UnlimitedHabits
+    );
+
+    assert_eq!(
+        connection.active_habits().unwrap().len(),
+        FREE_HABIT_LIMIT as usize
+    );
+}
+
+#[tokio::test]
+async fn daily_stats_are_free() {
+    let api = testing::api(&testing::temp_dir());
+
+    let result = api.get_daily_stats("2025-03-01".to_string()).await;
+
+    assert_eq!(result.unwrap(), 0);
+}
@@ -0,0 +1,127 @@
+use std::fs;
+
+use achievealchemy_lib::export::{self, DateRange};
+use achievealchemy_lib::import::{self, ImportSource};
+use achievealchemy_lib::repository::{
+    CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs, GetHabitCompletionsArgs,
+    HabitRepository,
+};
+use achievealchemy_lib::testing;
+use achievealchemy_lib::{Api, ImportArgs};
+
+const STREAKS_CSV: &str = "\
+title,entry_date,entry_type
+Walk,2025-03-01,completed_manually
+Walk,2025-03-02,completed_manually
+Walk,2025-03-03,missed
+Floss,2025-03-02,completed_manually
+";
+
+#[test]
+fn export_writes_completions_in_the_range() {
+    let mut connection = testing::connection();
+    let habit = connection
+        .create_habit(CreateHabitArgs {
+            title: "Walk".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .unwrap();
+    for day in ["2025-02-28", "2025-03-01", "2025-03-02"] {
+        connection
+            .toggle_habit_completion(CreateHabitCompletionArgs {
+                id: None,
+                habit_id: habit.id.clone(),
+                created_at: Some(day.to_string()),
+            })
+            .unwrap();
+    }
+    let path = testing::temp_dir().join("completions.csv");
+
+    let range = DateRange::parse("2025-03-01", "2025-03-31").unwrap();
+    let written = export::export_habit_completions(&mut connection, &range, &path).unwrap();
+
+    assert_eq!(written, 2);
+    let csv = fs::read_to_string(&path).unwrap();
+    assert!(csv.starts_with("completion_id,habit_id,habit_title,date\n"));
+    assert!(csv.contains("Walk,2025-03-01"));
+    assert!(!csv.contains("2025-02-28"));
+}
+
+#[test]
+fn export_writes_no_sessions_for_an_empty_range() {
+    let mut connection = testing::connection();
+    let path = testing::temp_dir().join("sessions.csv");
+
+    let range = DateRange::parse("2025-03-01", "2025-03-01").unwrap();
+    let written = export::export_pomodoro_sessions(&mut connection, &range, &path).unwrap();
+
+    assert_eq!(written, 0);
+    assert!(DateRange::parse("2025-03-02", "2025-03-01").is_err());
+}
+
+#[test]
+fn importing_twice_creates_nothing_the_second_time() {
+    let mut connection = testing::connection();
+    let path = testing::temp_dir().join("streaks.csv");
+    fs::write(&path, STREAKS_CSV).unwrap();
+
+    let read = || {
+        import::importer_for(ImportSource::Streaks)
+            .read(&path)
+            .unwrap()
+    };
+
+    let plan = import::plan_import(&mut connection, ImportSource::Streaks, read()).unwrap();
+    assert_eq!(plan.report.habits_to_create, 2);
+    assert_eq!(plan.report.completions_to_create, 3);
+    import::commit_import(&mut connection, &plan).unwrap();
+
+    let walk = connection
+        .active_habits()
+        .unwrap()
+        .into_iter()
+        .find(|habit| habit.title == "Walk")
+        .unwrap();
+    let completions = connection
+        .habit_completions(GetHabitCompletionsArgs {
+            habit_id: walk.id,
+            limit: None,
+        })
+        .unwrap();
+    assert_eq!(completions.len(), 2);
+
+    let plan = import::plan_import(&mut connection, ImportSource::Streaks, read()).unwrap();
+    assert_eq!(plan.report.habits_to_create, 0);
+    assert_eq!(plan.report.habits_merged, 2);
+    assert_eq!(plan.report.completions_to_create, 0);
+}
+
+#[tokio::test]
+async fn import_habits_commits_the_plan() {
+    let api = testing::api(&testing::temp_dir());
+    let path = testing::temp_dir().join("streaks.csv");
+    fs::write(&path, STREAKS_CSV).unwrap();
+    let args = || ImportArgs {
+        source: ImportSource::Streaks,
+        path: path.to_str().unwrap().to_string(),
+    };
+
+    let preview = api.clone().preview_import(args()).await.unwrap();
+    assert_eq!(preview.habits_to_create, 2);
+    assert!(api.clone().get_active_habits().await.unwrap().is_empty());
+
+    let report = api.clone().import_habits(args()).await.unwrap();
+
+    assert_eq!(report.habits_to_create, 2);
+    assert_eq!(report.completions_to_create, 3);
+    let mut titles: Vec<_> = api
+        .get_active_habits()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|habit| habit.title)
+        .collect();
+    titles.sort();
+    assert_eq!(titles, ["Floss", "Walk"]);
+}
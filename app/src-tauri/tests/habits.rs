@@ -0,0 +1,100 @@
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::repository::{CreateHabitArgs, HabitRepository, UpdateHabitArgs};
+use achievealchemy_lib::testing;
+
+fn create_args(title: &str) -> CreateHabitArgs {
+    CreateHabitArgs {
+        title: title.to_string(),
+        description: String::new(),
+        icon: "CircleCheck".to_string(),
+    }
+}
+
+fn update_args(id: &str) -> UpdateHabitArgs {
+    UpdateHabitArgs {
+        id: id.to_string(),
+        title: None,
+        description: None,
+        icon: None,
+        is_archived: None,
+    }
+}
+
+#[test]
+fn created_habits_are_listed_as_active() {
+    let mut connection = testing::connection();
+
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+
+    let active = connection.active_habits().unwrap();
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].id, habit.id);
+    assert_eq!(active[0].title, "Read");
+    assert!(connection.archived_habits().unwrap().is_empty());
+}
+
+#[test]
+fn create_habit_rejects_an_empty_title() {
+    let mut connection = testing::connection();
+
+    let error = connection.create_habit(create_args("  ")).unwrap_err();
+
+    assert!(matches!(error, AppError::Validation { .. }));
+    assert!(connection.active_habits().unwrap().is_empty());
+}
+
+#[test]
+fn archiving_moves_a_habit_to_the_archive() {
+    let mut connection = testing::connection();
+    let habit = connection.create_habit(create_args("Run")).unwrap();
+
+    let mutation = connection
+        .update_habit(UpdateHabitArgs {
+            is_archived: Some(true),
+            ..update_args(&habit.id)
+        })
+        .unwrap();
+
+    assert!(mutation.value.is_archived);
+    assert_eq!(mutation.description, "Archived \"Run\"");
+    assert!(connection.active_habits().unwrap().is_empty());
+    assert_eq!(connection.archived_habits().unwrap().len(), 1);
+}
+
+#[test]
+fn update_habit_only_changes_given_fields() {
+    let mut connection = testing::connection();
+    let habit = connection.create_habit(create_args("Run")).unwrap();
+
+    let mutation = connection
+        .update_habit(UpdateHabitArgs {
+            title: Some("Run 5k".to_string()),
+            ..update_args(&habit.id)
+        })
+        .unwrap();
+
+    assert_eq!(mutation.value.title, "Run 5k");
+    assert_eq!(mutation.value.icon, habit.icon);
+    assert_eq!(mutation.value.created_at, habit.created_at);
+    assert_eq!(mutation.description, "Edited \"Run 5k\"");
+}
+
+#[test]
+fn update_habit_fails_for_an_unknown_habit() {
+    let mut connection = testing::connection();
+
+    assert!(connection.update_habit(update_args("missing")).is_err());
+}
+
+#[test]
+fn delete_habit_returns_the_deleted_habit() {
+    let mut connection = testing::connection();
+    let habit = connection.create_habit(create_args("Meditate")).unwrap();
+
+    let mutation = connection.delete_habit(&habit.id).unwrap();
+
+    assert_eq!(mutation.value.id, habit.id);
+    assert_eq!(mutation.event.operation, "DELETE");
+    assert!(connection.active_habits().unwrap().is_empty());
+    assert!(connection.delete_habit(&habit.id).is_err());
+}
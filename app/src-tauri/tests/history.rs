@@ -0,0 +1,178 @@
+use achievealchemy_lib::events;
+use achievealchemy_lib::repository::{
+    CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs, GetHabitCompletionsArgs,
+    HabitRepository, UpdateHabitArgs,
+};
+use achievealchemy_lib::testing;
+use achievealchemy_lib::undo::UndoStack;
+
+fn create_args(title: &str) -> CreateHabitArgs {
+    CreateHabitArgs {
+        title: title.to_string(),
+        description: String::new(),
+        icon: "CircleCheck".to_string(),
+    }
+}
+
+#[test]
+fn every_mutation_is_recorded_in_the_history() {
+    let mut connection = testing::connection();
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+    connection
+        .update_habit(UpdateHabitArgs {
+            id: habit.id.clone(),
+            title: Some("Read more".to_string()),
+            description: None,
+            icon: None,
+            is_archived: None,
+        })
+        .unwrap();
+    connection.delete_habit(&habit.id).unwrap();
+
+    let operations: Vec<String> = events::history(&mut connection, &habit.id)
+        .unwrap()
+        .into_iter()
+        .map(|event| event.operation)
+        .collect();
+
+    assert_eq!(operations.len(), 3);
+    assert!(operations.contains(&"CREATE".to_string()));
+    assert!(operations.contains(&"UPDATE".to_string()));
+    assert!(operations.contains(&"DELETE".to_string()));
+}
+
+#[test]
+fn undo_and_redo_an_update() {
+    let mut connection = testing::connection();
+    let mut undo_stack = UndoStack::default();
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+
+    let mutation = connection
+        .update_habit(UpdateHabitArgs {
+            id: habit.id.clone(),
+            title: None,
+            description: None,
+            icon: None,
+            is_archived: Some(true),
+        })
+        .unwrap();
+    undo_stack.push(mutation.description, vec![mutation.event]);
+
+    assert_eq!(
+        undo_stack.undo(&mut connection).unwrap().as_deref(),
+        Some("Archived \"Read\"")
+    );
+    assert_eq!(connection.active_habits().unwrap().len(), 1);
+
+    assert_eq!(
+        undo_stack.redo(&mut connection).unwrap().as_deref(),
+        Some("Archived \"Read\"")
+    );
+    assert!(connection.active_habits().unwrap().is_empty());
+}
+
+#[test]
+fn undo_restores_a_deleted_habit() {
+    let mut connection = testing::connection();
+    let mut undo_stack = UndoStack::default();
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+
+    let mutation = connection.delete_habit(&habit.id).unwrap();
+    undo_stack.push(mutation.description, vec![mutation.event]);
+    undo_stack.undo(&mut connection).unwrap();
+
+    let restored = connection.active_habits().unwrap();
+    assert_eq!(restored.len(), 1);
+    assert_eq!(restored[0].id, habit.id);
+    assert_eq!(restored[0].created_at, habit.created_at);
+}
+
+#[test]
+fn undo_removes_a_completion() {
+    let mut connection = testing::connection();
+    let mut undo_stack = UndoStack::default();
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+
+    let mutation = connection
+        .toggle_habit_completion(CreateHabitCompletionArgs {
+            id: None,
+            habit_id: habit.id.clone(),
+            created_at: Some("2025-03-01".to_string()),
+        })
+        .unwrap();
+    undo_stack.push(mutation.description, vec![mutation.event]);
+    undo_stack.undo(&mut connection).unwrap();
+
+    let completions = connection
+        .habit_completions(GetHabitCompletionsArgs {
+            habit_id: habit.id,
+            limit: None,
+        })
+        .unwrap();
+    assert!(completions.is_empty());
+}
+
+#[test]
+fn a_new_action_clears_what_could_be_redone() {
+    let mut connection = testing::connection();
+    let mut undo_stack = UndoStack::default();
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+
+    for title in ["Read more", "Read less"] {
+        let mutation = connection
+            .update_habit(UpdateHabitArgs {
+                id: habit.id.clone(),
+                title: Some(title.to_string()),
+                description: None,
+                icon: None,
+                is_archived: None,
+            })
+            .unwrap();
+        undo_stack.push(mutation.description, vec![mutation.event]);
+        if title == "Read more" {
+            undo_stack.undo(&mut connection).unwrap();
+        }
+    }
+
+    assert_eq!(undo_stack.redo(&mut connection).unwrap(), None);
+    assert_eq!(
+        undo_stack.undo(&mut connection).unwrap().as_deref(),
+        Some("Edited \"Read less\"")
+    );
+    assert_eq!(undo_stack.undo(&mut connection).unwrap(), None);
+    assert_eq!(connection.active_habits().unwrap()[0].title, "Read");
+}
+
+#[test]
+fn undo_refuses_to_overwrite_a_later_change() {
+    let mut connection = testing::connection();
+    let mut undo_stack = UndoStack::default();
+    let habit = connection.create_habit(create_args("Read")).unwrap();
+
+    let mutation = connection
+        .update_habit(UpdateHabitArgs {
+            id: habit.id.clone(),
+            title: Some("Read more".to_string()),
+            description: None,
+            icon: None,
+            is_archived: None,
+        })
+        .unwrap();
+    undo_stack.push(mutation.description, vec![mutation.event]);
+
+    // Changed elsewhere, e.g. with the CLI, so not on this undo stack
+    connection
+        .update_habit(UpdateHabitArgs {
+            id: habit.id.clone(),
+            title: Some("Read daily".to_string()),
+            description: None,
+            icon: None,
+            is_archived: None,
+        })
+        .unwrap();
+
+    let error = undo_stack.undo(&mut connection).unwrap_err().to_string();
+
+    assert!(error.contains("changed since"), "{}", error);
+    assert_eq!(connection.active_habits().unwrap()[0].title, "Read daily");
+}
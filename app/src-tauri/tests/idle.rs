@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::idle::{
+    self, IdleEvent, Interruption, InterruptionResolution, ResolveInterruptionArgs,
+};
+use achievealchemy_lib::models::{PomodoroSession, SessionType};
+use achievealchemy_lib::repository::{
+    CompleteSessionArgs, CreateSessionArgs, PomodoroRepository, StartCycleArgs,
+};
+use achievealchemy_lib::settings::{self, IdleSettings, Settings};
+use achievealchemy_lib::testing::{self, FakeIdleSource};
+use achievealchemy_lib::timer::TimerState;
+use chrono::Local;
+use diesel::sqlite::SqliteConnection;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+fn focusing() -> TimerState {
+    TimerState {
+        mode: SessionType::Focus,
+        time_left: 600_000,
+        is_running: true,
+        is_paused: false,
+    }
+}
+
+fn start_focus(connection: &mut SqliteConnection) -> PomodoroSession {
+    let cycle = connection
+        .start_pomodoro_cycle(StartCycleArgs {
+            preset_id: None,
+            focus_duration: None,
+            short_break_duration: None,
+            long_break_duration: None,
+            sessions_until_long_break: None,
+            auto_start_breaks: None,
+            auto_start_pomodoros: None,
+        })
+        .unwrap();
+    connection
+        .start_session(CreateSessionArgs {
+            cycle_id: cycle.id,
+            session_type: SessionType::Focus,
+            duration_ms: 1_500_000,
+        })
+        .unwrap()
+}
+
+/// Goes idle for six minutes, ten minutes into `session`.
+fn go_away(
+    connection: &mut SqliteConnection,
+    source: &FakeIdleSource,
+    session: &PomodoroSession,
+) -> Option<IdleEvent> {
+    source.set(6 * MINUTE);
+    let now = session.started_at + chrono::Duration::minutes(10);
+
+    idle::check(connection, source, Some(&focusing()), now).unwrap()
+}
+
+fn away_and_back(
+    connection: &mut SqliteConnection,
+    source: &FakeIdleSource,
+    session: &PomodoroSession,
+) -> Interruption {
+    go_away(connection, source, session);
+
+    source.set(Duration::ZERO);
+    let now = session.started_at + chrono::Duration::minutes(12);
+    match idle::check(connection, source, None, now).unwrap() {
+        Some(IdleEvent::Returned(interruption)) => interruption,
+        other => panic!("Expected the user to be back, got {:?}", other),
+    }
+}
+
+#[test]
+fn going_idle_during_focus_pauses_the_session() {
+    let mut connection = testing::connection();
+    let source = FakeIdleSource::default();
+    let session = start_focus(&mut connection);
+
+    let interruption = match go_away(&mut connection, &source, &session) {
+        Some(IdleEvent::Paused(interruption)) => interruption,
+        other => panic!("Expected a pause, got {:?}", other),
+    };
+
+    assert_eq!(interruption.session_id, session.id);
+    assert_eq!(
+        interruption.started_at,
+        session.started_at + chrono::Duration::minutes(4)
+    );
+    assert_eq!(interruption.counted_ms, 360_000);
+    assert!(interruption.ended_at.is_none());
+
+    // Still away, and the timer is paused by now
+    let now = session.started_at + chrono::Duration::minutes(11);
+    source.set(7 * MINUTE);
+    assert!(idle::check(&mut connection, &source, None, now)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn the_gap_starts_no_earlier_than_the_session() {
+    let mut connection = testing::connection();
+    let source = FakeIdleSource::default();
+    let session = start_focus(&mut connection);
+
+    source.set(30 * MINUTE);
+    let now = session.started_at + chrono::Duration::minutes(10);
+    let event = idle::check(&mut connection, &source, Some(&focusing()), now).unwrap();
+
+    match event {
+        Some(IdleEvent::Paused(interruption)) => {
+            assert_eq!(interruption.started_at, session.started_at);
+            assert_eq!(interruption.counted_ms, 600_000);
+        }
+        other => panic!("Expected a pause, got {:?}", other),
+    }
+}
+
+#[test]
+fn short_gaps_breaks_and_disabled_detection_are_ignored() {
+    let mut connection = testing::connection();
+    let source = FakeIdleSource::default();
+    let session = start_focus(&mut connection);
+    let now = session.started_at + chrono::Duration::minutes(10);
+
+    source.set(4 * MINUTE);
+    assert!(
+        idle::check(&mut connection, &source, Some(&focusing()), now)
+            .unwrap()
+            .is_none()
+    );
+
+    source.set(6 * MINUTE);
+    let short_break = TimerState {
+        mode: SessionType::ShortBreak,
+        ..focusing()
+    };
+    let paused = TimerState {
+        is_paused: true,
+        ..focusing()
+    };
+    for timer in [None, Some(&short_break), Some(&paused)] {
+        assert!(idle::check(&mut connection, &source, timer, now)
+            .unwrap()
+            .is_none());
+    }
+
+    let disabled = Settings {
+        idle: IdleSettings {
+            enabled: false,
+            ..IdleSettings::default()
+        },
+        ..Settings::default()
+    };
+    settings::save(&mut connection, &disabled).unwrap();
+    assert!(
+        idle::check(&mut connection, &source, Some(&focusing()), now)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn coming_back_ends_the_interruption() {
+    let mut connection = testing::connection();
+    let source = FakeIdleSource::default();
+    let session = start_focus(&mut connection);
+
+    let interruption = away_and_back(&mut connection, &source, &session);
+
+    assert_eq!(
+        interruption.ended_at,
+        Some(session.started_at + chrono::Duration::minutes(12))
+    );
+    assert!(interruption.resolution.is_none());
+    let unresolved = idle::unresolved_interruptions(&mut connection).unwrap();
+    assert_eq!(unresolved.len(), 1);
+    assert_eq!(unresolved[0].id, interruption.id);
+}
+
+#[test]
+fn discarded_idle_time_is_left_out_of_daily_stats() {
+    let mut connection = testing::connection();
+    let source = FakeIdleSource::default();
+    let today = Local::now().format("%Y-%m-%d").to_string();
+
+    let kept_session = start_focus(&mut connection);
+    let kept = away_and_back(&mut connection, &source, &kept_session);
+    connection
+        .complete_session(CompleteSessionArgs {
+            session_id: kept_session.id.clone(),
+            was_completed: true,
+        })
+        .unwrap();
+    idle::resolve_interruption(
+        &mut connection,
+        ResolveInterruptionArgs {
+            id: kept.id,
+            resolution: InterruptionResolution::Keep,
+        },
+    )
+    .unwrap();
+    assert_eq!(connection.daily_stats(&today).unwrap(), 25);
+
+    let discarded_session = start_focus(&mut connection);
+    let discarded = away_and_back(&mut connection, &source, &discarded_session);
+    connection
+        .complete_session(CompleteSessionArgs {
+            session_id: discarded_session.id.clone(),
+            was_completed: true,
+        })
+        .unwrap();
+    let resolved = idle::resolve_interruption(
+        &mut connection,
+        ResolveInterruptionArgs {
+            id: discarded.id,
+            resolution: InterruptionResolution::Discard,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(resolved.resolution, Some(InterruptionResolution::Discard));
+    assert_eq!(connection.daily_stats(&today).unwrap(), 44);
+    assert!(idle::unresolved_interruptions(&mut connection)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn resolving_an_unknown_interruption_fails() {
+    let mut connection = testing::connection();
+
+    let result = idle::resolve_interruption(
+        &mut connection,
+        ResolveInterruptionArgs {
+            id: "missing".to_string(),
+            resolution: InterruptionResolution::Keep,
+        },
+    );
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn the_threshold_must_be_between_a_minute_and_an_hour() {
+    let mut connection = testing::connection();
+
+    for threshold_ms in [1_000, 2 * 60 * 60 * 1000] {
+        let new_settings = Settings {
+            idle: IdleSettings {
+                enabled: true,
+                threshold_ms,
+            },
+            ..Settings::default()
+        };
+
+        match settings::save(&mut connection, &new_settings) {
+            Err(AppError::Validation { errors }) => {
+                assert_eq!(errors[0].field, "idle.thresholdMs");
+            }
+            other => panic!("Expected a validation error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::net::TcpListener;
+
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::license::{
+    self, ActivateLicenseKeyArgs, License, LicenseClient, LicenseErrorReason, LicenseState,
+    LicenseStatus,
+};
+use achievealchemy_lib::testing;
+use achievealchemy_lib::Api;
+use chrono::{DateTime, Duration, Utc};
+use diesel::sqlite::SqliteConnection;
+use diesel::RunQueryDsl;
+use serde_json::{json, Value};
+
+fn license_body(status: &str) -> String {
+    json!({
+        "id": "lic_1",
+        "mode": "test",
+        "object": "license",
+        "status": status,
+        "key": "KEY-123",
+        "activation": 1,
+        "activation_limit": 3,
+        "expires_at": "2099-03-01T00:00:00.000Z",
+        "created_at": "2025-03-01T00:00:00.000Z",
+        "instance": {
+            "id": "ins_42",
+            "mode": "test",
+            "object": "license-instance",
+            "name": "Laptop",
+            "status": "active",
+            "created_at": "2025-03-01T00:00:00.000Z",
+        },
+    })
+    .to_string()
+}
+
+fn activate_args() -> ActivateLicenseKeyArgs {
+    ActivateLicenseKeyArgs {
+        license_key: "KEY-123".to_string(),
+        instance_name: "Laptop".to_string(),
+    }
+}
+
+fn stored_license(status: LicenseStatus, validated_at: DateTime<Utc>) -> License {
+    License {
+        license_key: "KEY-123".to_string(),
+        instance_id: "ins_42".to_string(),
+        instance_name: "Laptop".to_string(),
+        status,
+        expires_at: None,
+        activated_at: validated_at,
+        validated_at,
+        updated_at: validated_at,
+    }
+}
+
+fn license_client(url: impl Into<String>) -> LicenseClient {
+    LicenseClient::new(url, Some("secret-key".to_string()))
+}
+
+/// Nothing listens on a port that was just released.
+fn unreachable_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+async fn activate(
+    connection: &mut SqliteConnection,
+    client: &LicenseClient,
+) -> Result<License, AppError> {
+    let value = license::activate(client, activate_args()).await?;
+    license::store(connection, &value)?;
+    Ok(value)
+}
+
+async fn validate(
+    connection: &mut SqliteConnection,
+    client: &LicenseClient,
+) -> Result<License, AppError> {
+    let value = license::stored(connection)?;
+    let response = license::validate(client, &value).await;
+    license::store_validation(connection, value, response)
+}
+
+fn reason(result: Result<impl std::fmt::Debug, AppError>) -> LicenseErrorReason {
+    match result {
+        Err(AppError::License { reason, .. }) => reason,
+        other => panic!("Expected a license error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn activation_stores_the_instance_from_the_response() {
+    let mut connection = testing::connection();
+    let (url, requests) = testing::http_stub(vec![(200, license_body("active"))]);
+    let client = license_client(url);
+
+    let activated = activate(&mut connection, &client).await.unwrap();
+
+    assert_eq!(activated.instance_id, "ins_42");
+    assert_eq!(activated.status, LicenseStatus::Active);
+    assert!(activated.expires_at.is_some());
+
+    let stored = license::load(&mut connection).unwrap().unwrap();
+    assert_eq!(stored.instance_id, "ins_42");
+    assert_eq!(stored.license_key, "KEY-123");
+
+    let request = requests.recv().unwrap();
+    assert_eq!(request.path, "/licenses/activate");
+    assert_eq!(request.headers["x-api-key"], "secret-key");
+    let body: Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body, json!({ "key": "KEY-123", "instance_name": "Laptop" }));
+}
+
+#[tokio::test]
+async fn a_rejected_key_is_not_stored() {
+    let mut connection = testing::connection();
+    let (url, _requests) = testing::http_stub(vec![(
+        400,
+        json!({ "status": 400, "error": "Bad Request", "message": ["Invalid license key"] })
+            .to_string(),
+    )]);
+    let client = license_client(url);
+
+    let result = activate(&mut connection, &client).await;
+
+    match &result {
+        Err(AppError::License { message, .. }) => assert_eq!(message, "Invalid license key"),
+        other => panic!("Expected a license error, got {:?}", other),
+    }
+    assert_eq!(reason(result), LicenseErrorReason::Rejected);
+    assert!(license::load(&mut connection).unwrap().is_none());
+}
+
+#[tokio::test]
+async fn an_unreachable_server_is_reported_as_such() {
+    let mut connection = testing::connection();
+    let client = license_client(unreachable_url());
+
+    let result = activate(&mut connection, &client).await;
+
+    assert_eq!(reason(result), LicenseErrorReason::Unreachable);
+}
+
+#[tokio::test]
+async fn a_build_without_an_api_key_does_not_call_the_server() {
+    let mut connection = testing::connection();
+    let client = LicenseClient::new(unreachable_url(), None);
+
+    let result = activate(&mut connection, &client).await;
+
+    assert_eq!(reason(result), LicenseErrorReason::NotConfigured);
+}
+
+#[tokio::test]
+async fn a_malformed_response_is_reported_as_such() {
+    let mut connection = testing::connection();
+    let (url, _requests) = testing::http_stub(vec![(200, json!({ "ok": true }).to_string())]);
+    let client = license_client(url);
+
+    let result = activate(&mut connection, &client).await;
+
+    assert_eq!(reason(result), LicenseErrorReason::InvalidResponse);
+}
+
+#[tokio::test]
+async fn validation_updates_the_stored_status() {
+    let mut connection = testing::connection();
+    let (url, requests) = testing::http_stub(vec![
+        (200, license_body("active")),
+        (200, license_body("expired")),
+    ]);
+    let client = license_client(url);
+    activate(&mut connection, &client).await.unwrap();
+
+    let validated = validate(&mut connection, &client).await.unwrap();
+
+    assert_eq!(validated.status, LicenseStatus::Expired);
+    assert_eq!(
+        license::load(&mut connection).unwrap().unwrap().status,
+        LicenseStatus::Expired
+    );
+    requests.recv().unwrap();
+    let body: Value = serde_json::from_str(&requests.recv().unwrap().body).unwrap();
+    assert_eq!(body, json!({ "key": "KEY-123", "instance_id": "ins_42" }));
+}
+
+#[tokio::test]
+async fn validation_needs_an_activated_license() {
+    let mut connection = testing::connection();
+    let client = license_client(unreachable_url());
+
+    let result = validate(&mut connection, &client).await;
+
+    assert_eq!(reason(result), LicenseErrorReason::NotActivated);
+}
+
+#[tokio::test]
+async fn deactivation_forgets_the_instance() {
+    let (url, requests) = testing::http_stub(vec![
+        (200, license_body("active")),
+        (200, license_body("active")),
+    ]);
+    let api = testing::api_with_license_client(&testing::temp_dir(), license_client(url));
+    api.clone()
+        .activate_license_key(activate_args())
+        .await
+        .unwrap();
+
+    let warning = api.clone().deactivate_license_key().await.unwrap();
+
+    assert!(warning.is_none());
+    assert!(api.get_license().await.unwrap().is_none());
+    requests.recv().unwrap();
+    assert_eq!(requests.recv().unwrap().path, "/licenses/deactivate");
+}
+
+#[tokio::test]
+async fn deactivation_forgets_the_instance_even_if_the_server_fails() {
+    let (url, _requests) = testing::http_stub(vec![
+        (200, license_body("active")),
+        (500, json!({ "message": "Server Error" }).to_string()),
+    ]);
+    let api = testing::api_with_license_client(&testing::temp_dir(), license_client(url));
+    api.clone()
+        .activate_license_key(activate_args())
+        .await
+        .unwrap();
+
+    let warning = api.clone().deactivate_license_key().await.unwrap();
+
+    assert!(warning.is_some());
+    assert!(api.get_license().await.unwrap().is_none());
+}
+
+#[test]
+fn a_recent_validation_is_valid_without_the_network() {
+    let now = Utc::now();
+    let report = license::status(
+        Some(stored_license(
+            LicenseStatus::Active,
+            now - Duration::hours(1),
+        )),
+        14,
+        now,
+    );
+
+    assert_eq!(report.state, LicenseState::Valid);
+}
+
+#[test]
+fn an_old_validation_is_in_the_grace_period_until_it_runs_out() {
+    let now = Utc::now();
+    let validated_at = now - Duration::days(10);
+
+    let report = license::status(
+        Some(stored_license(LicenseStatus::Active, validated_at)),
+        14,
+        now,
+    );
+    assert_eq!(report.state, LicenseState::Grace);
+    assert_eq!(
+        report.grace_ends_at,
+        Some(validated_at + Duration::days(15))
+    );
+
+    let report = license::status(
+        Some(stored_license(LicenseStatus::Active, validated_at)),
+        7,
+        now,
+    );
+    assert_eq!(report.state, LicenseState::Expired);
+}
+
+#[test]
+fn the_status_follows_what_the_provider_reported() {
+    let now = Utc::now();
+    let status = |status| license::status(Some(stored_license(status, now)), 14, now).state;
+
+    assert_eq!(status(LicenseStatus::Expired), LicenseState::Expired);
+    assert_eq!(status(LicenseStatus::Disabled), LicenseState::Invalid);
+    assert_eq!(status(LicenseStatus::Inactive), LicenseState::Invalid);
+    assert_eq!(license::status(None, 14, now).state, LicenseState::Invalid);
+
+    let mut expired = stored_license(LicenseStatus::Active, now);
+    expired.expires_at = Some(now - Duration::minutes(1));
+    assert_eq!(
+        license::status(Some(expired), 14, now).state,
+        LicenseState::Expired
+    );
+}
+
+#[tokio::test]
+async fn revalidation_only_asks_once_the_last_validation_is_old() {
+    let mut connection = testing::connection();
+    let (url, requests) = testing::http_stub(vec![
+        (200, license_body("active")),
+        (200, license_body("active")),
+    ]);
+    let client = license_client(url);
+    activate(&mut connection, &client).await.unwrap();
+    requests.recv().unwrap();
+
+    assert!(license::due_for_revalidation(&mut connection)
+        .unwrap()
+        .is_none());
+
+    diesel::sql_query("UPDATE license SET validated_at = '2025-01-01T00:00:00.000+00:00'")
+        .execute(&mut connection)
+        .unwrap();
+    assert!(license::due_for_revalidation(&mut connection)
+        .unwrap()
+        .is_some());
+    let revalidated = validate(&mut connection, &client).await.unwrap();
+
+    assert!(revalidated.validated_at > Utc::now() - Duration::minutes(1));
+    assert_eq!(requests.recv().unwrap().path, "/licenses/validate");
+    assert_eq!(
+        license::load_status(&mut connection).unwrap().state,
+        LicenseState::Valid
+    );
+}
+
+#[tokio::test]
+async fn an_unreachable_server_keeps_the_cached_validation() {
+    let mut connection = testing::connection();
+    let (url, _requests) = testing::http_stub(vec![(200, license_body("active"))]);
+    let client = license_client(url);
+    let activated = activate(&mut connection, &client).await.unwrap();
+    let offline = license_client(unreachable_url());
+
+    let result = validate(&mut connection, &offline).await;
+
+    assert_eq!(reason(result), LicenseErrorReason::Unreachable);
+    let stored = license::load(&mut connection).unwrap().unwrap();
+    assert_eq!(stored.status, LicenseStatus::Active);
+    assert_eq!(stored.validated_at, activated.validated_at);
+}
@@ -0,0 +1,220 @@
+use achievealchemy_lib::models::{PomodoroCycle, PomodoroSession, SessionType};
+use achievealchemy_lib::notifications::{self, NotificationAction};
+use achievealchemy_lib::reminders::DueReminder;
+use achievealchemy_lib::repository::{
+    CompleteSessionArgs, CreateSessionArgs, PomodoroRepository, StartCycleArgs,
+};
+use achievealchemy_lib::settings::{self, NotificationSettings, Settings};
+use achievealchemy_lib::testing::{self, FakeNotifier};
+use chrono::{Duration, Utc};
+use diesel::sqlite::SqliteConnection;
+
+fn start_cycle(connection: &mut SqliteConnection, auto_start: bool) -> PomodoroCycle {
+    connection
+        .start_pomodoro_cycle(StartCycleArgs {
+            preset_id: None,
+            focus_duration: None,
+            short_break_duration: None,
+            long_break_duration: None,
+            sessions_until_long_break: None,
+            auto_start_breaks: Some(auto_start),
+            auto_start_pomodoros: Some(auto_start),
+        })
+        .unwrap()
+}
+
+fn run_session(
+    connection: &mut SqliteConnection,
+    cycle: &PomodoroCycle,
+    session_type: SessionType,
+    was_completed: bool,
+) -> PomodoroSession {
+    let session = connection
+        .start_session(CreateSessionArgs {
+            cycle_id: cycle.id.clone(),
+            session_type,
+            duration_ms: 60 * 1000,
+        })
+        .unwrap();
+
+    connection
+        .complete_session(CompleteSessionArgs {
+            session_id: session.id,
+            was_completed,
+        })
+        .unwrap()
+}
+
+#[test]
+fn the_end_of_a_phase_offers_to_start_the_next_one() {
+    let mut connection = testing::connection();
+    let cycle = start_cycle(&mut connection, false);
+    let settings = NotificationSettings::default();
+
+    let focus = run_session(&mut connection, &cycle, SessionType::Focus, true);
+    let notification = notifications::session_ended(&settings, &focus, &cycle).unwrap();
+    assert_eq!(notification.title, "Focus session complete");
+    assert_eq!(notification.body, settings.focus_end_message);
+    assert_eq!(notification.action, Some(NotificationAction::StartBreak));
+
+    let short_break = run_session(&mut connection, &cycle, SessionType::ShortBreak, true);
+    let notification = notifications::session_ended(&settings, &short_break, &cycle).unwrap();
+    assert_eq!(notification.body, settings.break_end_message);
+    assert_eq!(notification.action, Some(NotificationAction::StartFocus));
+}
+
+#[test]
+fn phases_that_start_by_themselves_have_no_action() {
+    let mut connection = testing::connection();
+    let cycle = start_cycle(&mut connection, true);
+    let session = run_session(&mut connection, &cycle, SessionType::Focus, true);
+
+    let notification =
+        notifications::session_ended(&NotificationSettings::default(), &session, &cycle).unwrap();
+
+    assert_eq!(notification.action, None);
+}
+
+#[test]
+fn only_sessions_that_ran_out_are_notified() {
+    let mut connection = testing::connection();
+    let cycle = start_cycle(&mut connection, false);
+    let settings = NotificationSettings::default();
+
+    let stopped = run_session(&mut connection, &cycle, SessionType::Focus, false);
+    assert_eq!(
+        notifications::session_ended(&settings, &stopped, &cycle),
+        None
+    );
+
+    // The cycle completion that follows is notified instead
+    let long_break = run_session(&mut connection, &cycle, SessionType::LongBreak, true);
+    assert_eq!(
+        notifications::session_ended(&settings, &long_break, &cycle),
+        None
+    );
+}
+
+#[test]
+fn the_settings_turn_notifications_off() {
+    let mut connection = testing::connection();
+    let cycle = start_cycle(&mut connection, false);
+    let session = run_session(&mut connection, &cycle, SessionType::Focus, true);
+    let reminder = DueReminder {
+        reminder_id: "reminder".to_string(),
+        habit_id: "habit".to_string(),
+        habit_title: "Stretch".to_string(),
+        fire_at: Utc::now(),
+    };
+
+    let disabled = NotificationSettings {
+        enabled: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        notifications::session_ended(&disabled, &session, &cycle),
+        None
+    );
+    assert_eq!(notifications::cycle_completed(&disabled), None);
+    assert_eq!(notifications::reminder(&disabled, &reminder), None);
+
+    let without_session_end = NotificationSettings {
+        session_end: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        notifications::session_ended(&without_session_end, &session, &cycle),
+        None
+    );
+    assert!(notifications::reminder(&without_session_end, &reminder).is_some());
+}
+
+#[test]
+fn notifications_use_the_stored_text_and_sound() {
+    let mut connection = testing::connection();
+    let mut stored = Settings::default();
+    stored.notifications.sound = false;
+    stored.notifications.focus_end_message = "Stand up and stretch".to_string();
+    stored.notifications.cycle_end_message = "All done".to_string();
+    settings::save(&mut connection, &stored).unwrap();
+    let cycle = start_cycle(&mut connection, false);
+    let session = run_session(&mut connection, &cycle, SessionType::Focus, true);
+    let notifier = FakeNotifier::default();
+
+    notifications::notify_session_ended(&mut connection, &notifier, &session).unwrap();
+    notifications::notify_cycle_completed(&mut connection, &notifier).unwrap();
+
+    let sent = notifier.sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].body, "Stand up and stretch");
+    assert!(!sent[0].sound);
+    assert_eq!(sent[1].title, "Pomodoro cycle complete");
+    assert_eq!(sent[1].body, "All done");
+}
+
+#[test]
+fn empty_notification_text_is_rejected() {
+    let mut connection = testing::connection();
+    let mut invalid = Settings::default();
+    invalid.notifications.break_end_message = " ".to_string();
+
+    assert!(settings::save(&mut connection, &invalid).is_err());
+}
+
+#[test]
+fn the_action_types_offer_to_start_the_next_phase() {
+    let action_types = notifications::action_types();
+
+    assert_eq!(action_types[0]["id"], "start-break");
+    assert_eq!(action_types[0]["actions"][0]["title"], "Start break");
+    assert_eq!(action_types[1]["id"], "start-focus");
+    assert_eq!(action_types[1]["actions"][0]["title"], "Start focus");
+}
+
+#[test]
+fn the_end_of_a_phase_is_notified_without_the_window() {
+    let mut connection = testing::connection();
+    let notifier = FakeNotifier::default();
+    let cycle = start_cycle(&mut connection, false);
+    let session = connection
+        .start_session(CreateSessionArgs {
+            cycle_id: cycle.id.clone(),
+            session_type: SessionType::Focus,
+            duration_ms: 60 * 1000,
+        })
+        .unwrap();
+
+    // Not before the session can have run out
+    let notified = notifications::notify_phase_ended(
+        &mut connection,
+        &notifier,
+        SessionType::Focus,
+        session.started_at + Duration::seconds(30),
+    )
+    .unwrap();
+    assert!(notified.is_none());
+    assert!(notifier.sent().is_empty());
+
+    let notified = notifications::notify_phase_ended(
+        &mut connection,
+        &notifier,
+        SessionType::Focus,
+        session.started_at + Duration::minutes(1),
+    )
+    .unwrap();
+    assert_eq!(notified.map(|notified| notified.id), Some(session.id));
+    let sent = notifier.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].title, "Focus session complete");
+    assert_eq!(sent[0].action, Some(NotificationAction::StartBreak));
+
+    // A phase of another type isn't running
+    let notified = notifications::notify_phase_ended(
+        &mut connection,
+        &notifier,
+        SessionType::ShortBreak,
+        session.started_at + Duration::minutes(1),
+    )
+    .unwrap();
+    assert!(notified.is_none());
+}
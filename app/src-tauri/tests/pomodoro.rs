@@ -0,0 +1,228 @@
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::export::{self, DateRange};
+use achievealchemy_lib::models::{SessionType, Status};
+use achievealchemy_lib::repository::{
+    CompleteSessionArgs, CreateSessionArgs, PomodoroRepository, StartCycleArgs, UpdateCycleArgs,
+};
+use achievealchemy_lib::settings::{self, Settings};
+use achievealchemy_lib::testing;
+use chrono::Local;
+use diesel::sqlite::SqliteConnection;
+use diesel::RunQueryDsl;
+
+fn start_args() -> StartCycleArgs {
+    StartCycleArgs {
+        preset_id: None,
+        focus_duration: None,
+        short_break_duration: None,
+        long_break_duration: None,
+        sessions_until_long_break: None,
+        auto_start_breaks: None,
+        auto_start_pomodoros: None,
+    }
+}
+
+fn run_session(
+    connection: &mut SqliteConnection,
+    cycle_id: &str,
+    session_type: SessionType,
+    duration_ms: i32,
+    was_completed: bool,
+) {
+    let session = connection
+        .start_session(CreateSessionArgs {
+            cycle_id: cycle_id.to_string(),
+            session_type,
+            duration_ms,
+        })
+        .unwrap();
+    connection
+        .complete_session(CompleteSessionArgs {
+            session_id: session.id,
+            was_completed,
+        })
+        .unwrap();
+}
+
+#[test]
+fn a_cycle_without_args_uses_the_default_preset() {
+    let mut connection = testing::connection();
+
+    let cycle = connection.start_pomodoro_cycle(start_args()).unwrap();
+
+    assert_eq!(cycle.status, Status::InProgress);
+    assert_eq!(cycle.preset_id.as_deref(), Some("classic"));
+    assert_eq!(cycle.focus_duration, 25 * 60 * 1000);
+}
+
+#[test]
+fn explicit_values_take_precedence_over_the_preset() {
+    let mut connection = testing::connection();
+
+    let cycle = connection
+        .start_pomodoro_cycle(StartCycleArgs {
+            preset_id: Some("deep-work".to_string()),
+            focus_duration: Some(40 * 60 * 1000),
+            ..start_args()
+        })
+        .unwrap();
+
+    assert_eq!(cycle.preset_id.as_deref(), Some("deep-work"));
+    assert_eq!(cycle.focus_duration, 40 * 60 * 1000);
+    assert_eq!(cycle.short_break_duration, 10 * 60 * 1000);
+}
+
+#[test]
+fn starting_a_cycle_from_an_unknown_preset_fails() {
+    let mut connection = testing::connection();
+
+    let result = connection.start_pomodoro_cycle(StartCycleArgs {
+        preset_id: Some("missing".to_string()),
+        ..start_args()
+    });
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn the_current_cycle_includes_its_sessions() {
+    let mut connection = testing::connection();
+    assert!(connection.current_cycle().unwrap().is_none());
+
+    let cycle = connection.start_pomodoro_cycle(start_args()).unwrap();
+    run_session(
+        &mut connection,
+        &cycle.id,
+        SessionType::Focus,
+        1_500_000,
+        true,
+    );
+
+    let current = connection.current_cycle().unwrap().unwrap();
+    assert_eq!(current.id, cycle.id);
+    assert_eq!(current.sessions.len(), 1);
+    assert!(current.sessions[0].was_completed);
+    assert!(current.sessions[0].completed_at.is_some());
+}
+
+#[test]
+fn completing_a_cycle_ends_it() {
+    let mut connection = testing::connection();
+    let cycle = connection.start_pomodoro_cycle(start_args()).unwrap();
+
+    let mutation = connection
+        .update_cycle_status(UpdateCycleArgs {
+            id: cycle.id,
+            status: Status::Completed,
+        })
+        .unwrap();
+
+    assert_eq!(mutation.value.status, Status::Completed);
+    assert!(mutation.value.completed_at.is_some());
+    assert_eq!(mutation.description, "Marked pomodoro cycle as completed");
+    assert!(connection.current_cycle().unwrap().is_none());
+}
+
+#[test]
+fn sessions_need_a_positive_duration() {
+    let mut connection = testing::connection();
+    let cycle = connection.start_pomodoro_cycle(start_args()).unwrap();
+
+    let result = connection.start_session(CreateSessionArgs {
+        cycle_id: cycle.id,
+        session_type: SessionType::Focus,
+        duration_ms: 0,
+    });
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn daily_stats_sum_completed_focus_sessions_in_minutes() {
+    let mut connection = testing::connection();
+    let cycle = connection.start_pomodoro_cycle(start_args()).unwrap();
+    run_session(
+        &mut connection,
+        &cycle.id,
+        SessionType::Focus,
+        1_500_000,
+        true,
+    );
+    run_session(
+        &mut connection,
+        &cycle.id,
+        SessionType::Focus,
+        1_230_000,
+        true,
+    );
+    run_session(
+        &mut connection,
+        &cycle.id,
+        SessionType::Focus,
+        1_500_000,
+        false,
+    );
+    run_session(
+        &mut connection,
+        &cycle.id,
+        SessionType::ShortBreak,
+        300_000,
+        true,
+    );
+
+    let today = Local::now().format("%Y-%m-%d").to_string();
+
+    assert_eq!(connection.daily_stats(&today).unwrap(), 45);
+    assert_eq!(connection.daily_stats("2000-01-01").unwrap(), 0);
+    assert!(connection.daily_stats("today").is_err());
+}
+
+#[test]
+fn focus_days_follow_the_configured_timezone() {
+    let mut connection = testing::connection();
+    let new_settings = Settings {
+        timezone: Some("Pacific/Kiritimati".to_string()),
+        ..Settings::default()
+    };
+    settings::save(&mut connection, &new_settings).unwrap();
+    let cycle = connection.start_pomodoro_cycle(start_args()).unwrap();
+    run_session(
+        &mut connection,
+        &cycle.id,
+        SessionType::Focus,
+        1_500_000,
+        true,
+    );
+    // 01:00 on March 2nd in UTC+14
+    diesel::sql_query("UPDATE pomodoro_sessions SET started_at = '2025-03-01 11:00:00+00:00'")
+        .execute(&mut connection)
+        .unwrap();
+
+    assert_eq!(connection.daily_stats("2025-03-02").unwrap(), 25);
+    assert_eq!(connection.daily_stats("2025-03-01").unwrap(), 0);
+
+    let path = testing::temp_dir().join("sessions.csv");
+    let range = DateRange::parse("2025-03-02", "2025-03-02").unwrap();
+    assert_eq!(
+        export::export_pomodoro_sessions(&mut connection, &range, &path).unwrap(),
+        1
+    );
+    let csv = std::fs::read_to_string(&path).unwrap();
+    assert!(csv.contains(",2025-03-02,2025-03-02 01:00:00,"), "{}", csv);
+}
+
+#[test]
+fn a_session_needs_an_existing_cycle() {
+    let mut connection = testing::connection();
+
+    let result = connection.start_session(CreateSessionArgs {
+        cycle_id: "missing".to_string(),
+        session_type: SessionType::Focus,
+        duration_ms: 1_500_000,
+    });
+
+    match result {
+        Err(AppError::Validation { errors }) => assert_eq!(errors[0].field, "cycleId"),
+        other => panic!("Expected a validation error, got {:?}", other),
+    }
+}
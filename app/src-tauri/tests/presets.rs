@@ -0,0 +1,108 @@
+use achievealchemy_lib::repository::{CreatePresetArgs, PresetRepository, UpdatePresetArgs};
+use achievealchemy_lib::testing;
+
+fn create_args(name: &str) -> CreatePresetArgs {
+    CreatePresetArgs {
+        name: name.to_string(),
+        focus_duration: 20 * 60 * 1000,
+        short_break_duration: 4 * 60 * 1000,
+        long_break_duration: 12 * 60 * 1000,
+        sessions_until_long_break: 3,
+        auto_start_breaks: false,
+        auto_start_pomodoros: false,
+    }
+}
+
+#[test]
+fn the_classic_preset_is_the_default_one() {
+    let mut connection = testing::connection();
+
+    let presets = connection.presets().unwrap();
+
+    assert_eq!(presets.len(), 3);
+    assert_eq!(presets[0].id, "classic");
+    assert!(presets[0].is_default);
+    assert!(presets[1..].iter().all(|preset| !preset.is_default));
+}
+
+#[test]
+fn created_presets_are_not_the_default() {
+    let mut connection = testing::connection();
+
+    let preset = connection.create_preset(create_args("Short")).unwrap();
+
+    assert!(!preset.is_default);
+    assert!(connection
+        .presets()
+        .unwrap()
+        .iter()
+        .any(|p| p.id == preset.id));
+}
+
+#[test]
+fn create_preset_rejects_a_zero_duration() {
+    let mut connection = testing::connection();
+
+    let result = connection.create_preset(CreatePresetArgs {
+        focus_duration: 0,
+        ..create_args("Broken")
+    });
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn update_preset_only_changes_given_fields() {
+    let mut connection = testing::connection();
+    let preset = connection.create_preset(create_args("Short")).unwrap();
+
+    let updated = connection
+        .update_preset(UpdatePresetArgs {
+            id: preset.id.clone(),
+            name: Some("Shorter".to_string()),
+            focus_duration: Some(15 * 60 * 1000),
+            short_break_duration: None,
+            long_break_duration: None,
+            sessions_until_long_break: None,
+            auto_start_breaks: None,
+            auto_start_pomodoros: None,
+        })
+        .unwrap();
+
+    assert_eq!(updated.name, "Shorter");
+    assert_eq!(updated.focus_duration, 15 * 60 * 1000);
+    assert_eq!(updated.short_break_duration, preset.short_break_duration);
+}
+
+#[test]
+fn setting_the_default_moves_it_from_the_previous_one() {
+    let mut connection = testing::connection();
+
+    connection.set_default_preset("deep-work").unwrap();
+
+    let defaults: Vec<String> = connection
+        .presets()
+        .unwrap()
+        .into_iter()
+        .filter(|preset| preset.is_default)
+        .map(|preset| preset.id)
+        .collect();
+    assert_eq!(defaults, ["deep-work"]);
+    assert!(connection.set_default_preset("missing").is_err());
+}
+
+#[test]
+fn only_presets_other_than_the_default_can_be_deleted() {
+    let mut connection = testing::connection();
+
+    assert!(connection.delete_preset("classic").is_err());
+    connection.delete_preset("study").unwrap();
+
+    let ids: Vec<String> = connection
+        .presets()
+        .unwrap()
+        .into_iter()
+        .map(|preset| preset.id)
+        .collect();
+    assert_eq!(ids, ["classic", "deep-work"]);
+}
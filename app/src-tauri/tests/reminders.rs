@@ -0,0 +1,229 @@
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::reminders::{
+    self, CreateHabitReminderArgs, HabitReminder, UpdateHabitReminderArgs, Weekday,
+};
+use achievealchemy_lib::repository::{
+    CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs, HabitRepository,
+    UpdateHabitArgs,
+};
+use achievealchemy_lib::testing;
+use chrono::{DateTime, Utc};
+use chrono_tz::Europe::Berlin;
+use diesel::sqlite::SqliteConnection;
+use diesel::RunQueryDsl;
+
+fn create_habit(connection: &mut SqliteConnection) -> String {
+    connection
+        .create_habit(CreateHabitArgs {
+            title: "Stretch".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .unwrap()
+        .id
+}
+
+/// A reminder at `time` every day, last edited at the start of 2025-03-03.
+fn create_reminder(connection: &mut SqliteConnection, habit_id: &str, time: &str) -> HabitReminder {
+    let reminder = reminders::create_reminder(
+        connection,
+        CreateHabitReminderArgs {
+            habit_id: habit_id.to_string(),
+            time: time.to_string(),
+            weekdays: Weekday::ALL.to_vec(),
+        },
+    )
+    .unwrap();
+
+    diesel::sql_query("UPDATE habit_reminders SET updated_at = '2025-03-03T00:00:00.000+00:00'")
+        .execute(connection)
+        .unwrap();
+    reminder
+}
+
+fn at(timestamp: &str) -> DateTime<Utc> {
+    timestamp.parse().unwrap()
+}
+
+fn reminder(time: &str, weekdays: &[Weekday]) -> HabitReminder {
+    HabitReminder {
+        id: "reminder".to_string(),
+        habit_id: "habit".to_string(),
+        time: time.to_string(),
+        weekdays: reminders::Weekdays(weekdays.to_vec()),
+        is_enabled: true,
+        last_fired_at: None,
+        created_at: at("2025-01-01T00:00:00Z"),
+        updated_at: at("2025-01-01T00:00:00Z"),
+    }
+}
+
+#[test]
+fn a_reminder_fires_on_its_next_weekday() {
+    // 2025-03-03 is a Monday
+    let reminder = reminder("08:00", &[Weekday::Monday, Weekday::Wednesday]);
+
+    assert_eq!(
+        reminder.next_fire_at(at("2025-03-03T07:00:00Z"), &Utc),
+        Some(at("2025-03-03T08:00:00Z"))
+    );
+    assert_eq!(
+        reminder.next_fire_at(at("2025-03-03T08:00:00Z"), &Utc),
+        Some(at("2025-03-05T08:00:00Z"))
+    );
+    assert_eq!(
+        reminder.next_fire_at(at("2025-03-05T09:00:00Z"), &Utc),
+        Some(at("2025-03-10T08:00:00Z"))
+    );
+}
+
+#[test]
+fn the_time_is_local_across_daylight_saving_changes() {
+    let every_day = reminder("08:00", &Weekday::ALL);
+    assert_eq!(
+        every_day.next_fire_at(at("2025-03-29T12:00:00Z"), &Berlin),
+        Some(at("2025-03-30T06:00:00Z"))
+    );
+    assert_eq!(
+        every_day.next_fire_at(at("2025-03-28T12:00:00Z"), &Berlin),
+        Some(at("2025-03-29T07:00:00Z"))
+    );
+
+    // 02:30 doesn't exist on 2025-03-30 in Berlin
+    let skipped = reminder("02:30", &Weekday::ALL);
+    assert_eq!(
+        skipped.next_fire_at(at("2025-03-29T12:00:00Z"), &Berlin),
+        Some(at("2025-03-30T01:30:00Z"))
+    );
+}
+
+#[test]
+fn a_due_reminder_fires_once() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+    let reminder = create_reminder(&mut connection, &habit_id, "08:00");
+
+    let check = reminders::check(&mut connection, at("2025-03-03T08:00:30Z"), &Utc).unwrap();
+
+    assert_eq!(check.due.len(), 1);
+    assert_eq!(check.due[0].reminder_id, reminder.id);
+    assert_eq!(check.due[0].habit_title, "Stretch");
+    assert_eq!(check.due[0].fire_at, at("2025-03-03T08:00:00Z"));
+    assert_eq!(check.next_at, Some(at("2025-03-04T08:00:00Z")));
+
+    let check = reminders::check(&mut connection, at("2025-03-03T08:01:00Z"), &Utc).unwrap();
+    assert!(check.due.is_empty());
+}
+
+#[test]
+fn a_reminder_is_skipped_when_the_habit_is_done_for_the_day() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+    create_reminder(&mut connection, &habit_id, "20:00");
+    connection
+        .toggle_habit_completion(CreateHabitCompletionArgs {
+            id: None,
+            habit_id: habit_id.clone(),
+            created_at: Some("2025-03-03".to_string()),
+        })
+        .unwrap();
+
+    let check = reminders::check(&mut connection, at("2025-03-03T20:00:00Z"), &Utc).unwrap();
+    assert!(check.due.is_empty());
+
+    let check = reminders::check(&mut connection, at("2025-03-04T20:00:00Z"), &Utc).unwrap();
+    assert_eq!(check.due.len(), 1);
+}
+
+#[test]
+fn a_reminder_missed_while_asleep_fires_on_wake_unless_it_is_old() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+    create_reminder(&mut connection, &habit_id, "08:00");
+
+    let check = reminders::check(&mut connection, at("2025-03-03T08:40:00Z"), &Utc).unwrap();
+    assert_eq!(check.due.len(), 1);
+
+    // Asleep from before 08:00 on 2025-03-04 until the evening
+    let check = reminders::check(&mut connection, at("2025-03-04T19:00:00Z"), &Utc).unwrap();
+    assert!(check.due.is_empty());
+    assert_eq!(check.next_at, Some(at("2025-03-05T08:00:00Z")));
+}
+
+#[test]
+fn archived_habits_and_disabled_reminders_are_not_reminded() {
+    let mut connection = testing::connection();
+    let archived = create_habit(&mut connection);
+    create_reminder(&mut connection, &archived, "08:00");
+    connection
+        .update_habit(UpdateHabitArgs {
+            id: archived,
+            title: None,
+            description: None,
+            icon: None,
+            is_archived: Some(true),
+        })
+        .unwrap();
+
+    let check = reminders::check(&mut connection, at("2025-03-03T08:00:00Z"), &Utc).unwrap();
+    assert!(check.due.is_empty());
+
+    let habit_id = create_habit(&mut connection);
+    let reminder = create_reminder(&mut connection, &habit_id, "09:00");
+    reminders::update_reminder(
+        &mut connection,
+        UpdateHabitReminderArgs {
+            id: reminder.id,
+            time: None,
+            weekdays: None,
+            is_enabled: Some(false),
+        },
+    )
+    .unwrap();
+
+    let check = reminders::check(&mut connection, at("2025-03-03T09:00:00Z"), &Utc).unwrap();
+    assert!(check.due.is_empty());
+    assert_eq!(check.next_at, Some(at("2025-03-04T08:00:00Z")));
+}
+
+#[test]
+fn invalid_reminders_are_rejected() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+
+    let result = reminders::create_reminder(
+        &mut connection,
+        CreateHabitReminderArgs {
+            habit_id,
+            time: "25:00".to_string(),
+            weekdays: Vec::new(),
+        },
+    );
+
+    match result {
+        Err(AppError::Validation { errors }) => {
+            let fields: Vec<_> = errors.iter().map(|error| error.field.as_str()).collect();
+            assert_eq!(fields, ["time", "weekdays"]);
+        }
+        other => panic!("Expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn weekdays_are_stored_in_week_order() {
+    let mut connection = testing::connection();
+    let habit_id = create_habit(&mut connection);
+
+    reminders::create_reminder(
+        &mut connection,
+        CreateHabitReminderArgs {
+            habit_id: habit_id.clone(),
+            time: "07:30".to_string(),
+            weekdays: vec![Weekday::Friday, Weekday::Monday, Weekday::Friday],
+        },
+    )
+    .unwrap();
+
+    let stored = reminders::reminders(&mut connection, &habit_id).unwrap();
+    assert_eq!(stored[0].weekdays.0, [Weekday::Monday, Weekday::Friday]);
+}
@@ -0,0 +1,86 @@
+use achievealchemy_lib::settings::{self, AutomationSettings, Settings, Theme};
+use achievealchemy_lib::testing;
+
+#[test]
+fn a_new_database_has_the_default_settings() {
+    let mut connection = testing::connection();
+
+    assert_eq!(
+        settings::load(&mut connection).unwrap(),
+        Settings::default()
+    );
+}
+
+#[test]
+fn saved_settings_are_loaded_back() {
+    let mut connection = testing::connection();
+    let new_settings = Settings {
+        focus_duration: 30 * 60 * 1000,
+        timezone: Some("Europe/Berlin".to_string()),
+        theme: Theme::Dark,
+        ..Settings::default()
+    };
+
+    settings::save(&mut connection, &new_settings).unwrap();
+
+    assert_eq!(settings::load(&mut connection).unwrap(), new_settings);
+}
+
+#[test]
+fn invalid_settings_are_not_saved() {
+    let mut connection = testing::connection();
+    let new_settings = Settings {
+        timezone: Some("Mars/Olympus_Mons".to_string()),
+        ..Settings::default()
+    };
+
+    assert!(settings::save(&mut connection, &new_settings).is_err());
+    assert_eq!(
+        settings::load(&mut connection).unwrap(),
+        Settings::default()
+    );
+}
+
+#[test]
+fn the_automation_api_only_binds_to_loopback() {
+    let mut connection = testing::connection();
+    let with_automation = |automation: AutomationSettings| Settings {
+        automation,
+        ..Settings::default()
+    };
+    let enabled = AutomationSettings {
+        enabled: true,
+        token: Some("token".to_string()),
+        ..AutomationSettings::default()
+    };
+
+    for bind_address in ["0.0.0.0", "192.168.1.10", "localhost"] {
+        let new_settings = with_automation(AutomationSettings {
+            bind_address: bind_address.to_string(),
+            ..enabled.clone()
+        });
+        assert!(settings::save(&mut connection, &new_settings).is_err());
+    }
+
+    let new_settings = with_automation(AutomationSettings {
+        bind_address: "::1".to_string(),
+        ..enabled.clone()
+    });
+    settings::save(&mut connection, &new_settings).unwrap();
+    assert_eq!(settings::load(&mut connection).unwrap(), new_settings);
+}
+
+#[test]
+fn the_automation_api_needs_a_token_to_be_enabled() {
+    let mut connection = testing::connection();
+    let new_settings = Settings {
+        automation: AutomationSettings {
+            enabled: true,
+            token: None,
+            ..AutomationSettings::default()
+        },
+        ..Settings::default()
+    };
+
+    assert!(settings::save(&mut connection, &new_settings).is_err());
+}
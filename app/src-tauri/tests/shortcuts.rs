@@ -0,0 +1,126 @@
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::settings::{self, Settings, ShortcutSettings};
+use achievealchemy_lib::shortcuts::{self, Modifier, ShortcutAction};
+use achievealchemy_lib::testing::{self, FakeShortcutRegistry};
+
+#[test]
+fn bindings_are_parsed_regardless_of_spelling() {
+    let shortcut = shortcuts::parse("shift + CTRL+alt+p").unwrap();
+
+    assert_eq!(
+        shortcut.modifiers,
+        [Modifier::Ctrl, Modifier::Alt, Modifier::Shift]
+    );
+    assert_eq!(shortcut.key, "P");
+    assert_eq!(shortcut.to_string(), "Ctrl+Alt+Shift+P");
+    assert_eq!(
+        shortcuts::parse("Control+Option+F12").unwrap().to_string(),
+        "Ctrl+Alt+F12"
+    );
+    assert_eq!(
+        shortcuts::parse("Super+pageup").unwrap().to_string(),
+        "Super+PageUp"
+    );
+}
+
+#[test]
+fn invalid_bindings_are_rejected() {
+    for binding in [
+        "",
+        "P",
+        "Shift+P",
+        "Ctrl+",
+        "Hyper+P",
+        "Ctrl+F25",
+        "Ctrl+Alt+PP",
+    ] {
+        assert!(
+            shortcuts::parse(binding).is_err(),
+            "{} should be rejected",
+            binding
+        );
+    }
+}
+
+#[test]
+fn actions_cannot_share_a_shortcut() {
+    let mut connection = testing::connection();
+    let new_settings = Settings {
+        shortcuts: ShortcutSettings {
+            start_pause: Some("Ctrl+Alt+P".to_string()),
+            skip: Some("alt+ctrl+p".to_string()),
+            log_habit: Some("Ctrl+L".to_string()),
+        },
+        ..Settings::default()
+    };
+
+    match settings::save(&mut connection, &new_settings) {
+        Err(AppError::Validation { errors }) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].field, "shortcuts.skip");
+            assert_eq!(errors[0].message, "conflicts with shortcuts.startPause");
+        }
+        other => panic!("Expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn actions_without_a_binding_are_not_registered() {
+    let registry = FakeShortcutRegistry::default();
+    let settings = ShortcutSettings {
+        start_pause: Some("Ctrl+Alt+P".to_string()),
+        skip: None,
+        log_habit: None,
+    };
+
+    let errors = shortcuts::register(&registry, &settings);
+
+    assert!(errors.is_empty());
+    assert_eq!(
+        registry.registered(),
+        [("Ctrl+Alt+P".to_string(), ShortcutAction::StartPause)]
+    );
+}
+
+#[test]
+fn registration_failures_are_reported_per_binding() {
+    let registry = FakeShortcutRegistry::with_taken(&["Ctrl+Alt+S"]);
+    let settings = ShortcutSettings {
+        start_pause: Some("Ctrl+Alt+P".to_string()),
+        skip: Some("Ctrl+Alt+S".to_string()),
+        log_habit: Some("Ctrl+Alt+L".to_string()),
+    };
+
+    let errors = shortcuts::register(&registry, &settings);
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].action, ShortcutAction::Skip);
+    assert_eq!(errors[0].binding, "Ctrl+Alt+S");
+    let registered: Vec<_> = registry
+        .registered()
+        .into_iter()
+        .map(|(_, action)| action)
+        .collect();
+    assert_eq!(
+        registered,
+        [ShortcutAction::StartPause, ShortcutAction::LogHabit]
+    );
+}
+
+#[test]
+fn registering_again_replaces_the_previous_shortcuts() {
+    let registry = FakeShortcutRegistry::default();
+    shortcuts::register(&registry, &ShortcutSettings::default());
+
+    let settings = ShortcutSettings {
+        start_pause: None,
+        skip: None,
+        log_habit: Some("Ctrl+Shift+H".to_string()),
+    };
+    shortcuts::register(&registry, &settings);
+
+    assert_eq!(
+        registry.registered(),
+        [("Ctrl+Shift+H".to_string(), ShortcutAction::LogHabit)]
+    );
+}
@@ -0,0 +1,392 @@
+use std::path::Path;
+
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::license::{ActivateLicenseKeyArgs, LicenseClient};
+use achievealchemy_lib::repository::{
+    CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs, GetHabitCompletionsArgs,
+    HabitRepository, UpdateHabitArgs,
+};
+use achievealchemy_lib::sync::{
+    self, FolderBackend, SecretStore, SyncBackend, SyncBackendConfig, WebDavBackend,
+};
+use achievealchemy_lib::testing::{self, FakeSecretStore};
+use achievealchemy_lib::{Api, ApiImpl};
+use diesel::sql_types::Text;
+use diesel::{QueryableByName, RunQueryDsl};
+use serde_json::json;
+
+/// `alice:secret` as HTTP basic auth.
+const ALICE: &str = "Basic YWxpY2U6c2VjcmV0";
+
+/// The app on a fresh database with an activated license.
+async fn licensed_api(data_dir: &Path) -> ApiImpl {
+    let body = json!({
+        "status": "active",
+        "expires_at": null,
+        "instance": { "id": "ins_42", "name": "Laptop" },
+    });
+    let (url, _requests) = testing::http_stub(vec![(200, body.to_string())]);
+    let client = LicenseClient::new(url, Some("secret-key".to_string()));
+    let api = testing::api_with_license_client(data_dir, client);
+
+    api.clone()
+        .activate_license_key(ActivateLicenseKeyArgs {
+            license_key: "KEY-123".to_string(),
+            instance_name: "Laptop".to_string(),
+        })
+        .await
+        .unwrap();
+    api
+}
+
+fn folder_config(folder: &Path) -> SyncBackendConfig {
+    SyncBackendConfig::Folder {
+        path: folder.to_str().unwrap().to_string(),
+    }
+}
+
+fn webdav_config(url: &str, password: Option<&str>) -> SyncBackendConfig {
+    SyncBackendConfig::WebDav {
+        url: url.to_string(),
+        username: "alice".to_string(),
+        password: password.map(str::to_string),
+    }
+}
+
+/// A PROPFIND response listing `children` of the collection at `/`.
+fn multistatus(children: &[&str]) -> String {
+    let responses: String = std::iter::once("/")
+        .chain(children.iter().copied())
+        .map(|href| format!("<d:response><d:href>{}</d:href></d:response>", href))
+        .collect();
+    format!(
+        r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">{}</d:multistatus>"#,
+        responses
+    )
+}
+
+#[derive(QueryableByName)]
+struct StoredBackend {
+    #[diesel(sql_type = diesel::sql_types::Nullable<Text>)]
+    backend: Option<String>,
+}
+
+fn stored_backend(connection: &mut diesel::SqliteConnection) -> Option<String> {
+    diesel::sql_query("SELECT backend FROM sync_state")
+        .get_result::<StoredBackend>(connection)
+        .unwrap()
+        .backend
+}
+
+#[test]
+fn the_backend_config_is_saved() {
+    let mut connection = testing::connection();
+    let secrets = FakeSecretStore::default();
+    assert!(sync::load_config(&mut connection, &secrets)
+        .unwrap()
+        .is_none());
+
+    let config = SyncBackendConfig::Folder {
+        path: "/tmp/sync".to_string(),
+    };
+    sync::save_config(&mut connection, &secrets, Some(&config)).unwrap();
+
+    assert!(matches!(
+        sync::load_config(&mut connection, &secrets).unwrap(),
+        Some(SyncBackendConfig::Folder { path }) if path == "/tmp/sync"
+    ));
+
+    sync::save_config(&mut connection, &secrets, None).unwrap();
+    assert!(sync::load_config(&mut connection, &secrets)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn the_webdav_password_is_kept_out_of_the_database() {
+    let mut connection = testing::connection();
+    let secrets = FakeSecretStore::default();
+
+    let config = webdav_config("https://dav.example.com/habits", Some("secret"));
+    sync::save_config(&mut connection, &secrets, Some(&config)).unwrap();
+
+    assert!(!stored_backend(&mut connection).unwrap().contains("secret"));
+    assert_eq!(
+        secrets.get("sync.webdav.password").unwrap().as_deref(),
+        Some("secret")
+    );
+    assert!(matches!(
+        sync::load_config(&mut connection, &secrets).unwrap(),
+        Some(SyncBackendConfig::WebDav { username, password: None, .. }) if username == "alice"
+    ));
+
+    // Editing the config without retyping the password keeps it
+    let config = webdav_config("https://dav.example.com/sync", None);
+    sync::save_config(&mut connection, &secrets, Some(&config)).unwrap();
+    assert!(secrets.get("sync.webdav.password").unwrap().is_some());
+
+    sync::save_config(&mut connection, &secrets, None).unwrap();
+    assert!(secrets.get("sync.webdav.password").unwrap().is_none());
+}
+
+#[test]
+fn passwords_stored_by_older_versions_are_moved_to_the_secret_store() {
+    let mut connection = testing::connection();
+    let secrets = FakeSecretStore::default();
+    let legacy = json!({
+        "kind": "WEBDAV",
+        "url": "https://dav.example.com/habits",
+        "username": "alice",
+        "password": "secret",
+    });
+    diesel::sql_query("UPDATE sync_state SET backend = ?")
+        .bind::<Text, _>(legacy.to_string())
+        .execute(&mut connection)
+        .unwrap();
+
+    let config = sync::load_config(&mut connection, &secrets).unwrap();
+
+    assert!(matches!(
+        config,
+        Some(SyncBackendConfig::WebDav { password: None, .. })
+    ));
+    assert!(!stored_backend(&mut connection).unwrap().contains("secret"));
+    assert_eq!(
+        secrets.get("sync.webdav.password").unwrap().as_deref(),
+        Some("secret")
+    );
+}
+
+#[test]
+fn the_webdav_backend_writes_and_reads_batches() {
+    let (url, requests) = testing::http_stub(vec![
+        (201, String::new()),
+        (405, String::new()),
+        (201, String::new()),
+        (207, multistatus(&["/laptop/"])),
+        (
+            207,
+            multistatus(&["/laptop/", "/laptop/1.json", "/laptop/notes.txt"]),
+        ),
+        (200, "[]".to_string()),
+    ]);
+    let backend = WebDavBackend::new(&url, "alice", "secret").unwrap();
+
+    backend.write_batch("laptop", "1.json", b"[]").unwrap();
+    assert_eq!(backend.list_devices().unwrap(), ["laptop"]);
+    assert_eq!(backend.list_batches("laptop").unwrap(), ["1.json"]);
+    assert_eq!(backend.read_batch("laptop", "1.json").unwrap(), b"[]");
+
+    let requests: Vec<_> = requests.iter().collect();
+    let paths: Vec<_> = requests
+        .iter()
+        .map(|request| request.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/",
+            "/laptop/",
+            "/laptop/1.json",
+            "/",
+            "/laptop/",
+            "/laptop/1.json"
+        ]
+    );
+    assert_eq!(requests[2].body, "[]");
+    assert!(requests
+        .iter()
+        .all(|request| request.headers["authorization"] == ALICE));
+}
+
+#[test]
+fn a_failed_webdav_upload_is_an_error() {
+    let (url, _requests) = testing::http_stub(vec![
+        (201, String::new()),
+        (201, String::new()),
+        (507, String::new()),
+    ]);
+    let backend = WebDavBackend::new(&url, "alice", "secret").unwrap();
+
+    let error = backend.write_batch("laptop", "1.json", b"[]").unwrap_err();
+
+    assert!(error.contains("507"), "{}", error);
+}
+
+#[test]
+fn changes_travel_between_devices_through_a_folder() {
+    let folder = testing::temp_dir();
+    let backend = FolderBackend::new(folder.to_str().unwrap());
+    let mut laptop = testing::connection();
+    let mut phone = testing::connection();
+
+    let habit = laptop
+        .create_habit(CreateHabitArgs {
+            title: "Journal".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .unwrap();
+
+    let report = sync::sync(&mut laptop, &backend).unwrap();
+    assert!(report.pushed > 0);
+    assert_eq!(report.pulled, 0);
+
+    let report = sync::sync(&mut phone, &backend).unwrap();
+    assert!(report.applied > 0);
+    let synced = phone.active_habits().unwrap();
+    assert_eq!(synced.len(), 1);
+    assert_eq!(synced[0].id, habit.id);
+
+    phone
+        .update_habit(UpdateHabitArgs {
+            id: habit.id.clone(),
+            title: Some("Journal daily".to_string()),
+            description: None,
+            icon: None,
+            is_archived: None,
+        })
+        .unwrap();
+    sync::sync(&mut phone, &backend).unwrap();
+    sync::sync(&mut laptop, &backend).unwrap();
+
+    assert_eq!(laptop.active_habits().unwrap()[0].title, "Journal daily");
+}
+
+#[test]
+fn devices_completing_the_same_day_keep_one_completion() {
+    let folder = testing::temp_dir();
+    let backend = FolderBackend::new(folder.to_str().unwrap());
+    let mut laptop = testing::connection();
+    let mut phone = testing::connection();
+
+    let habit = laptop
+        .create_habit(CreateHabitArgs {
+            title: "Journal".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .unwrap();
+    sync::sync(&mut laptop, &backend).unwrap();
+    sync::sync(&mut phone, &backend).unwrap();
+
+    let complete = |connection: &mut diesel::SqliteConnection, id: Option<String>| {
+        connection
+            .toggle_habit_completion(CreateHabitCompletionArgs {
+                id,
+                habit_id: habit.id.clone(),
+                created_at: Some("2025-03-01".to_string()),
+            })
+            .unwrap()
+    };
+    let completions = |connection: &mut diesel::SqliteConnection| {
+        connection
+            .habit_completions(GetHabitCompletionsArgs {
+                habit_id: habit.id.clone(),
+                limit: None,
+            })
+            .unwrap()
+    };
+
+    complete(&mut laptop, None);
+    complete(&mut phone, None);
+    sync::sync(&mut laptop, &backend).unwrap();
+    sync::sync(&mut phone, &backend).unwrap();
+    sync::sync(&mut laptop, &backend).unwrap();
+
+    assert_eq!(completions(&mut laptop).len(), 1);
+    assert_eq!(completions(&mut phone).len(), 1);
+
+    // Unmarking the day on one device unmarks it on the other
+    let completion = completions(&mut phone).remove(0);
+    complete(&mut phone, Some(completion.id));
+    sync::sync(&mut phone, &backend).unwrap();
+    sync::sync(&mut laptop, &backend).unwrap();
+
+    assert!(completions(&mut laptop).is_empty());
+}
+
+#[tokio::test]
+async fn configuring_sync_needs_a_license() {
+    let api = testing::api(&testing::temp_dir());
+
+    let result = api
+        .clone()
+        .update_sync_config(Some(folder_config(&testing::temp_dir())))
+        .await;
+
+    assert!(matches!(result, Err(AppError::LicenseRequired { .. })));
+    assert!(api.clone().get_sync_config().await.unwrap().is_none());
+    assert!(api.sync_now().await.is_err());
+}
+
+#[tokio::test]
+async fn sync_now_uses_the_configured_backend() {
+    let folder = testing::temp_dir();
+    let laptop = licensed_api(&testing::temp_dir()).await;
+    let phone = licensed_api(&testing::temp_dir()).await;
+    for api in [&laptop, &phone] {
+        api.clone()
+            .update_sync_config(Some(folder_config(&folder)))
+            .await
+            .unwrap();
+    }
+    assert!(matches!(
+        laptop.clone().get_sync_config().await.unwrap(),
+        Some(SyncBackendConfig::Folder { path }) if Path::new(&path) == folder
+    ));
+
+    laptop
+        .clone()
+        .create_habit(CreateHabitArgs {
+            title: "Journal".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .await
+        .unwrap();
+    let report = laptop.sync_now().await.unwrap();
+    assert!(report.pushed > 0);
+
+    let report = phone.clone().sync_now().await.unwrap();
+    assert!(report.applied > 0);
+    let habits = phone.get_active_habits().await.unwrap();
+    assert_eq!(habits.len(), 1);
+    assert_eq!(habits[0].title, "Journal");
+}
+
+#[tokio::test]
+async fn sync_now_signs_in_to_webdav_with_the_stored_password() {
+    let api = licensed_api(&testing::temp_dir()).await;
+    let (url, requests) = testing::http_stub(vec![
+        (201, String::new()),
+        (201, String::new()),
+        (201, String::new()),
+        (207, multistatus(&[])),
+    ]);
+    api.clone()
+        .update_sync_config(Some(webdav_config(&url, Some("secret"))))
+        .await
+        .unwrap();
+    assert!(matches!(
+        api.clone().get_sync_config().await.unwrap(),
+        Some(SyncBackendConfig::WebDav { password: None, .. })
+    ));
+
+    api.clone()
+        .create_habit(CreateHabitArgs {
+            title: "Journal".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .await
+        .unwrap();
+    let report = api.sync_now().await.unwrap();
+
+    assert!(report.pushed > 0);
+    let requests: Vec<_> = requests.iter().collect();
+    assert_eq!(requests.len(), 4);
+    assert!(requests
+        .iter()
+        .all(|request| request.headers["authorization"] == ALICE));
+}
@@ -0,0 +1,103 @@
+use achievealchemy_lib::models::SessionType;
+use achievealchemy_lib::timer::{self, PhaseClock, TimerCommand, TimerState};
+use chrono::{Duration, Utc};
+
+fn state(is_running: bool, is_paused: bool) -> TimerState {
+    TimerState {
+        mode: SessionType::Focus,
+        time_left: (12 * 60 + 34) * 1000,
+        is_running,
+        is_paused,
+    }
+}
+
+#[test]
+fn one_control_starts_pauses_and_resumes() {
+    assert_eq!(TimerCommand::start_or_pause(None), TimerCommand::Start);
+    assert_eq!(
+        TimerCommand::start_or_pause(Some(&state(false, false))),
+        TimerCommand::Start
+    );
+    assert_eq!(
+        TimerCommand::start_or_pause(Some(&state(true, false))),
+        TimerCommand::Pause
+    );
+    assert_eq!(
+        TimerCommand::start_or_pause(Some(&state(true, true))),
+        TimerCommand::Resume
+    );
+}
+
+#[test]
+fn the_label_shows_the_phase_and_time_left() {
+    assert_eq!(timer::label(None), "Timer stopped");
+    assert_eq!(timer::label(Some(&state(false, false))), "Timer stopped");
+    assert_eq!(timer::label(Some(&state(true, false))), "Focus, 12:34 left");
+    assert_eq!(
+        timer::label(Some(&state(true, true))),
+        "Focus paused, 12:34 left"
+    );
+
+    let short_break = TimerState {
+        mode: SessionType::ShortBreak,
+        time_left: 999,
+        ..state(true, false)
+    };
+    assert_eq!(timer::label(Some(&short_break)), "Short break, 00:00 left");
+}
+
+#[test]
+fn the_state_reported_by_the_window_is_camel_case() {
+    let reported = r#"{"mode":"SHORT_BREAK","timeLeft":300000,"isRunning":true,"isPaused":false}"#;
+
+    let state: TimerState = serde_json::from_str(reported).unwrap();
+
+    assert_eq!(state.mode, SessionType::ShortBreak);
+    assert_eq!(state.time_left, 300_000);
+    assert!(state.is_ticking());
+    assert_eq!(
+        serde_json::to_string(&TimerCommand::Skip).unwrap(),
+        r#""SKIP""#
+    );
+}
+
+#[test]
+fn the_phase_clock_ends_the_phase_once() {
+    let start = Utc::now();
+    let mut clock = PhaseClock::default();
+    let ticking = state(true, false);
+
+    clock.update(Some(ticking.clone()), start);
+    let ends_at = start + Duration::milliseconds(ticking.time_left.into());
+    assert_eq!(clock.ends_at(), Some(ends_at));
+
+    // A window falling behind reports more time left than there is
+    let behind = TimerState {
+        time_left: ticking.time_left - 1000,
+        ..ticking.clone()
+    };
+    clock.update(Some(behind), start + Duration::seconds(5));
+    assert_eq!(clock.ends_at(), Some(ends_at));
+
+    assert_eq!(clock.take_ended(ends_at - Duration::seconds(1)), None);
+    assert_eq!(clock.take_ended(ends_at), Some(SessionType::Focus));
+    assert_eq!(clock.take_ended(ends_at + Duration::seconds(1)), None);
+}
+
+#[test]
+fn pausing_moves_the_end_of_the_phase() {
+    let start = Utc::now();
+    let mut clock = PhaseClock::default();
+
+    clock.update(Some(state(true, false)), start);
+    clock.update(Some(state(true, true)), start + Duration::seconds(10));
+    assert_eq!(clock.ends_at(), None);
+    assert_eq!(clock.take_ended(start + Duration::days(1)), None);
+
+    let resumed_at = start + Duration::minutes(1);
+    clock.update(Some(state(true, false)), resumed_at);
+    assert_eq!(
+        clock.ends_at(),
+        Some(resumed_at + Duration::milliseconds(state(true, false).time_left.into()))
+    );
+}
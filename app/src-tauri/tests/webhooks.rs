@@ -0,0 +1,274 @@
+use achievealchemy_lib::error::AppError;
+use achievealchemy_lib::models::SessionType;
+use achievealchemy_lib::repository::{
+    CompleteSessionArgs, CompletionRepository, CreateHabitArgs, CreateHabitCompletionArgs,
+    CreateSessionArgs, HabitRepository, PomodoroRepository, StartCycleArgs,
+};
+use achievealchemy_lib::testing;
+use achievealchemy_lib::webhooks::{
+    self, CreateWebhookArgs, DeliveryStatus, Webhook, WebhookEventType,
+};
+use chrono::Utc;
+use diesel::sqlite::SqliteConnection;
+use serde_json::Value;
+
+fn create_webhook(
+    connection: &mut SqliteConnection,
+    url: &str,
+    event_types: Vec<WebhookEventType>,
+) -> Webhook {
+    webhooks::create_webhook(
+        connection,
+        CreateWebhookArgs {
+            url: url.to_string(),
+            event_types,
+            secret: None,
+        },
+    )
+    .unwrap()
+}
+
+fn create_habit(connection: &mut SqliteConnection) -> String {
+    connection
+        .create_habit(CreateHabitArgs {
+            title: "Stretch".to_string(),
+            description: String::new(),
+            icon: "CircleCheck".to_string(),
+        })
+        .unwrap()
+        .id
+}
+
+fn complete(connection: &mut SqliteConnection, habit_id: &str, date: &str) -> String {
+    connection
+        .toggle_habit_completion(CreateHabitCompletionArgs {
+            id: None,
+            habit_id: habit_id.to_string(),
+            created_at: Some(date.to_string()),
+        })
+        .unwrap()
+        .value
+        .id
+}
+
+fn uncomplete(connection: &mut SqliteConnection, habit_id: &str, completion_id: String) {
+    connection
+        .toggle_habit_completion(CreateHabitCompletionArgs {
+            id: Some(completion_id),
+            habit_id: habit_id.to_string(),
+            created_at: None,
+        })
+        .unwrap();
+}
+
+#[test]
+fn completing_a_habit_posts_a_signed_event() {
+    let mut connection = testing::connection();
+    let (url, requests) = testing::http_stub(vec![(200, String::new())]);
+    let webhook = create_webhook(
+        &mut connection,
+        &format!("{}/hook", url),
+        vec![WebhookEventType::HabitCompleted],
+    );
+    let habit_id = create_habit(&mut connection);
+
+    complete(&mut connection, &habit_id, "2025-03-01");
+    let report = webhooks::deliver_due(&mut connection, &webhooks::client().unwrap()).unwrap();
+
+    assert_eq!(report.delivered, 1);
+    let request = requests.recv().unwrap();
+    assert_eq!(request.path, "/hook");
+    assert_eq!(
+        request.headers["x-achievealchemy-signature-256"],
+        format!(
+            "sha256={}",
+            webhooks::sign(&webhook.secret, request.body.as_bytes())
+        )
+    );
+    assert_eq!(request.headers["x-achievealchemy-event"], "HABIT_COMPLETED");
+
+    let payload: Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(payload["type"], "HABIT_COMPLETED");
+    assert_eq!(payload["id"], request.headers["x-achievealchemy-delivery"]);
+    assert_eq!(payload["data"]["habit"]["id"], habit_id);
+    assert_eq!(payload["data"]["completion"]["createdAt"], "2025-03-01");
+
+    let deliveries = webhooks::deliveries(&mut connection, &webhook.id, 10).unwrap();
+    assert_eq!(deliveries[0].status, DeliveryStatus::Delivered);
+    assert_eq!(deliveries[0].attempts, 1);
+}
+
+#[test]
+fn failed_deliveries_are_retried_later() {
+    let mut connection = testing::connection();
+    let (url, requests) = testing::http_stub(vec![(500, String::new())]);
+    let webhook = create_webhook(
+        &mut connection,
+        &format!("{}/hook", url),
+        vec![WebhookEventType::HabitCompleted],
+    );
+    let habit_id = create_habit(&mut connection);
+    let client = webhooks::client().unwrap();
+
+    complete(&mut connection, &habit_id, "2025-03-01");
+    let report = webhooks::deliver_due(&mut connection, &client).unwrap();
+
+    assert_eq!(report.retrying, 1);
+    requests.recv().unwrap();
+
+    let delivery = &webhooks::deliveries(&mut connection, &webhook.id, 10).unwrap()[0];
+    assert_eq!(delivery.status, DeliveryStatus::Pending);
+    assert_eq!(delivery.attempts, 1);
+    assert!(delivery.next_attempt_at > Utc::now());
+    assert!(delivery.last_error.as_ref().unwrap().contains("500"));
+
+    // Not due yet, so nothing is sent
+    let report = webhooks::deliver_due(&mut connection, &client).unwrap();
+    assert_eq!(report.retrying, 0);
+    assert_eq!(report.delivered, 0);
+}
+
+#[test]
+fn only_subscribed_events_are_queued() {
+    let mut connection = testing::connection();
+    let webhook = create_webhook(
+        &mut connection,
+        "http://127.0.0.1:9/hook",
+        vec![WebhookEventType::SessionStarted],
+    );
+    let habit_id = create_habit(&mut connection);
+
+    complete(&mut connection, &habit_id, "2025-03-01");
+
+    assert!(webhooks::deliveries(&mut connection, &webhook.id, 10)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn reaching_a_streak_milestone_queues_an_event() {
+    let mut connection = testing::connection();
+    let webhook = create_webhook(
+        &mut connection,
+        "http://127.0.0.1:9/hook",
+        vec![WebhookEventType::StreakMilestone],
+    );
+    let habit_id = create_habit(&mut connection);
+
+    complete(&mut connection, &habit_id, "2025-03-01");
+    complete(&mut connection, &habit_id, "2025-03-02");
+    assert!(webhooks::deliveries(&mut connection, &webhook.id, 10)
+        .unwrap()
+        .is_empty());
+
+    complete(&mut connection, &habit_id, "2025-03-03");
+
+    let deliveries = webhooks::deliveries(&mut connection, &webhook.id, 10).unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].event_type, WebhookEventType::StreakMilestone);
+    let payload: Value = serde_json::from_str(&deliveries[0].payload).unwrap();
+    assert_eq!(payload["data"]["streak"], 3);
+}
+
+#[test]
+fn a_streak_milestone_is_only_queued_once() {
+    let mut connection = testing::connection();
+    let webhook = create_webhook(
+        &mut connection,
+        "http://127.0.0.1:9/hook",
+        vec![WebhookEventType::StreakMilestone],
+    );
+    let habit_id = create_habit(&mut connection);
+
+    complete(&mut connection, &habit_id, "2025-03-01");
+    complete(&mut connection, &habit_id, "2025-03-02");
+    let third = complete(&mut connection, &habit_id, "2025-03-03");
+    uncomplete(&mut connection, &habit_id, third);
+    complete(&mut connection, &habit_id, "2025-03-03");
+
+    let deliveries = webhooks::deliveries(&mut connection, &webhook.id, 10).unwrap();
+    assert_eq!(deliveries.len(), 1);
+
+    // A new streak reaching the same length is a new milestone
+    complete(&mut connection, &habit_id, "2025-03-10");
+    complete(&mut connection, &habit_id, "2025-03-11");
+    complete(&mut connection, &habit_id, "2025-03-12");
+
+    let deliveries = webhooks::deliveries(&mut connection, &webhook.id, 10).unwrap();
+    assert_eq!(deliveries.len(), 2);
+}
+
+#[test]
+fn a_session_is_reported_once_and_only_when_completed() {
+    let mut connection = testing::connection();
+    let webhook = create_webhook(
+        &mut connection,
+        "http://127.0.0.1:9/hook",
+        vec![WebhookEventType::SessionCompleted],
+    );
+    let cycle = connection
+        .start_pomodoro_cycle(StartCycleArgs {
+            preset_id: None,
+            focus_duration: None,
+            short_break_duration: None,
+            long_break_duration: None,
+            sessions_until_long_break: None,
+            auto_start_breaks: None,
+            auto_start_pomodoros: None,
+        })
+        .unwrap();
+    let mut end_session = |was_completed: bool| {
+        let session = connection
+            .start_session(CreateSessionArgs {
+                cycle_id: cycle.id.clone(),
+                session_type: SessionType::Focus,
+                duration_ms: 1_500_000,
+            })
+            .unwrap();
+        for _ in 0..2 {
+            connection
+                .complete_session(CompleteSessionArgs {
+                    session_id: session.id.clone(),
+                    was_completed,
+                })
+                .unwrap();
+        }
+    };
+
+    end_session(false);
+    end_session(true);
+
+    let deliveries = webhooks::deliveries(&mut connection, &webhook.id, 10).unwrap();
+    assert_eq!(deliveries.len(), 1);
+}
+
+#[test]
+fn webhooks_need_an_http_url_and_event_types() {
+    let mut connection = testing::connection();
+
+    let result = webhooks::create_webhook(
+        &mut connection,
+        CreateWebhookArgs {
+            url: "ftp://example.com/hook".to_string(),
+            event_types: vec![],
+            secret: None,
+        },
+    );
+
+    match result {
+        Err(AppError::Validation { errors }) => {
+            let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+            assert_eq!(fields, vec!["url", "eventTypes"]);
+        }
+        other => panic!("Expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn signatures_are_hmac_sha256() {
+    // RFC 4231, test case 2
+    assert_eq!(
+        webhooks::sign("Jefe", b"what do ya want for nothing?"),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
@@ -5,7 +5,33 @@ import { createTauRPCProxy as createProxy, type InferCommandOutput } from 'taurp
 
 export type ActivateLicenseKeyArgs = { licenseKey: string; instanceName: string }
 
-export type ActivateLicenseKeyResponse = { isActivated: boolean; instanceId: string }
+/**
+ * Error returned by resolvers that the frontend needs to tell apart, e.g. to
+ * highlight invalid form fields. Everything else is `Internal`.
+ */
+export type AppError = { kind: "VALIDATION"; errors: FieldError[] } | { kind: "LICENSE"; reason: LicenseErrorReason; message: string } | { kind: "LICENSE_REQUIRED"; feature: Feature; message: string } | { kind: "INTERNAL"; message: string }
+
+/**
+ * The local HTTP API for scripts and automation tools. Off by default.
+ */
+export type AutomationSettings = { enabled: boolean; 
+/**
+ * Must be a loopback address, the API is never reachable from outside.
+ */
+bindAddress: string; port: number; 
+/**
+ * Expected as `Authorization: Bearer <token>`, generated by the app.
+ */
+token: string | null }
+
+export type BackupInfo = { fileName: string; kind: BackupKind; createdAt: string; sizeBytes: number }
+
+export type BackupKind = "DAILY" | "PRE_MIGRATION" | "PRE_RESTORE"
+
+/**
+ * How many snapshots of each kind are kept before the oldest are deleted.
+ */
+export type BackupRetention = { daily: number; preMigration: number; preRestore: number }
 
 export type CompleteSessionArgs = { sessionId: string; wasCompleted: boolean }
 
@@ -13,44 +39,226 @@ export type CreateHabitArgs = { title: string; description: string; icon: string
 
 export type CreateHabitCompletionArgs = { id: string | null; habitId: string; createdAt: string | null }
 
-export type CreateSessionArgs = { cycleId: string; sessionType: SessionType; durationMinutes: number }
+export type CreateHabitReminderArgs = { habitId: string; time: string; weekdays: Weekday[] }
+
+export type CreatePresetArgs = { name: string; focusDuration: number; shortBreakDuration: number; longBreakDuration: number; sessionsUntilLongBreak: number; autoStartBreaks: boolean; autoStartPomodoros: boolean }
+
+export type CreateSessionArgs = { cycleId: string; sessionType: SessionType; durationMs: number }
+
+export type CreateWebhookArgs = { url: string; eventTypes: WebhookEventType[]; 
+/**
+ * Generated if not given.
+ */
+secret: string | null }
+
+export type DataChange = { type: "habit.created"; habit: Habit } | { type: "habit.updated"; habit: Habit } | { type: "habit.deleted"; habitId: string } | { type: "completion.toggled"; completion: HabitCompletion; completed: boolean } | { type: "cycle.started"; cycle: PomodoroCycle } | { type: "cycle.updated"; cycle: PomodoroCycle } | { type: "session.started"; session: PomodoroSession } | { type: "session.completed"; session: PomodoroSession } | { type: "preset.changed"; presetId: string } | { type: "data.reloaded" }
+
+export type DeliveryStatus = "PENDING" | "DELIVERED" | "FAILED"
+
+export type EncryptionStatus = { isEncrypted: boolean; isUnlocked: boolean }
+
+export type Entitlements = { licenseState: LicenseState; features: Feature[]; 
+/**
+ * Most active habits allowed, `None` with `UnlimitedHabits`.
+ */
+habitLimit: number | null }
+
+export type Event = { id: string; entity: string; entityId: string; operation: string; before: string | null; after: string | null; createdAt: string }
 
-export type DeactivateLicenseKeyArgs = { licenseKey: string; instanceId: string }
+/**
+ * The event types a webhook subscribes to, stored as a JSON array.
+ */
+export type EventTypes = WebhookEventType[]
 
-export type DeactivateLicenseKeyResponse = { isDeactivated: boolean }
+export type ExportArgs = { startDate: string; endDate: string; path: string }
+
+export type Feature = "UNLIMITED_HABITS" | "ANALYTICS" | "EXPORT" | "SYNC"
+
+/**
+ * A problem with a single argument field. `field` uses the camelCase name the
+ * frontend sends.
+ */
+export type FieldError = { field: string; message: string }
 
 export type GetHabitCompletionsArgs = { habitId: string; limit: number | null }
 
-export type GetPomodoroCycleWithRelationships = { id: string; status: Status; focusDuration: number; shortBreakDuration: number; longBreakDuration: number; sessionsUntilLongBreak: number; autoStartBreaks: boolean; autoStartPomodoros: boolean; startedAt: string; completedAt: string | null; updatedAt: string; sessions: PomodoroSession[] }
+export type GetPomodoroCycleWithRelationships = { id: string; status: Status; focusDuration: number; shortBreakDuration: number; longBreakDuration: number; sessionsUntilLongBreak: number; autoStartBreaks: boolean; autoStartPomodoros: boolean; startedAt: string; completedAt: string | null; updatedAt: string; presetId: string | null; sessions: PomodoroSession[] }
 
-export type Habit = { id: string; title: string; description: string; icon: string; isArchived: boolean; updatedAt: string; createdAt: string }
+export type Habit = { id: string; title: string; description: string; icon: string; isArchived: boolean; createdAt: string; updatedAt: string }
 
 export type HabitCompletion = { id: string; habitId: string; createdAt: string }
 
-export type PomodoroCycle = { id: string; status: string; focus_duration: number; short_break_duration: number; long_break_duration: number; sessions_until_long_break: number; auto_start_breaks: boolean; auto_start_pomodoros: boolean; started_at: string; completed_at: string | null; updated_at: string }
+export type HabitReminder = { id: string; habitId: string; 
+/**
+ * Local time of day, `HH:MM`.
+ */
+time: string; weekdays: Weekdays; isEnabled: boolean; 
+/**
+ * When the reminder was last due, whether it fired or was skipped.
+ */
+lastFiredAt: string | null; createdAt: string; updatedAt: string }
+
+/**
+ * Pausing focus sessions while away from the computer.
+ */
+export type IdleSettings = { enabled: boolean; 
+/**
+ * Time without input after which a running focus session is paused.
+ */
+thresholdMs: number }
+
+export type ImportAction = "CREATE" | "MERGE" | "SKIP"
+
+export type ImportArgs = { source: ImportSource; path: string }
+
+export type ImportReport = { source: ImportSource; habitsToCreate: number; habitsMerged: number; habitsSkipped: number; completionsToCreate: number; completionsSkipped: number; habits: ImportedHabitReport[] }
+
+export type ImportSource = "LOOP_CSV" | "LOOP_SQLITE" | "HABITICA" | "STREAKS"
+
+export type ImportedHabitReport = { title: string; action: ImportAction; reason: string | null; completionsToCreate: number; completionsSkipped: number }
+
+/**
+ * A gap in input during a focus session.
+ */
+export type Interruption = { id: string; sessionId: string; 
+/**
+ * The last input before the gap.
+ */
+startedAt: string; 
+/**
+ * The first input after the gap, `None` while the user is still away.
+ */
+endedAt: string | null; 
+/**
+ * The part of the gap the timer ran before it was paused.
+ */
+countedMs: number; resolution: InterruptionResolution | null; createdAt: string }
+
+/**
+ * What the user decided about the time the timer counted while they were
+ * away. Interruptions without one count as kept.
+ */
+export type InterruptionResolution = "KEEP" | "DISCARD"
+
+/**
+ * The activation of this device.
+ */
+export type License = { licenseKey: string; instanceId: string; instanceName: string; status: LicenseStatus; expiresAt: string | null; activatedAt: string; validatedAt: string; updatedAt: string }
 
-export type PomodoroSession = { id: string; cycle_id: string; session_type: string; started_at: string; completed_at: string | null; duration_minutes: number; was_completed: boolean }
+export type LicenseErrorReason = "UNREACHABLE" | "REJECTED" | "INVALID_RESPONSE" | "NOT_ACTIVATED" | "NOT_CONFIGURED"
+
+export type LicenseState = "VALID" | "GRACE" | "EXPIRED" | "INVALID"
+
+export type LicenseStatus = "INACTIVE" | "ACTIVE" | "EXPIRED" | "DISABLED"
+
+export type LicenseStatusReport = { state: LicenseState; license: License | null; 
+/**
+ * When the license stops working unless it is validated again.
+ */
+graceEndsAt: string | null }
+
+export type NotificationSettings = { enabled: boolean; sound: boolean; sessionEnd: boolean; habitReminders: boolean; 
+/**
+ * Shown when a focus session ends.
+ */
+focusEndMessage: string; 
+/**
+ * Shown when a short break ends.
+ */
+breakEndMessage: string; 
+/**
+ * Shown when the long break ends a cycle.
+ */
+cycleEndMessage: string }
+
+export type PomodoroCycle = { id: string; status: Status; focus_duration: number; short_break_duration: number; long_break_duration: number; sessions_until_long_break: number; auto_start_breaks: boolean; auto_start_pomodoros: boolean; started_at: string; completed_at: string | null; updated_at: string; preset_id: string | null }
+
+export type PomodoroPreset = { id: string; name: string; focus_duration: number; short_break_duration: number; long_break_duration: number; sessions_until_long_break: number; auto_start_breaks: boolean; auto_start_pomodoros: boolean; is_default: boolean; created_at: string; updated_at: string }
+
+export type PomodoroSession = { id: string; cycle_id: string; session_type: SessionType; started_at: string; completed_at: string | null; duration_ms: number; was_completed: boolean }
+
+export type ResolveInterruptionArgs = { id: string; resolution: InterruptionResolution }
 
 export type SessionType = "LONG_BREAK" | "FOCUS" | "SHORT_BREAK"
 
-export type StartCycleArgs = { focusDuration: number; shortBreakDuration: number; longBreakDuration: number; sessionsUntilLongBreak: number; autoStartBreaks: boolean; autoStartPomodoros: boolean }
+/**
+ * User preferences. Durations are in milliseconds, like in `StartCycleArgs`.
+ */
+export type Settings = { focusDuration: number; shortBreakDuration: number; longBreakDuration: number; sessionsUntilLongBreak: number; autoStartBreaks: boolean; autoStartPomodoros: boolean; 
+/**
+ * IANA name such as `Europe/Berlin`, `None` follows the system timezone.
+ */
+timezone: string | null; weekStart: WeekStart; theme: Theme; notifications: NotificationSettings; automation: AutomationSettings; shortcuts: ShortcutSettings; idle: IdleSettings }
+
+export type ShortcutAction = "START_PAUSE" | "SKIP" | "LOG_HABIT"
+
+/**
+ * A binding that couldn't be registered, usually because another app holds
+ * the same shortcut.
+ */
+export type ShortcutError = { action: ShortcutAction; binding: string; message: string }
+
+/**
+ * Global keyboard shortcuts, see `shortcuts::parse` for the format. `None`
+ * leaves an action without a shortcut.
+ */
+export type ShortcutSettings = { startPause: string | null; skip: string | null; logHabit: string | null }
+
+export type StartCycleArgs = { 
+/**
+ * Values missing from the args are taken from this preset, or from the
+ * default preset if none is given.
+ */
+presetId: string | null; focusDuration: number | null; shortBreakDuration: number | null; longBreakDuration: number | null; sessionsUntilLongBreak: number | null; autoStartBreaks: boolean | null; autoStartPomodoros: boolean | null }
 
 export type Status = "COMPLETED" | "IN_PROGRESS" | "ABANDONED"
 
+export type SyncBackendConfig = { kind: "FOLDER"; path: string } | { kind: "WEBDAV"; url: string; username: string; 
+/**
+ * Only set when saving, the password is kept in the [`SecretStore`]
+ * and never loaded. Saving without one keeps the stored password.
+ */
+password?: string | null }
+
+export type SyncReport = { pushed: number; pulled: number; applied: number; skipped: number }
+
+export type Theme = "LIGHT" | "DARK" | "SYSTEM"
+
 export type UpdateCycleArgs = { id: string; status: Status }
 
 export type UpdateHabitArgs = { id: string; title: string | null; description: string | null; icon: string | null; isArchived: boolean | null }
 
-export type ValidateLicenseKeyArgs = { licenseKey: string; instanceId: string }
+export type UpdateHabitReminderArgs = { id: string; time: string | null; weekdays: Weekday[] | null; isEnabled: boolean | null }
+
+export type UpdatePresetArgs = { id: string; name: string | null; focusDuration: number | null; shortBreakDuration: number | null; longBreakDuration: number | null; sessionsUntilLongBreak: number | null; autoStartBreaks: boolean | null; autoStartPomodoros: boolean | null }
+
+export type UpdateWebhookArgs = { id: string; url: string | null; eventTypes: WebhookEventType[] | null; isEnabled: boolean | null }
+
+export type Webhook = { id: string; url: string; secret: string; eventTypes: EventTypes; isEnabled: boolean; createdAt: string; updatedAt: string }
+
+export type WebhookDelivery = { id: string; webhookId: string; eventType: WebhookEventType; payload: string; status: DeliveryStatus; attempts: number; nextAttemptAt: string; lastError: string | null; createdAt: string }
+
+export type WebhookEventType = "HABIT_COMPLETED" | "STREAK_MILESTONE" | "SESSION_STARTED" | "SESSION_COMPLETED" | "CYCLE_COMPLETED" | "PING"
+
+export type WeekStart = "MONDAY" | "TUESDAY" | "WEDNESDAY" | "THURSDAY" | "FRIDAY" | "SATURDAY" | "SUNDAY"
+
+export type Weekday = "MONDAY" | "TUESDAY" | "WEDNESDAY" | "THURSDAY" | "FRIDAY" | "SATURDAY" | "SUNDAY"
 
-export type ValidateLicenseKeyResponse = { isValid: boolean }
+/**
+ * The days a reminder fires on, stored as a JSON array in week order.
+ */
+export type Weekdays = Weekday[]
 
-const ARGS_MAP = { '': '{"get_active_habits":[],"start_session":["args"],"get_daily_stats":["date"],"get_current_cycle":[],"activate_license_key":["args"],"complete_session":["args"],"update_habit":["args"],"get_habit_completion_streak":["habit_id"],"update_cycle_status":["args"],"create_habit":["args"],"validate_license_key":["args"],"deactivate_license_key":["args"],"get_archived_habits":[],"start_pomodoro_cycle":["args"],"get_habit_completions":["args"],"delete_habit":["habit_id"],"toggle_habit_completion":["args"]}' }
+const ARGS_MAP = { '': '{"data_changed":["change"],"get_license":[],"get_license_status":[],"activate_license_key":["args"],"validate_license_key":[],"deactivate_license_key":[],"get_entitlements":[],"create_habit":["args"],"get_active_habits":[],"get_archived_habits":[],"update_habit":["args"],"delete_habit":["habit_id"],"toggle_habit_completion":["args"],"get_habit_completions":["args"],"get_habit_completion_streak":["habit_id"],"start_pomodoro_cycle":["args"],"get_current_cycle":[],"update_cycle_status":["args"],"start_session":["args"],"complete_session":["args"],"get_daily_stats":["date"],"get_presets":[],"create_preset":["args"],"update_preset":["args"],"delete_preset":["preset_id"],"set_default_preset":["preset_id"],"export_habit_completions":["args"],"export_pomodoro_sessions":["args"],"preview_import":["args"],"import_habits":["args"],"list_backups":[],"restore_backup":["file_name"],"get_backup_retention":[],"update_backup_retention":["retention"],"get_encryption_status":[],"unlock_database":["passphrase"],"enable_encryption":["passphrase"],"get_settings":[],"update_settings":["new_settings"],"regenerate_automation_token":[],"get_shortcut_errors":[],"get_history":["entity_id"],"undo":[],"redo":[],"get_sync_config":[],"update_sync_config":["config"],"sync_now":[],"get_webhooks":[],"create_webhook":["args"],"update_webhook":["args"],"delete_webhook":["webhook_id"],"get_webhook_deliveries":["webhook_id"],"test_webhook":["webhook_id"],"get_habit_reminders":["habit_id"],"create_habit_reminder":["args"],"update_habit_reminder":["args"],"delete_habit_reminder":["reminder_id"],"get_unresolved_interruptions":[],"resolve_interruption":["args"]}' }
 export type Router = {
     '': {
-        activate_license_key: (args: ActivateLicenseKeyArgs) => Promise<ActivateLicenseKeyResponse>,
-        validate_license_key: (args: ValidateLicenseKeyArgs) => Promise<ValidateLicenseKeyResponse>,
-        deactivate_license_key: (args: DeactivateLicenseKeyArgs) => Promise<DeactivateLicenseKeyResponse>,
+        data_changed: (change: DataChange) => Promise<void>,
+        get_license: () => Promise<License | null>,
+        get_license_status: () => Promise<LicenseStatusReport>,
+        activate_license_key: (args: ActivateLicenseKeyArgs) => Promise<License>,
+        validate_license_key: () => Promise<License>,
+        deactivate_license_key: () => Promise<string | null>,
+        get_entitlements: () => Promise<Entitlements>,
         create_habit: (args: CreateHabitArgs) => Promise<void>,
         get_active_habits: () => Promise<Habit[]>,
         get_archived_habits: () => Promise<Habit[]>,
@@ -64,7 +272,45 @@ export type Router = {
         update_cycle_status: (args: UpdateCycleArgs) => Promise<PomodoroCycle>,
         start_session: (args: CreateSessionArgs) => Promise<PomodoroSession>,
         complete_session: (args: CompleteSessionArgs) => Promise<PomodoroSession>,
-        get_daily_stats: (date: string) => Promise<number>
+        get_daily_stats: (date: string) => Promise<number>,
+        get_presets: () => Promise<PomodoroPreset[]>,
+        create_preset: (args: CreatePresetArgs) => Promise<PomodoroPreset>,
+        update_preset: (args: UpdatePresetArgs) => Promise<PomodoroPreset>,
+        delete_preset: (presetId: string) => Promise<void>,
+        set_default_preset: (presetId: string) => Promise<void>,
+        export_habit_completions: (args: ExportArgs) => Promise<number>,
+        export_pomodoro_sessions: (args: ExportArgs) => Promise<number>,
+        preview_import: (args: ImportArgs) => Promise<ImportReport>,
+        import_habits: (args: ImportArgs) => Promise<ImportReport>,
+        list_backups: () => Promise<BackupInfo[]>,
+        restore_backup: (fileName: string) => Promise<void>,
+        get_backup_retention: () => Promise<BackupRetention>,
+        update_backup_retention: (retention: BackupRetention) => Promise<void>,
+        get_encryption_status: () => Promise<EncryptionStatus>,
+        unlock_database: (passphrase: string) => Promise<void>,
+        enable_encryption: (passphrase: string) => Promise<void>,
+        get_settings: () => Promise<Settings>,
+        update_settings: (newSettings: Settings) => Promise<Settings>,
+        regenerate_automation_token: () => Promise<Settings>,
+        get_shortcut_errors: () => Promise<ShortcutError[]>,
+        get_history: (entityId: string) => Promise<Event[]>,
+        undo: () => Promise<string | null>,
+        redo: () => Promise<string | null>,
+        get_sync_config: () => Promise<SyncBackendConfig | null>,
+        update_sync_config: (config: SyncBackendConfig | null) => Promise<void>,
+        sync_now: () => Promise<SyncReport>,
+        get_webhooks: () => Promise<Webhook[]>,
+        create_webhook: (args: CreateWebhookArgs) => Promise<Webhook>,
+        update_webhook: (args: UpdateWebhookArgs) => Promise<Webhook>,
+        delete_webhook: (webhookId: string) => Promise<void>,
+        get_webhook_deliveries: (webhookId: string) => Promise<WebhookDelivery[]>,
+        test_webhook: (webhookId: string) => Promise<WebhookDelivery>,
+        get_habit_reminders: (habitId: string) => Promise<HabitReminder[]>,
+        create_habit_reminder: (args: CreateHabitReminderArgs) => Promise<HabitReminder>,
+        update_habit_reminder: (args: UpdateHabitReminderArgs) => Promise<HabitReminder>,
+        delete_habit_reminder: (reminderId: string) => Promise<void>,
+        get_unresolved_interruptions: () => Promise<Interruption[]>,
+        resolve_interruption: (args: ResolveInterruptionArgs) => Promise<Interruption>
     }
 };
 
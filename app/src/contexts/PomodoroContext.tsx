@@ -3,14 +3,21 @@ import {
   useContext,
   useReducer,
   useEffect,
+  useRef,
   ReactNode,
 } from "react";
+import { addPluginListener } from "@tauri-apps/api/core";
+import { emit, listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import { toast } from "sonner";
+import { taurpc } from "@/lib/taurpc";
 import { useStartPomodoroCycle } from "@/hooks/pomodoro/use-start-pomodoro-cycle";
 import { useStartSession } from "@/hooks/pomodoro/use-start-session";
 import { useCompleteSession } from "@/hooks/pomodoro/use-complete-session";
 import { useGetCurrentCycle } from "@/hooks/pomodoro/use-get-current-cycle";
 import { useUpdateCycleStatus } from "@/hooks/pomodoro/use-update-cycle-status";
+import { useGetPresets } from "@/hooks/pomodoro/use-get-presets";
+import { Interruption, InterruptionResolution, PomodoroPreset } from "@/bindings";
 
 export enum PomodoroMode {
   FOCUS = "FOCUS",
@@ -19,7 +26,7 @@ export enum PomodoroMode {
 }
 
 // Pomodoro Settings
-interface PomodoroSettings {
+export interface PomodoroSettings {
   focusDuration: number;
   shortBreakDuration: number;
   longBreakDuration: number;
@@ -28,6 +35,12 @@ interface PomodoroSettings {
   autoStartPomodoros: boolean;
 }
 
+// The preset cycles start from, with the values it sets
+interface SelectedPreset {
+  id: string;
+  settings: PomodoroSettings;
+}
+
 // Timer State
 interface PomodoroState {
   mode: PomodoroMode;
@@ -36,6 +49,7 @@ interface PomodoroState {
   isPaused: boolean;
   completedSessions: number;
   settings: PomodoroSettings;
+  preset?: SelectedPreset;
   currentCycleId?: string;
   currentSessionId?: string;
 }
@@ -49,6 +63,44 @@ const DEFAULT_SETTINGS: PomodoroSettings = {
   autoStartPomodoros: true,
 };
 
+// Tray and other controls outside the window talk to the timer through these
+const TIMER_STATE_EVENT = "pomodoro-timer-state";
+const TIMER_COMMAND_EVENT = "pomodoro-timer-command";
+// Actions offered on phase end notifications, see notifications.rs
+const NOTIFICATION_ACTIONS = ["start-break", "start-focus"];
+
+type TimerCommand = "START" | "PAUSE" | "RESUME" | "SKIP";
+
+// Sent by the backend when the user is back after a focus session was paused
+// for inactivity
+const IDLE_RETURNED_EVENT = "idle-returned";
+
+function presetSettings(preset: PomodoroPreset): PomodoroSettings {
+  return {
+    focusDuration: preset.focus_duration,
+    shortBreakDuration: preset.short_break_duration,
+    longBreakDuration: preset.long_break_duration,
+    sessionsUntilLongBreak: preset.sessions_until_long_break,
+    autoStartBreaks: preset.auto_start_breaks,
+    autoStartPomodoros: preset.auto_start_pomodoros,
+  };
+}
+
+// The settings the user changed on top of the preset
+function overridesOf(
+  preset: PomodoroSettings,
+  settings: PomodoroSettings
+): Partial<PomodoroSettings> {
+  return Object.fromEntries(
+    Object.entries(settings).filter(
+      ([key, value]) => preset[key as keyof PomodoroSettings] !== value
+    )
+  );
+}
+
+// Other windows, like the quick log, share the provider but not the timer
+const isTimerWindow = getCurrentWindow().label === "main";
+
 // Reducer Actions
 type PomodoroAction =
   | { type: "START"; mode?: PomodoroMode }
@@ -58,6 +110,7 @@ type PomodoroAction =
   | { type: "RESET" }
   | { type: "TICK" }
   | { type: "UPDATE_SETTINGS"; settings: Partial<PomodoroSettings> }
+  | { type: "SELECT_PRESET"; preset: SelectedPreset }
   | { type: "SESSION_COMPLETE" }
   | { type: "SET_CYCLE_ID"; cycleId: string | undefined }
   | { type: "SET_SESSION_ID"; sessionId: string | undefined }
@@ -167,6 +220,22 @@ function pomodoroReducer(
     case "UPDATE_SETTINGS":
       return { ...state, settings: { ...state.settings, ...action.settings } };
 
+    case "SELECT_PRESET": {
+      // Changes made on top of the previous preset carry over
+      const overrides = state.preset
+        ? overridesOf(state.preset.settings, state.settings)
+        : {};
+      const settings = { ...action.preset.settings, ...overrides };
+      const isIdle = !state.isRunning && !state.currentCycleId;
+
+      return {
+        ...state,
+        preset: action.preset,
+        settings,
+        timeLeft: isIdle ? settings.focusDuration : state.timeLeft,
+      };
+    }
+
     case "SET_CYCLE_ID":
       return { ...state, currentCycleId: action.cycleId };
 
@@ -213,6 +282,7 @@ export function PomodoroProvider({ children }: { children: ReactNode }) {
   const { mutateAsync: completeSession } = useCompleteSession();
   const { data: currentCycle } = useGetCurrentCycle();
   const { mutateAsync: updateCycleStatus } = useUpdateCycleStatus();
+  const { data: presets } = useGetPresets();
 
   // Effect to sync with backend state
   useEffect(() => {
@@ -283,28 +353,196 @@ export function PomodoroProvider({ children }: { children: ReactNode }) {
     state.currentCycleId,
   ]);
 
+  // Effect to report the timer to the backend
+  useEffect(() => {
+    if (!isTimerWindow) {
+      return;
+    }
+    emit(TIMER_STATE_EVENT, {
+      mode: state.mode,
+      timeLeft: state.timeLeft,
+      isRunning: state.isRunning,
+      isPaused: state.isPaused,
+    });
+  }, [state.mode, state.timeLeft, state.isRunning, state.isPaused]);
+
+  // Commands arrive at any time, so they read the latest state from a ref
+  const stateRef = useRef(state);
+  stateRef.current = state;
+
+  // Effect to keep the selected preset, or the default one, up to date
+  useEffect(() => {
+    const selectedId = stateRef.current.preset?.id;
+    const preset =
+      presets?.find((preset) => preset.id === selectedId) ??
+      presets?.find((preset) => preset.is_default);
+    if (preset) {
+      dispatch({
+        type: "SELECT_PRESET",
+        preset: { id: preset.id, settings: presetSettings(preset) },
+      });
+    }
+  }, [presets]);
+
+  // Effect to follow commands from the backend
+  useEffect(() => {
+    if (!isTimerWindow) {
+      return;
+    }
+
+    async function skip() {
+      const { currentSessionId, currentCycleId, mode, settings } =
+        stateRef.current;
+      try {
+        if (currentSessionId) {
+          await completeSession({
+            sessionId: currentSessionId,
+            wasCompleted: false,
+          });
+          dispatch({ type: "SET_SESSION_ID", sessionId: undefined });
+        }
+
+        // Skipping the long break ends the cycle
+        if (mode === PomodoroMode.LONG_BREAK) {
+          if (currentCycleId) {
+            await updateCycleStatus({ id: currentCycleId, status: "COMPLETED" });
+          }
+          dispatch({
+            type: "STOP_AND_RESET",
+            payload: {
+              cycleId: undefined,
+              sessionId: undefined,
+              isRunning: false,
+              isPaused: false,
+              mode: PomodoroMode.FOCUS,
+              timeLeft: settings.focusDuration,
+            },
+          });
+        } else {
+          dispatch({ type: "SESSION_COMPLETE" });
+        }
+      } catch (error) {
+        console.error("Error skipping session:", error);
+        dispatch({ type: "STOP" });
+        toast.error("Error skipping session. Timer stopped.");
+      }
+    }
+
+    const unlisten = listen<TimerCommand>(TIMER_COMMAND_EVENT, (event) => {
+      switch (event.payload) {
+        case "START":
+          if (!stateRef.current.isRunning) {
+            dispatch({ type: "START" });
+          }
+          break;
+        case "PAUSE":
+          dispatch({ type: "PAUSE" });
+          break;
+        case "RESUME":
+          dispatch({ type: "RESUME" });
+          break;
+        case "SKIP":
+          if (stateRef.current.isRunning) {
+            skip();
+          }
+          break;
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Effect to start the next phase from a notification, where the platform
+  // shows actions
+  useEffect(() => {
+    if (!isTimerWindow) {
+      return;
+    }
+
+    const listener = addPluginListener<{ actionId: string }>(
+      "notification",
+      "actionPerformed",
+      ({ actionId }) => {
+        if (NOTIFICATION_ACTIONS.includes(actionId)) {
+          emit<TimerCommand>(TIMER_COMMAND_EVENT, "START");
+        }
+      }
+    ).catch(() => undefined);
+
+    return () => {
+      listener.then((listener) => listener?.unregister());
+    };
+  }, []);
+
+  // Effect to ask whether time spent away counts as focus
+  useEffect(() => {
+    if (!isTimerWindow) {
+      return;
+    }
+
+    async function resolve(id: string, resolution: InterruptionResolution) {
+      try {
+        await taurpc.resolve_interruption({ id, resolution });
+      } catch (error) {
+        console.error("Failed to resolve interruption:", error);
+        toast.error("Failed to save your choice about the idle time.");
+      }
+      if (stateRef.current.isRunning && stateRef.current.isPaused) {
+        dispatch({ type: "RESUME" });
+      }
+    }
+
+    function ask(interruption: Interruption) {
+      const minutes = Math.max(1, Math.round(interruption.countedMs / 60000));
+      toast("Focus session paused while you were away", {
+        id: interruption.id,
+        description: `Keep the ${minutes} min the timer ran without you?`,
+        duration: Infinity,
+        action: {
+          label: "Keep",
+          onClick: () => resolve(interruption.id, "KEEP"),
+        },
+        cancel: {
+          label: "Discard",
+          onClick: () => resolve(interruption.id, "DISCARD"),
+        },
+      });
+    }
+
+    // Ask about the ones left open when the app was closed
+    taurpc
+      .get_unresolved_interruptions()
+      .then((interruptions) => interruptions.forEach(ask))
+      .catch((error) =>
+        console.error("Failed to load idle interruptions:", error)
+      );
+
+    const unlisten = listen<Interruption>(IDLE_RETURNED_EVENT, (event) =>
+      ask(event.payload)
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Effect to start new cycle when needed
   useEffect(() => {
     async function startCycle() {
       if (state.isRunning && !state.currentCycleId) {
         try {
-          console.log("Starting new cycle with settings:", {
-            focusDuration: state.settings.focusDuration,
-            shortBreakDuration: state.settings.shortBreakDuration,
-            longBreakDuration: state.settings.longBreakDuration,
-            sessionsUntilLongBreak: state.settings.sessionsUntilLongBreak,
-            autoStartBreaks: state.settings.autoStartBreaks,
-            autoStartPomodoros: state.settings.autoStartPomodoros,
-          });
+          // Without a preset every value is sent, so the cycle matches
+          // what the timer shows
+          const presetId = state.preset?.id ?? null;
+          const overrides = state.preset
+            ? overridesOf(state.preset.settings, state.settings)
+            : state.settings;
 
-          const cycle = await startPomodoroCycle({
-            focusDuration: state.settings.focusDuration,
-            shortBreakDuration: state.settings.shortBreakDuration,
-            longBreakDuration: state.settings.longBreakDuration,
-            sessionsUntilLongBreak: state.settings.sessionsUntilLongBreak,
-            autoStartBreaks: state.settings.autoStartBreaks,
-            autoStartPomodoros: state.settings.autoStartPomodoros,
-          });
+          console.log("Starting new cycle with:", { presetId, overrides });
+
+          const cycle = await startPomodoroCycle({ presetId, overrides });
 
           console.log("Cycle created:", cycle);
 
@@ -321,7 +559,7 @@ export function PomodoroProvider({ children }: { children: ReactNode }) {
       }
     }
     startCycle();
-  }, [state.isRunning, state.currentCycleId, state.settings]);
+  }, [state.isRunning, state.currentCycleId, state.settings, state.preset]);
 
   // Effect to start new session when needed
   useEffect(() => {
@@ -346,13 +584,13 @@ export function PomodoroProvider({ children }: { children: ReactNode }) {
             ? "SHORT_BREAK"
             : "LONG_BREAK";
 
-      const durationMinutes = Math.floor(state.timeLeft / (60 * 1000));
+      const durationMs = state.timeLeft;
 
       try {
         console.log("Starting new session with:", {
           cycleId: state.currentCycleId,
           sessionType,
-          durationMinutes,
+          durationMs,
           completedSessions: state.completedSessions,
           sessionsUntilLongBreak: state.settings.sessionsUntilLongBreak,
         });
@@ -360,7 +598,7 @@ export function PomodoroProvider({ children }: { children: ReactNode }) {
         const session = await startSession({
           cycleId: state.currentCycleId,
           sessionType,
-          durationMinutes,
+          durationMs,
         });
 
         console.log("Session created:", session);
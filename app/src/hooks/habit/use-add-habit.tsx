@@ -1,5 +1,5 @@
 import { CreateHabitArgs } from "@/bindings";
-import { useMutation, useQueryClient } from "@tanstack/react-query";
+import { useMutation } from "@tanstack/react-query";
 import { taurpc } from "@/lib/taurpc";
 import { AlertTriangle, CheckCircle } from "lucide-react";
 import { toast } from "sonner";
@@ -7,13 +7,11 @@ import { toast } from "sonner";
 export const habitsQueryKey = ["habits"];
 
 export function useCreateHabit() {
-  const queryClient = useQueryClient();
   const mutation = useMutation({
     mutationFn: async (createHabit: CreateHabitArgs) => {
       return await taurpc.create_habit(createHabit);
     },
     onSuccess: () => {
-      queryClient.invalidateQueries({ queryKey: habitsQueryKey });
       toast.success("Habit succesfully added!", {
         icon: <CheckCircle size={16} />,
       });
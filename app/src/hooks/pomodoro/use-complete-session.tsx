@@ -2,19 +2,13 @@ import { AlertTriangle } from "lucide-react";
 import { useEffect } from "react";
 import { toast } from "sonner";
 import { taurpc } from "@/lib/taurpc";
-import { useMutation, useQueryClient } from "@tanstack/react-query";
-import { pomodoroQueryKey } from "./use-get-current-cycle";
+import { useMutation } from "@tanstack/react-query";
 
 export function useCompleteSession() {
-  const queryClient = useQueryClient();
-
   const mutation = useMutation({
     mutationFn: async (args: { sessionId: string; wasCompleted: boolean }) => {
       return await taurpc.complete_session(args);
     },
-    onSuccess: () => {
-      queryClient.invalidateQueries({ queryKey: pomodoroQueryKey });
-    },
   });
 
   useEffect(() => {
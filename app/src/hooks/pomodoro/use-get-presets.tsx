@@ -0,0 +1,31 @@
+import { AlertTriangle } from "lucide-react";
+import { useEffect } from "react";
+import { toast } from "sonner";
+import { taurpc } from "@/lib/taurpc";
+import { useQuery } from "@tanstack/react-query";
+
+export const presetsQueryKey = ["pomodoro", "presets"] as const;
+
+export function useGetPresets() {
+  const query = useQuery({
+    queryKey: presetsQueryKey,
+    queryFn: async () => {
+      return await taurpc.get_presets();
+    },
+  });
+
+  useEffect(() => {
+    if (query.isError) {
+      toast.warning("Failed to fetch pomodoro presets.", {
+        icon: <AlertTriangle size={16} />,
+        className: "!text-red-600",
+        action: {
+          label: "Try again",
+          onClick: () => query.refetch(),
+        },
+      });
+    }
+  }, [query.isError]);
+
+  return query;
+}
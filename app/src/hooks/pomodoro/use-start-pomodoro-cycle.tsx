@@ -3,18 +3,28 @@ import { useEffect } from "react";
 import { toast } from "sonner";
 import { taurpc } from "@/lib/taurpc";
 import { useMutation } from "@tanstack/react-query";
+import type { PomodoroSettings } from "@/contexts/PomodoroContext";
 
 export function useStartPomodoroCycle() {
   const mutation = useMutation({
-    mutationFn: async (args: {
-      focusDuration: number;
-      shortBreakDuration: number;
-      longBreakDuration: number;
-      sessionsUntilLongBreak: number;
-      autoStartBreaks: boolean;
-      autoStartPomodoros: boolean;
+    // Values left out of the overrides are taken from the preset, or from the
+    // default preset without one
+    mutationFn: async ({
+      presetId,
+      overrides,
+    }: {
+      presetId: string | null;
+      overrides: Partial<PomodoroSettings>;
     }) => {
-      return await taurpc.start_pomodoro_cycle(args);
+      return await taurpc.start_pomodoro_cycle({
+        presetId,
+        focusDuration: overrides.focusDuration ?? null,
+        shortBreakDuration: overrides.shortBreakDuration ?? null,
+        longBreakDuration: overrides.longBreakDuration ?? null,
+        sessionsUntilLongBreak: overrides.sessionsUntilLongBreak ?? null,
+        autoStartBreaks: overrides.autoStartBreaks ?? null,
+        autoStartPomodoros: overrides.autoStartPomodoros ?? null,
+      });
     },
   });
 
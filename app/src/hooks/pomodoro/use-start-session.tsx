@@ -2,23 +2,17 @@ import { AlertTriangle } from "lucide-react";
 import { useEffect } from "react";
 import { toast } from "sonner";
 import { taurpc } from "@/lib/taurpc";
-import { useMutation, useQueryClient } from "@tanstack/react-query";
-import { pomodoroQueryKey } from "./use-get-current-cycle";
+import { useMutation } from "@tanstack/react-query";
 
 export function useStartSession() {
-  const queryClient = useQueryClient();
-
   const mutation = useMutation({
     mutationFn: async (args: {
       cycleId: string;
       sessionType: "FOCUS" | "SHORT_BREAK" | "LONG_BREAK";
-      durationMinutes: number;
+      durationMs: number;
     }) => {
       return await taurpc.start_session(args);
     },
-    onSuccess: () => {
-      queryClient.invalidateQueries({ queryKey: pomodoroQueryKey });
-    },
   });
 
   useEffect(() => {
@@ -2,12 +2,9 @@ import { AlertTriangle } from "lucide-react";
 import { useEffect } from "react";
 import { toast } from "sonner";
 import { taurpc } from "@/lib/taurpc";
-import { useMutation, useQueryClient } from "@tanstack/react-query";
-import { pomodoroQueryKey } from "./use-get-current-cycle";
+import { useMutation } from "@tanstack/react-query";
 
 export function useUpdateCycleStatus() {
-  const queryClient = useQueryClient();
-
   const mutation = useMutation({
     mutationFn: async (args: {
       id: string;
@@ -15,9 +12,6 @@ export function useUpdateCycleStatus() {
     }) => {
       return await taurpc.update_cycle_status(args);
     },
-    onSuccess: () => {
-      queryClient.invalidateQueries({ queryKey: pomodoroQueryKey });
-    },
   });
 
   useEffect(() => {
@@ -0,0 +1,59 @@
+import { useEffect } from "react";
+import { useQueryClient } from "@tanstack/react-query";
+import { taurpc } from "@/lib/taurpc";
+import { habitsQueryKey } from "@/hooks/habit/use-add-habit";
+import { habitCompletionsQueryKey } from "@/hooks/habit/use-get-habit-completions";
+import { habitCompletionStreakQueryKey } from "@/hooks/habit/use-get-habit-completion-streak";
+import { pomodoroQueryKey } from "@/hooks/pomodoro/use-get-current-cycle";
+import { presetsQueryKey } from "@/hooks/pomodoro/use-get-presets";
+
+// The backend pushes every data change, whether it came from this window, the
+// tray, the CLI or a background task, so queries are refreshed here instead
+// of after each mutation
+export function useDataChanges() {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    const unlisten = taurpc.data_changed.on((change) => {
+      switch (change.type) {
+        case "habit.created":
+        case "habit.updated":
+          queryClient.invalidateQueries({ queryKey: habitsQueryKey });
+          break;
+        case "habit.deleted":
+          queryClient.invalidateQueries({ queryKey: habitsQueryKey });
+          queryClient.removeQueries({
+            queryKey: habitCompletionsQueryKey(change.habitId),
+          });
+          queryClient.removeQueries({
+            queryKey: habitCompletionStreakQueryKey(change.habitId),
+          });
+          break;
+        case "completion.toggled":
+          queryClient.invalidateQueries({
+            queryKey: habitCompletionsQueryKey(change.completion.habitId),
+          });
+          queryClient.invalidateQueries({
+            queryKey: habitCompletionStreakQueryKey(change.completion.habitId),
+          });
+          break;
+        case "cycle.started":
+        case "cycle.updated":
+        case "session.started":
+        case "session.completed":
+          queryClient.invalidateQueries({ queryKey: pomodoroQueryKey });
+          break;
+        case "preset.changed":
+          queryClient.invalidateQueries({ queryKey: presetsQueryKey });
+          break;
+        case "data.reloaded":
+          queryClient.invalidateQueries();
+          break;
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [queryClient]);
+}
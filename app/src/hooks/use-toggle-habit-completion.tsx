@@ -1,14 +1,10 @@
 import { AlertTriangle, CheckCircle } from "lucide-react";
 import { toast } from "sonner";
-import { useMutation, useQueryClient } from "@tanstack/react-query";
+import { useMutation } from "@tanstack/react-query";
 import { taurpc } from "@/lib/taurpc";
-import { habitCompletionsQueryKey } from "@/hooks/habit/use-get-habit-completions";
-import { habitCompletionStreakQueryKey } from "@/hooks/habit/use-get-habit-completion-streak";
 import { CreateHabitCompletionArgs } from "@/bindings";
 
 export function useToggleHabitCompletion() {
-  const queryClient = useQueryClient();
-
   const mutation = useMutation({
     mutationFn: async (habitCompletion: CreateHabitCompletionArgs) => {
       return await taurpc.toggle_habit_completion({
@@ -17,13 +13,7 @@ export function useToggleHabitCompletion() {
         createdAt: habitCompletion.createdAt,
       });
     },
-    onSuccess: async (_, habitCompletion) => {
-      await queryClient.invalidateQueries({
-        queryKey: habitCompletionsQueryKey(habitCompletion.habitId),
-      });
-      await queryClient.invalidateQueries({
-        queryKey: habitCompletionStreakQueryKey(habitCompletion.habitId),
-      });
+    onSuccess: () => {
       toast.success("Habit succesfully updated!", {
         icon: <CheckCircle size={16} />,
       });
@@ -11,11 +11,18 @@
 // Import Routes
 
 import { Route as rootRoute } from './routes/__root'
+import { Route as QuickLogImport } from './routes/quick-log'
 import { Route as PomodoroImport } from './routes/pomodoro'
 import { Route as IndexImport } from './routes/index'
 
 // Create/Update Routes
 
+const QuickLogRoute = QuickLogImport.update({
+  id: '/quick-log',
+  path: '/quick-log',
+  getParentRoute: () => rootRoute,
+} as any)
+
 const PomodoroRoute = PomodoroImport.update({
   id: '/pomodoro',
   path: '/pomodoro',
@@ -46,6 +53,13 @@ declare module '@tanstack/react-router' {
       preLoaderRoute: typeof PomodoroImport
       parentRoute: typeof rootRoute
     }
+    '/quick-log': {
+      id: '/quick-log'
+      path: '/quick-log'
+      fullPath: '/quick-log'
+      preLoaderRoute: typeof QuickLogImport
+      parentRoute: typeof rootRoute
+    }
   }
 }
 
@@ -54,36 +68,41 @@ declare module '@tanstack/react-router' {
 export interface FileRoutesByFullPath {
   '/': typeof IndexRoute
   '/pomodoro': typeof PomodoroRoute
+  '/quick-log': typeof QuickLogRoute
 }
 
 export interface FileRoutesByTo {
   '/': typeof IndexRoute
   '/pomodoro': typeof PomodoroRoute
+  '/quick-log': typeof QuickLogRoute
 }
 
 export interface FileRoutesById {
   __root__: typeof rootRoute
   '/': typeof IndexRoute
   '/pomodoro': typeof PomodoroRoute
+  '/quick-log': typeof QuickLogRoute
 }
 
 export interface FileRouteTypes {
   fileRoutesByFullPath: FileRoutesByFullPath
-  fullPaths: '/' | '/pomodoro'
+  fullPaths: '/' | '/pomodoro' | '/quick-log'
   fileRoutesByTo: FileRoutesByTo
-  to: '/' | '/pomodoro'
-  id: '__root__' | '/' | '/pomodoro'
+  to: '/' | '/pomodoro' | '/quick-log'
+  id: '__root__' | '/' | '/pomodoro' | '/quick-log'
   fileRoutesById: FileRoutesById
 }
 
 export interface RootRouteChildren {
   IndexRoute: typeof IndexRoute
   PomodoroRoute: typeof PomodoroRoute
+  QuickLogRoute: typeof QuickLogRoute
 }
 
 const rootRouteChildren: RootRouteChildren = {
   IndexRoute: IndexRoute,
   PomodoroRoute: PomodoroRoute,
+  QuickLogRoute: QuickLogRoute,
 }
 
 export const routeTree = rootRoute
@@ -97,7 +116,8 @@ export const routeTree = rootRoute
       "filePath": "__root.tsx",
       "children": [
         "/",
-        "/pomodoro"
+        "/pomodoro",
+        "/quick-log"
       ]
     },
     "/": {
@@ -105,6 +125,9 @@ export const routeTree = rootRoute
     },
     "/pomodoro": {
       "filePath": "pomodoro.tsx"
+    },
+    "/quick-log": {
+      "filePath": "quick-log.tsx"
     }
   }
 }
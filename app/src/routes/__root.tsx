@@ -1,6 +1,7 @@
 import { DesktopLayout } from "@/components/layouts/desktop-layout";
-import { createRootRoute, Outlet } from "@tanstack/react-router";
+import { createRootRoute, Outlet, useRouterState } from "@tanstack/react-router";
 import { Toaster } from "@/components/ui/sonner";
+import { useDataChanges } from "@/hooks/use-data-changes";
 
 export const Route = createRootRoute({
   component: () => (
@@ -12,6 +13,14 @@ export const Route = createRootRoute({
 });
 
 const Root = () => {
+  useDataChanges();
+  const pathname = useRouterState({ select: (state) => state.location.pathname });
+
+  // The quick log window is too small for the sidebar
+  if (pathname === "/quick-log") {
+    return <Outlet />;
+  }
+
   return (
     <DesktopLayout>
       <Outlet />
@@ -0,0 +1,55 @@
+import { createFileRoute } from "@tanstack/react-router";
+import { useEffect } from "react";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import { LucideIcon } from "@/components/lucide-icon";
+import { Skeleton } from "@/components/ui/skeleton";
+import { ToggleHabitCompletionButton } from "@/components/habit/toggle-habit-completion-button";
+import { useGetActiveHabits } from "@/hooks/habit/use-get-active-habits";
+
+// Opened by the log habit shortcut, in its own small window
+export const Route = createFileRoute("/quick-log")({
+  component: QuickLogComponent,
+});
+
+function QuickLogComponent() {
+  const { data: habits, isLoading } = useGetActiveHabits();
+
+  useEffect(() => {
+    const onKeyDown = (event: KeyboardEvent) => {
+      if (event.key === "Escape") {
+        getCurrentWindow().close();
+      }
+    };
+    window.addEventListener("keydown", onKeyDown);
+    return () => window.removeEventListener("keydown", onKeyDown);
+  }, []);
+
+  return (
+    <div className="h-screen flex flex-col gap-3 p-4 overflow-y-auto">
+      <h3 className="text-xl font-semibold tracking-tight">Log habit</h3>
+      {isLoading &&
+        Array.from({ length: 3 }).map((_, index) => (
+          <Skeleton key={index} className="h-12 w-full" />
+        ))}
+      {habits?.length === 0 && (
+        <p className="text-sm text-muted-foreground">No active habits yet.</p>
+      )}
+      {habits?.map((habit) => (
+        <div
+          key={habit.id}
+          className="flex items-center justify-between space-x-3"
+        >
+          <div className="flex items-center space-x-3 min-w-0">
+            <LucideIcon
+              name={habit.icon as any}
+              size={20}
+              className="min-w-[20px]"
+            />
+            <span className="truncate">{habit.title}</span>
+          </div>
+          <ToggleHabitCompletionButton habitId={habit.id} />
+        </div>
+      ))}
+    </div>
+  );
+}